@@ -0,0 +1,403 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use lib_utils::arg::Arg;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use typeshare::typeshare;
+
+use crate::{
+    converter::{ArgsType, AudioCodec, EncoderFamily, FfmpegOptions, HwAccel, VideoCodec},
+    exec::{encoder_version, Executor, FfmpegExecutor},
+    Error, Result,
+};
+
+/// What an ffmpeg build can do. Builds list hardware encoders and acceleration methods whether
+/// or not the machine has the hardware, those are only usable once they opened their device.
+#[typeshare]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    /// Version banner, e.g. `ffmpeg version 7.1 Copyright ...`
+    pub version: Option<String>,
+    pub encoders: Vec<String>,
+    pub decoders: Vec<String>,
+    pub hwaccels: Vec<String>,
+    pub filters: Vec<String>,
+    pub pixel_formats: Vec<String>,
+    /// Hardware encoders of [`VideoCodec`] that encoded a test frame
+    pub hardware_encoders: Vec<String>,
+    /// Acceleration methods of [`HwAccel`] whose device could be created
+    pub hardware_devices: Vec<String>,
+}
+
+impl Capabilities {
+    /// Capabilities of the ffmpeg at `executable`, `ffmpeg` from `PATH` when `None`. Only the
+    /// first call for an executable runs ffmpeg, later ones share its result.
+    pub async fn detect(executable: Option<&'static PathBuf>) -> Result<Arc<Capabilities>> {
+        static DETECTED: OnceLock<Mutex<HashMap<Option<PathBuf>, Arc<Capabilities>>>> =
+            OnceLock::new();
+        let detected = DETECTED.get_or_init(Default::default);
+        let key = executable.cloned();
+
+        if let Some(capabilities) = detected.lock().unwrap().get(&key) {
+            return Ok(capabilities.clone());
+        }
+
+        let capabilities = Arc::new(Self::query(&FfmpegExecutor::new(executable)).await?);
+        detected.lock().unwrap().insert(key, capabilities.clone());
+
+        Ok(capabilities)
+    }
+
+    /// Ask the transcoder behind `executor`, without caching.
+    pub async fn query(executor: &dyn Executor) -> Result<Capabilities> {
+        let mut capabilities = Capabilities {
+            version: encoder_version(executor).await,
+            encoders: parse_codecs(&listing(executor, "encoders").await?),
+            decoders: parse_codecs(&listing(executor, "decoders").await?),
+            hwaccels: parse_hwaccels(&listing(executor, "hwaccels").await?),
+            filters: parse_filters(&listing(executor, "filters").await?),
+            pixel_formats: parse_pixel_formats(&listing(executor, "pix_fmts").await?),
+            ..Default::default()
+        };
+
+        for codec in VideoCodec::ALL {
+            let encoder = codec.to_string();
+            if codec.family() != EncoderFamily::Software
+                && capabilities.has_encoder(&encoder)
+                && succeeds(executor, encoder_test_args(&encoder)).await
+            {
+                capabilities.hardware_encoders.push(encoder);
+            }
+        }
+        for hwaccel in HwAccel::ALL {
+            let device = hwaccel.to_string();
+            if capabilities.hwaccels.contains(&device)
+                && succeeds(executor, device_test_args(&device)).await
+            {
+                capabilities.hardware_devices.push(device);
+            }
+        }
+
+        Ok(capabilities)
+    }
+
+    pub fn has_encoder(&self, name: &str) -> bool {
+        self.encoders.iter().any(|encoder| encoder == name)
+    }
+
+    pub fn has_filter(&self, name: &str) -> bool {
+        self.filters.iter().any(|filter| filter == name)
+    }
+
+    pub fn has_pixel_format(&self, name: &str) -> bool {
+        self.pixel_formats.iter().any(|format| format == name)
+    }
+
+    pub fn supports_video_codec(&self, codec: VideoCodec) -> bool {
+        let encoder = codec.to_string();
+        match codec.family() {
+            EncoderFamily::Software => self.has_encoder(&encoder),
+            _ => self.hardware_encoders.contains(&encoder),
+        }
+    }
+
+    pub fn supports_audio_codec(&self, codec: AudioCodec) -> bool {
+        self.has_encoder(&codec.to_string())
+    }
+
+    pub fn supports_hwaccel(&self, hwaccel: HwAccel) -> bool {
+        self.hardware_devices.contains(&hwaccel.to_string())
+    }
+
+    pub fn video_codecs(&self) -> Vec<VideoCodec> {
+        VideoCodec::ALL
+            .into_iter()
+            .filter(|codec| self.supports_video_codec(*codec))
+            .collect()
+    }
+
+    pub fn audio_codecs(&self) -> Vec<AudioCodec> {
+        AudioCodec::ALL
+            .into_iter()
+            .filter(|codec| self.supports_audio_codec(*codec))
+            .collect()
+    }
+
+    pub fn hwaccel_methods(&self) -> Vec<HwAccel> {
+        HwAccel::ALL
+            .into_iter()
+            .filter(|hwaccel| self.supports_hwaccel(*hwaccel))
+            .collect()
+    }
+
+    /// Why `options` can't run on this build, empty when they can. Fallback encoders are
+    /// checked too, a conversion would reach them once the first encoder fails.
+    pub fn unsupported(&self, options: &FfmpegOptions) -> Vec<String> {
+        let mut reasons = Vec::new();
+
+        if let Some(export) = options.audio_export() {
+            let codec = export.encoder();
+            if !self.supports_audio_codec(codec) {
+                reasons.push(format!("audio encoder {codec} is missing"));
+            }
+        } else {
+            let codecs = options
+                .video_codec
+                .to_option()
+                .into_iter()
+                .chain(options.retry.fallback.iter().copied());
+            for codec in codecs {
+                if !self.supports_video_codec(codec) {
+                    reasons.push(format!("video encoder {codec} is not usable"));
+                }
+            }
+
+            if let Some(hwaccel) = options.hwaccel {
+                if !self.supports_hwaccel(hwaccel) {
+                    reasons.push(format!("hardware acceleration {hwaccel} is not usable"));
+                }
+            }
+            if let ArgsType::Custom(codec) = options.audio_codec {
+                if !self.supports_audio_codec(codec) {
+                    reasons.push(format!("audio encoder {codec} is missing"));
+                }
+            }
+            // ProRes profiles need prores_ks, not the plain prores encoder
+            if let Some(profile) = options.video_profile() {
+                let encoder = profile.encoder();
+                if encoder != profile.codec().to_string() && !self.has_encoder(encoder) {
+                    reasons.push(format!("video encoder {encoder} is missing"));
+                }
+            }
+            if let Some(format) = options.pixel_format() {
+                if !self.has_pixel_format(&format.to_string()) {
+                    reasons.push(format!("pixel format {format} is missing"));
+                }
+            }
+        }
+
+        if options.loudness_normalization().is_some() && !self.has_filter("loudnorm") {
+            reasons.push("filter loudnorm is missing".to_string());
+        }
+
+        reasons
+    }
+}
+
+/// Standard output of `ffmpeg -<flag>`.
+async fn listing(executor: &dyn Executor, flag: &str) -> Result<String> {
+    let mut args = Arg::new("hide_banner").build();
+    args.extend(Arg::new(flag).build());
+
+    let mut child = executor.spawn(args)?;
+    let mut listing = String::new();
+    if let Some(stdout) = child.take_stdout() {
+        BufReader::new(stdout)
+            .read_to_string(&mut listing)
+            .await
+            .map_err(|err| Error::FfmpegError(format!("Failed to read ffmpeg -{flag}: {err}")))?;
+    }
+
+    match child.wait().await {
+        Ok(Some(0)) => Ok(listing),
+        _ => Err(Error::FfmpegError(format!("ffmpeg -{flag} failed"))),
+    }
+}
+
+/// Whether ffmpeg exits cleanly with `args`.
+async fn succeeds(executor: &dyn Executor, args: Vec<String>) -> bool {
+    let Ok(mut child) = executor.spawn(args) else {
+        return false;
+    };
+
+    // Drain the log so the process doesn't block on a full pipe
+    if let Some(stderr) = child.take_stderr() {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(_)) = lines.next_line().await {}
+    }
+
+    matches!(child.wait().await, Ok(Some(0)))
+}
+
+/// A single generated frame through `encoder`, nothing written.
+fn encoder_test_args(encoder: &str) -> Vec<String> {
+    let mut args = Arg::new("hide_banner").build();
+    args.extend(Arg::new("v").value("error").build());
+    args.extend(Arg::new("f").value("lavfi").build());
+    // Hardware encoders refuse frames that are too small
+    args.extend(Arg::new("i").value("color=size=256x256:rate=1").build());
+    args.extend(Arg::new("frames:v").value("1").build());
+    args.extend(Arg::new("c:v").value(encoder).build());
+    args.extend(Arg::new("f").value("null").build());
+    args.push("-".to_string());
+    args
+}
+
+/// Create a `device` and pass a generated frame through ffmpeg, nothing written.
+fn device_test_args(device: &str) -> Vec<String> {
+    let mut args = Arg::new("hide_banner").build();
+    args.extend(Arg::new("v").value("error").build());
+    args.extend(Arg::new("init_hw_device").value(device).build());
+    args.extend(Arg::new("f").value("lavfi").build());
+    args.extend(Arg::new("i").value("color=size=64x64:rate=1").build());
+    args.extend(Arg::new("frames:v").value("1").build());
+    args.extend(Arg::new("f").value("null").build());
+    args.push("-".to_string());
+    args
+}
+
+/// Names of `-encoders` and `-decoders`, listed under a dashed line after their flags.
+fn parse_codecs(listing: &str) -> Vec<String> {
+    names_after_separator(listing)
+}
+
+/// `-pix_fmts` has the same layout as the codecs.
+fn parse_pixel_formats(listing: &str) -> Vec<String> {
+    names_after_separator(listing)
+}
+
+fn names_after_separator(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| line.split_whitespace().nth(1))
+        .map(str::to_string)
+        .collect()
+}
+
+/// One method per line under the `Hardware acceleration methods:` header.
+fn parse_hwaccels(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .skip_while(|line| !line.ends_with(':'))
+        .skip(1)
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Filters are the lines with their pads, e.g. `TSC acrossfade AA->A ...`. The legend above
+/// has none.
+fn parse_filters(listing: &str) -> Vec<String> {
+    listing
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let (_, name, pads) = (columns.next()?, columns.next()?, columns.next()?);
+            pads.contains("->").then(|| name.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_codecs, parse_filters, parse_hwaccels, parse_pixel_formats, Capabilities};
+    use crate::types::{
+        ArgsType, AudioCodec, FfmpegOptions, HwAccel, LoudnessTarget, OutputExtension, RetryPolicy,
+        VideoCodec,
+    };
+
+    const ENCODERS: &str = "Encoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ V....D h264_nvenc           NVIDIA NVENC H.264 encoder (codec h264)
+ V..... prores               Apple ProRes (codec prores)
+ A....D flac                 FLAC (Free Lossless Audio Codec)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+
+    #[test]
+    fn parses_the_listings() {
+        assert_eq!(
+            parse_codecs(ENCODERS),
+            ["libx264", "h264_nvenc", "prores", "flac", "aac"]
+        );
+
+        let hwaccels = "Hardware acceleration methods:\nvdpau\ncuda\nvaapi\n\n";
+        assert_eq!(parse_hwaccels(hwaccels), ["vdpau", "cuda", "vaapi"]);
+
+        let filters = "Filters:
+  T.. = Timeline support
+  A = Audio input/output
+  | = Source or sink filter
+ ... aformat           A->A       Convert the input audio to one of the specified formats.
+ T.C loudnorm          A->A       EBU R128 loudness normalization
+ ..C scale             V->V       Scale the input video size and/or convert the image format.
+";
+        assert_eq!(parse_filters(filters), ["aformat", "loudnorm", "scale"]);
+
+        let pixel_formats = "Pixel formats:
+I.... = Supported Input  format for conversion
+FLAGS NAME            NB_COMPONENTS BITS_PER_PIXEL BIT_DEPTHS
+-----
+IO... yuv420p                3             12      8-8-8
+IO... yuv422p10le            3             20      10-10-10
+";
+        assert_eq!(
+            parse_pixel_formats(pixel_formats),
+            ["yuv420p", "yuv422p10le"]
+        );
+    }
+
+    #[test]
+    fn listed_hardware_needs_its_device() {
+        let capabilities = Capabilities {
+            encoders: parse_codecs(ENCODERS),
+            hwaccels: vec!["cuda".into()],
+            filters: vec!["scale".into()],
+            // No NVIDIA card, NVENC is listed but never opened
+            ..Default::default()
+        };
+
+        assert_eq!(
+            capabilities.video_codecs(),
+            [VideoCodec::H264, VideoCodec::Prores]
+        );
+        assert_eq!(
+            capabilities.audio_codecs(),
+            [AudioCodec::Flac, AudioCodec::Aac]
+        );
+        assert!(capabilities.hwaccel_methods().is_empty());
+
+        let options = FfmpegOptions::new(
+            ArgsType::MatchSource,
+            Some(HwAccel::Cuda),
+            ArgsType::Custom(AudioCodec::Flac),
+            ArgsType::Custom(VideoCodec::H264NVENC),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            OutputExtension::Mkv,
+        )
+        .with_retry(RetryPolicy {
+            fallback: vec![VideoCodec::H264],
+            ..RetryPolicy::default()
+        })
+        .with_loudness(LoudnessTarget::default());
+        assert_eq!(
+            capabilities.unsupported(&options),
+            [
+                "video encoder h264_nvenc is not usable",
+                "hardware acceleration cuda is not usable",
+                "filter loudnorm is missing"
+            ]
+        );
+
+        let software = FfmpegOptions {
+            hwaccel: None,
+            video_codec: ArgsType::Custom(VideoCodec::Prores),
+            loudness: None,
+            ..options
+        };
+        assert!(capabilities.unsupported(&software).is_empty());
+    }
+}
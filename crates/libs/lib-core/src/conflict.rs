@@ -0,0 +1,108 @@
+use std::path::{Path, PathBuf};
+
+use crate::{converter::ConflictPolicy, Error, Result};
+
+/// How an existing output was handled, reported to the progress system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictDecision {
+    Skipped(PathBuf),
+    Overwritten(PathBuf),
+    Renamed { existing: PathBuf, written: PathBuf },
+}
+
+impl ConflictDecision {
+    /// Where the file should be written, `None` when it has to be skipped.
+    pub fn target(&self) -> Option<&Path> {
+        match self {
+            ConflictDecision::Skipped(_) => None,
+            ConflictDecision::Overwritten(target) => Some(target),
+            ConflictDecision::Renamed { written, .. } => Some(written),
+        }
+    }
+}
+
+/// Apply `policy` to `target`. Returns `None` when nothing is in the way.
+pub(crate) fn resolve_conflict(
+    policy: ConflictPolicy,
+    target: &Path,
+) -> Result<Option<ConflictDecision>> {
+    if !target.exists() {
+        return Ok(None);
+    }
+
+    let decision = match policy {
+        ConflictPolicy::Skip => ConflictDecision::Skipped(target.to_path_buf()),
+        ConflictPolicy::Overwrite => ConflictDecision::Overwritten(target.to_path_buf()),
+        ConflictPolicy::Rename => ConflictDecision::Renamed {
+            existing: target.to_path_buf(),
+            written: free_name(target),
+        },
+        ConflictPolicy::Fail => return Err(Error::OutputExists(target.display().to_string())),
+    };
+
+    Ok(Some(decision))
+}
+
+/// First `<stem>_<n>.<ext>` that doesn't exist yet.
+fn free_name(target: &Path) -> PathBuf {
+    let stem = target
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = target.extension().map(|e| e.to_string_lossy().to_string());
+
+    (1u32..)
+        .map(|n| {
+            let name = match &extension {
+                Some(extension) => format!("{stem}_{n}.{extension}"),
+                None => format!("{stem}_{n}"),
+            };
+            target.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use crate::{converter::ConflictPolicy, Error};
+
+    use super::{resolve_conflict, ConflictDecision};
+
+    #[test]
+    fn conflict_policies() {
+        let dir = std::env::temp_dir().join("reconv_conflict_policies");
+        fs::create_dir_all(&dir).unwrap();
+
+        let free = dir.join("free.mkv");
+        let taken = dir.join("taken.mkv");
+        fs::write(&taken, b"").unwrap();
+        fs::write(dir.join("taken_1.mkv"), b"").unwrap();
+
+        assert_eq!(resolve_conflict(ConflictPolicy::Fail, &free).unwrap(), None);
+
+        assert_eq!(
+            resolve_conflict(ConflictPolicy::Skip, &taken).unwrap(),
+            Some(ConflictDecision::Skipped(taken.clone()))
+        );
+        assert_eq!(
+            resolve_conflict(ConflictPolicy::Overwrite, &taken).unwrap(),
+            Some(ConflictDecision::Overwritten(taken.clone()))
+        );
+        assert_eq!(
+            resolve_conflict(ConflictPolicy::Rename, &taken).unwrap(),
+            Some(ConflictDecision::Renamed {
+                existing: taken.clone(),
+                written: dir.join("taken_2.mkv"),
+            })
+        );
+        assert!(matches!(
+            resolve_conflict(ConflictPolicy::Fail, &taken),
+            Err(Error::OutputExists(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
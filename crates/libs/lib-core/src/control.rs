@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Pauses and resumes a running conversion. Obtained from
+/// [`crate::types::Converter::control`], clones control the same conversion.
+///
+/// Pausing suspends the running transcoders (on Unix) and holds back the queued files.
+#[derive(Debug, Clone)]
+pub struct ConversionControl {
+    paused: Arc<watch::Sender<bool>>,
+}
+
+impl Default for ConversionControl {
+    fn default() -> Self {
+        Self {
+            paused: Arc::new(watch::channel(false).0),
+        }
+    }
+}
+
+impl ConversionControl {
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    /// Pause when running, resume when paused. Returns whether it is now paused.
+    pub fn toggle_pause(&self) -> bool {
+        let paused = !self.is_paused();
+        self.paused.send_replace(paused);
+        paused
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.paused.subscribe()
+    }
+}
@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use lib_utils::arg::Arg;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::AudioCodec;
+use crate::probe::MediaInfo;
+
+/// Container of an audio-only export.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AudioFormat {
+    Mp3,
+    /// AAC or ALAC in an MPEG-4 container
+    M4a,
+    /// Opus in an Ogg container
+    Opus,
+    Flac,
+    Wav,
+}
+
+impl AudioFormat {
+    /// Codecs the container can hold, the first one is the default.
+    pub fn codecs(&self) -> &'static [AudioCodec] {
+        match self {
+            AudioFormat::Mp3 => &[AudioCodec::Mp3],
+            AudioFormat::M4a => &[AudioCodec::Aac, AudioCodec::Alac],
+            AudioFormat::Opus => &[AudioCodec::Opus],
+            AudioFormat::Flac => &[AudioCodec::Flac],
+            AudioFormat::Wav => &[AudioCodec::Wav],
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        }
+    }
+}
+
+/// Export the audio of every video on its own, the video, subtitle and data streams are dropped.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioExport {
+    pub format: AudioFormat,
+    /// One of [`AudioFormat::codecs`], the format's default when `None` or not supported
+    #[serde(default)]
+    pub codec: Option<AudioCodec>,
+    /// In Hz, the source's when `None`
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
+    /// 1 for mono, 2 for stereo, the source's when `None`
+    #[serde(default)]
+    pub channels: Option<u8>,
+    /// Audio stream of the source to export, counted from 0 among the audio streams
+    #[serde(default)]
+    pub stream: u32,
+    /// Write a title and a recording date tag
+    #[serde(default)]
+    pub tags: bool,
+}
+
+impl AudioExport {
+    pub fn new(format: AudioFormat) -> Self {
+        Self {
+            format,
+            codec: None,
+            sample_rate: None,
+            channels: None,
+            stream: 0,
+            tags: true,
+        }
+    }
+
+    pub fn with_codec(mut self, codec: AudioCodec) -> Self {
+        self.codec = Some(codec);
+        self
+    }
+
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    pub fn with_channels(mut self, channels: u8) -> Self {
+        self.channels = Some(channels);
+        self
+    }
+
+    pub fn with_stream(mut self, stream: u32) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    /// The codec actually used, see [`AudioExport::codec`].
+    pub fn encoder(&self) -> AudioCodec {
+        let codecs = self.format.codecs();
+        self.codec
+            .filter(|codec| codecs.contains(codec))
+            .unwrap_or(codecs[0])
+    }
+
+    /// Everything between the input and the output, except the audio filters.
+    pub(crate) fn args(&self, bitrate: Option<u32>) -> Vec<String> {
+        let mut args = Arg::new("map")
+            .value(format!("0:a:{}", self.stream))
+            .build();
+        args.extend(Arg::new("vn").build());
+        args.extend(Arg::new("sn").build());
+        args.extend(Arg::new("dn").build());
+
+        let codec = self.encoder();
+        args.extend(Arg::new("c:a").value(codec.to_string()).build());
+
+        if let Some(bitrate) = bitrate.filter(|_| codec.is_lossy()) {
+            args.extend(Arg::new("b:a").value(bitrate.to_string() + "k").build());
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            args.extend(Arg::new("ar").value(sample_rate.to_string()).build());
+        }
+        if let Some(channels) = self.channels {
+            args.extend(Arg::new("ac").value(channels.to_string()).build());
+        }
+
+        // ID3v2.4 is still unreadable for a lot of players
+        if self.format == AudioFormat::Mp3 {
+            args.extend(Arg::new("id3v2_version").value("3").build());
+        }
+
+        args
+    }
+
+    /// Title from the file name and date from the camera's `creation_time`, empty without
+    /// `tags`.
+    pub(crate) fn tag_args(&self, source: &Path, media: Option<&MediaInfo>) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.tags {
+            return args;
+        }
+
+        if let Some(title) = source.file_stem() {
+            args.extend(
+                Arg::new("metadata")
+                    .value(format!("title={}", title.to_string_lossy()))
+                    .build(),
+            );
+        }
+
+        let date = media
+            .and_then(|media| media.creation_time.as_deref())
+            .and_then(|time| time.get(..10));
+        if let Some(date) = date {
+            args.extend(Arg::new("metadata").value(format!("date={date}")).build());
+        }
+
+        args
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{AudioExport, AudioFormat};
+    use crate::{probe::MediaInfo, types::AudioCodec};
+
+    #[test]
+    fn codec_matrix() {
+        let m4a = AudioExport::new(AudioFormat::M4a);
+        assert_eq!(m4a.encoder(), AudioCodec::Aac);
+        assert_eq!(m4a.with_codec(AudioCodec::Alac).encoder(), AudioCodec::Alac);
+        // Not something an m4a holds
+        assert_eq!(m4a.with_codec(AudioCodec::Opus).encoder(), AudioCodec::Aac);
+
+        assert_eq!(
+            AudioExport::new(AudioFormat::Wav).encoder().to_string(),
+            "pcm_s16le"
+        );
+    }
+
+    #[test]
+    fn drops_video_and_maps_one_stream() {
+        let export = AudioExport::new(AudioFormat::Mp3)
+            .with_stream(1)
+            .with_sample_rate(44100)
+            .with_channels(2);
+
+        assert_eq!(
+            export.args(Some(192)),
+            [
+                "-map",
+                "0:a:1",
+                "-vn",
+                "-sn",
+                "-dn",
+                "-c:a",
+                "libmp3lame",
+                "-b:a",
+                "192k",
+                "-ar",
+                "44100",
+                "-ac",
+                "2",
+                "-id3v2_version",
+                "3"
+            ]
+        );
+
+        // Lossless codecs have no bitrate
+        let flac = AudioExport::new(AudioFormat::Flac).args(Some(192));
+        assert!(!flac.contains(&"-b:a".to_string()));
+    }
+
+    #[test]
+    fn tags_from_name_and_creation_time() {
+        let media = MediaInfo {
+            format_name: "mov,mp4".to_string(),
+            duration: None,
+            size: None,
+            bit_rate: None,
+            creation_time: Some("2024-11-06T07:12:40.000000Z".to_string()),
+            video: Vec::new(),
+            audio: Vec::new(),
+            subtitles: Vec::new(),
+        };
+        let export = AudioExport::new(AudioFormat::Opus);
+
+        assert_eq!(
+            export.tag_args(Path::new("/in/C0001.MP4"), Some(&media)),
+            ["-metadata", "title=C0001", "-metadata", "date=2024-11-06"]
+        );
+
+        let untagged = AudioExport {
+            tags: false,
+            ..export
+        };
+        assert!(untagged
+            .tag_args(Path::new("/in/C0001.MP4"), Some(&media))
+            .is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::PictureFormat;
+
+/// How a scale treats a source whose aspect ratio differs from the target's.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScaleMode {
+    /// Exactly the target size, the picture is distorted
+    #[default]
+    Stretch,
+    /// Largest size inside the target, the output can be smaller than the target
+    Fit,
+    /// Fit inside the target then pad the rest with black bars
+    Letterbox,
+    /// Smallest size covering the target then crop the overflow
+    Fill,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Deinterlacer {
+    Yadif,
+    /// Better on motion than yadif, a bit slower
+    Bwdif,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Rotation {
+    Clockwise,
+    CounterClockwise,
+    UpsideDown,
+    FlipHorizontal,
+    FlipVertical,
+}
+
+/// One step of a video filter graph. Sizes are in pixels, offsets from the top left corner.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "filter", content = "settings", rename_all = "camelCase")]
+pub enum Filter {
+    Scale {
+        width: u32,
+        height: u32,
+        mode: ScaleMode,
+    },
+    /// Centered when no offset is given
+    Crop {
+        width: u32,
+        height: u32,
+        x: Option<u32>,
+        y: Option<u32>,
+    },
+    /// Grow the picture to `width`×`height` with centered black bars
+    Pad {
+        width: u32,
+        height: u32,
+    },
+    /// Constant output frame rate of `numerator`/`denominator`, e.g. 30000/1001
+    Fps {
+        numerator: u32,
+        denominator: u32,
+    },
+    /// Only touches frames flagged as interlaced
+    Deinterlace(Deinterlacer),
+    Rotate(Rotation),
+    PixelFormat(PictureFormat),
+}
+
+impl Filter {
+    /// Position of the filter in the graph, see [`FilterGraph`].
+    fn stage(&self) -> u8 {
+        match self {
+            Filter::Deinterlace(_) => 0,
+            Filter::Crop { .. } => 1,
+            Filter::Rotate(_) => 2,
+            Filter::Scale { .. } => 3,
+            Filter::Pad { .. } => 4,
+            Filter::Fps { .. } => 5,
+            Filter::PixelFormat(_) => 6,
+        }
+    }
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Filter::Scale {
+                width,
+                height,
+                mode,
+            } => {
+                let aspect = match mode {
+                    ScaleMode::Stretch => "",
+                    ScaleMode::Fit | ScaleMode::Letterbox => {
+                        ":force_original_aspect_ratio=decrease:force_divisible_by=2"
+                    }
+                    ScaleMode::Fill => ":force_original_aspect_ratio=increase:force_divisible_by=2",
+                };
+                write!(f, "scale=w={width}:h={height}{aspect}:flags=lanczos")?;
+
+                match mode {
+                    ScaleMode::Letterbox => write!(
+                        f,
+                        ",{}",
+                        Filter::Pad {
+                            width: *width,
+                            height: *height
+                        }
+                    ),
+                    ScaleMode::Fill => write!(
+                        f,
+                        ",{}",
+                        Filter::Crop {
+                            width: *width,
+                            height: *height,
+                            x: None,
+                            y: None
+                        }
+                    ),
+                    ScaleMode::Stretch | ScaleMode::Fit => Ok(()),
+                }
+            }
+            Filter::Crop {
+                width,
+                height,
+                x,
+                y,
+            } => {
+                write!(f, "crop=w={width}:h={height}")?;
+                if let Some(x) = x {
+                    write!(f, ":x={x}")?;
+                }
+                if let Some(y) = y {
+                    write!(f, ":y={y}")?;
+                }
+                Ok(())
+            }
+            Filter::Pad { width, height } => write!(
+                f,
+                "pad=w={width}:h={height}:x=(ow-iw)/2:y=(oh-ih)/2:color=black"
+            ),
+            Filter::Fps {
+                numerator,
+                denominator: 1,
+            } => write!(f, "fps={numerator}"),
+            Filter::Fps {
+                numerator,
+                denominator,
+            } => write!(f, "fps={numerator}/{denominator}"),
+            Filter::Deinterlace(deinterlacer) => {
+                let name = match deinterlacer {
+                    Deinterlacer::Yadif => "yadif",
+                    Deinterlacer::Bwdif => "bwdif",
+                };
+                write!(f, "{name}=mode=send_frame:parity=auto:deint=interlaced")
+            }
+            Filter::Rotate(rotation) => f.write_str(match rotation {
+                Rotation::Clockwise => "transpose=clock",
+                Rotation::CounterClockwise => "transpose=cclock",
+                Rotation::UpsideDown => "hflip,vflip",
+                Rotation::FlipHorizontal => "hflip",
+                Rotation::FlipVertical => "vflip",
+            }),
+            Filter::PixelFormat(format) => write!(f, "format={format}"),
+        }
+    }
+}
+
+/// A chain of video filters rendered as the value of `-vf`.
+///
+/// Filters run in a fixed order whatever order they were added in : deinterlace, crop, rotate,
+/// scale, pad, fps then pixel format. So a crop is always in source pixels and a pad always around
+/// the scaled picture.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterGraph {
+    filters: Vec<Filter>,
+}
+
+impl FilterGraph {
+    pub fn new() -> Self {
+        FilterGraph::default()
+    }
+
+    pub fn with(mut self, filter: Filter) -> Self {
+        self.push(filter);
+        self
+    }
+
+    pub fn push(&mut self, filter: Filter) {
+        self.filters.push(filter);
+    }
+
+    /// Whether a filter of the same kind as `filter` is in the graph.
+    pub fn contains_kind(&self, filter: &Filter) -> bool {
+        self.filters.iter().any(|f| f.stage() == filter.stage())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// The `-vf` value, `None` when there is nothing to filter.
+    pub fn render(&self) -> Option<String> {
+        (!self.is_empty()).then(|| self.to_string())
+    }
+}
+
+impl Display for FilterGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut filters: Vec<_> = self.filters.iter().collect();
+        filters.sort_by_key(|filter| filter.stage());
+
+        for (i, filter) in filters.into_iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{filter}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<Filter> for FilterGraph {
+    fn from_iter<T: IntoIterator<Item = Filter>>(iter: T) -> Self {
+        FilterGraph {
+            filters: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Deinterlacer, Filter, FilterGraph, Rotation, ScaleMode};
+    use crate::types::PictureFormat;
+
+    fn scale(mode: ScaleMode) -> String {
+        Filter::Scale {
+            width: 1920,
+            height: 1080,
+            mode,
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn scale_modes() {
+        assert_eq!(
+            scale(ScaleMode::Stretch),
+            "scale=w=1920:h=1080:flags=lanczos"
+        );
+        assert_eq!(
+            scale(ScaleMode::Fit),
+            "scale=w=1920:h=1080:force_original_aspect_ratio=decrease:force_divisible_by=2:flags=lanczos"
+        );
+        assert_eq!(
+            scale(ScaleMode::Letterbox),
+            "scale=w=1920:h=1080:force_original_aspect_ratio=decrease:force_divisible_by=2:flags=lanczos,\
+             pad=w=1920:h=1080:x=(ow-iw)/2:y=(oh-ih)/2:color=black"
+        );
+        assert_eq!(
+            scale(ScaleMode::Fill),
+            "scale=w=1920:h=1080:force_original_aspect_ratio=increase:force_divisible_by=2:flags=lanczos,\
+             crop=w=1920:h=1080"
+        );
+    }
+
+    #[test]
+    fn single_filters() {
+        let crop = Filter::Crop {
+            width: 1280,
+            height: 720,
+            x: Some(10),
+            y: None,
+        };
+        assert_eq!(crop.to_string(), "crop=w=1280:h=720:x=10");
+
+        let pad = Filter::Pad {
+            width: 1920,
+            height: 1080,
+        };
+        assert_eq!(
+            pad.to_string(),
+            "pad=w=1920:h=1080:x=(ow-iw)/2:y=(oh-ih)/2:color=black"
+        );
+
+        let fps = |numerator, denominator| {
+            Filter::Fps {
+                numerator,
+                denominator,
+            }
+            .to_string()
+        };
+        assert_eq!(fps(25, 1), "fps=25");
+        assert_eq!(fps(30000, 1001), "fps=30000/1001");
+
+        assert_eq!(
+            Filter::Deinterlace(Deinterlacer::Bwdif).to_string(),
+            "bwdif=mode=send_frame:parity=auto:deint=interlaced"
+        );
+
+        assert_eq!(
+            Filter::Rotate(Rotation::Clockwise).to_string(),
+            "transpose=clock"
+        );
+        assert_eq!(
+            Filter::Rotate(Rotation::UpsideDown).to_string(),
+            "hflip,vflip"
+        );
+
+        assert_eq!(
+            Filter::PixelFormat(PictureFormat::Pf42010B).to_string(),
+            "format=yuv420p10le"
+        );
+    }
+
+    #[test]
+    fn graph_keeps_a_fixed_order() {
+        let graph = FilterGraph::new()
+            .with(Filter::PixelFormat(PictureFormat::Pf4208B))
+            .with(Filter::Fps {
+                numerator: 25,
+                denominator: 1,
+            })
+            .with(Filter::Scale {
+                width: 1280,
+                height: 720,
+                mode: ScaleMode::Stretch,
+            })
+            .with(Filter::Rotate(Rotation::CounterClockwise))
+            .with(Filter::Deinterlace(Deinterlacer::Yadif));
+
+        assert_eq!(
+            graph.render().unwrap(),
+            "yadif=mode=send_frame:parity=auto:deint=interlaced,transpose=cclock,\
+             scale=w=1280:h=720:flags=lanczos,fps=25,format=yuv420p"
+        );
+    }
+
+    #[test]
+    fn empty_graph() {
+        assert_eq!(FilterGraph::new().render(), None);
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt::Display;
+
+use lib_utils::arg::Arg;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::VideoCodec;
+
+/// Frames per second as a fraction, NTSC rates don't fit an integer.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameRate {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+impl FrameRate {
+    pub fn new(numerator: u32, denominator: u32) -> Self {
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// A whole number of frames per second.
+    pub fn fps(fps: u32) -> Self {
+        Self::new(fps, 1)
+    }
+
+    /// The NTSC rate just below `fps`, e.g. `29.97` for 30.
+    pub fn ntsc(fps: u32) -> Self {
+        Self::new(fps * 1000, 1001)
+    }
+}
+
+impl Display for FrameRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.denominator {
+            1 => write!(f, "{}", self.numerator),
+            denominator => write!(f, "{}/{}", self.numerator, denominator),
+        }
+    }
+}
+
+/// How frames are timed in the output.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FrameRateMode {
+    /// Left to the muxer, variable frame rate sources may stay variable
+    #[default]
+    Auto,
+    /// Frames are duplicated or dropped to a constant rate, the target rate or the one of the
+    /// source. What editors expect from phone and screen recordings
+    Constant,
+    /// Timestamps are kept as they are
+    Passthrough,
+}
+
+impl FrameRateMode {
+    pub(crate) fn args(&self) -> Vec<String> {
+        match self {
+            FrameRateMode::Auto => Vec::new(),
+            FrameRateMode::Constant => Arg::new("fps_mode").value("cfr").build(),
+            FrameRateMode::Passthrough => Arg::new("fps_mode").value("passthrough").build(),
+        }
+    }
+}
+
+/// Where the encoder places keyframes. Editors seek faster with short GOPs.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "content", rename_all = "camelCase")]
+pub enum Keyframes {
+    /// A keyframe every this many frames, scene changes add none in between
+    Interval(u32),
+    /// Every frame is a keyframe
+    AllIntra,
+}
+
+impl Keyframes {
+    /// GOP flags of `codec`. ProRes, CineForm and DNxHR only have keyframes, they get none.
+    pub(crate) fn args(&self, codec: VideoCodec) -> Vec<String> {
+        let mut args = Vec::new();
+
+        match (self, codec) {
+            (_, VideoCodec::CineForm | VideoCodec::Prores | VideoCodec::DnxHr) => {}
+
+            (Keyframes::AllIntra, _) => {
+                args.extend(Arg::new("g").value("1").build());
+                args.extend(Arg::new("bf").value("0").build());
+            }
+
+            (Keyframes::Interval(frames), VideoCodec::H264 | VideoCodec::H265) => {
+                args.extend(Arg::new("g").value(frames.to_string()).build());
+                args.extend(Arg::new("keyint_min").value(frames.to_string()).build());
+                if codec == VideoCodec::H264 {
+                    args.extend(Arg::new("sc_threshold").value("0").build());
+                } else {
+                    args.extend(Arg::new("x265-params").value("scenecut=0").build());
+                }
+            }
+            (Keyframes::Interval(frames), _) => {
+                args.extend(Arg::new("g").value(frames.to_string()).build());
+                // The hardware encoders insert keyframes on scene changes unless told otherwise
+                if matches!(codec, VideoCodec::H264NVENC | VideoCodec::H265NVENC) {
+                    args.extend(Arg::new("no-scenecut").value("1").build());
+                }
+            }
+        }
+
+        args
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FrameRate, FrameRateMode, Keyframes};
+    use crate::types::VideoCodec;
+
+    #[test]
+    fn rates_and_modes() {
+        assert_eq!(FrameRate::ntsc(30).to_string(), "30000/1001");
+        assert_eq!(FrameRate::fps(25).to_string(), "25");
+
+        assert_eq!(FrameRateMode::Constant.args(), ["-fps_mode", "cfr"]);
+        assert!(FrameRateMode::Auto.args().is_empty());
+    }
+
+    #[test]
+    fn keyframes_of_each_encoder() {
+        let interval = Keyframes::Interval(24);
+
+        assert_eq!(
+            interval.args(VideoCodec::H264),
+            ["-g", "24", "-keyint_min", "24", "-sc_threshold", "0"]
+        );
+        assert_eq!(
+            interval.args(VideoCodec::H265),
+            [
+                "-g",
+                "24",
+                "-keyint_min",
+                "24",
+                "-x265-params",
+                "scenecut=0"
+            ]
+        );
+        assert_eq!(
+            interval.args(VideoCodec::H265NVENC),
+            ["-g", "24", "-no-scenecut", "1"]
+        );
+        assert_eq!(interval.args(VideoCodec::H264QSV), ["-g", "24"]);
+
+        assert_eq!(
+            Keyframes::AllIntra.args(VideoCodec::H264AMF),
+            ["-g", "1", "-bf", "0"]
+        );
+        assert!(Keyframes::AllIntra.args(VideoCodec::Prores).is_empty());
+        assert!(interval.args(VideoCodec::CineForm).is_empty());
+    }
+}
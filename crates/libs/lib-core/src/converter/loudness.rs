@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+/// Two-pass EBU R128 loudness normalization of the audio, see ffmpeg's `loudnorm`.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoudnessTarget {
+    /// Integrated loudness in LUFS, -70 to -5
+    pub integrated: f64,
+    /// Maximum true peak in dBTP, -9 to 0
+    pub true_peak: f64,
+    /// Loudness range in LU, 1 to 50
+    pub lra: f64,
+}
+
+impl Default for LoudnessTarget {
+    /// The EBU R128 broadcast target
+    fn default() -> Self {
+        Self {
+            integrated: -23.,
+            true_peak: -1.,
+            lra: 7.,
+        }
+    }
+}
+
+impl LoudnessTarget {
+    /// Keep every value in the range `loudnorm` accepts.
+    pub fn clamped(self) -> Self {
+        Self {
+            integrated: self.integrated.clamp(-70., -5.),
+            true_peak: self.true_peak.clamp(-9., 0.),
+            lra: self.lra.clamp(1., 50.),
+        }
+    }
+
+    /// The first pass, only measures.
+    pub(crate) fn measure_filter(&self) -> String {
+        format!("{}:print_format=json", self.base_filter())
+    }
+
+    /// The second pass. Without a measurement `loudnorm` falls back to normalizing in a single
+    /// pass, dynamically. `loudnorm` always outputs 192 kHz, the audio is resampled back to
+    /// `sample_rate`.
+    pub(crate) fn apply_filter(
+        &self,
+        measured: Option<&LoudnessMeasurement>,
+        sample_rate: u32,
+    ) -> String {
+        let mut filter = self.base_filter();
+
+        if let Some(LoudnessMeasurement { input, offset }) = measured {
+            filter += &format!(
+                ":measured_I={:.2}:measured_TP={:.2}:measured_LRA={:.2}\
+                 :measured_thresh={:.2}:offset={:.2}:linear=true",
+                input.integrated, input.true_peak, input.lra, input.threshold, offset
+            );
+        }
+
+        filter + &format!(":print_format=json,aresample={sample_rate}")
+    }
+
+    fn base_filter(&self) -> String {
+        let target = self.clamped();
+        format!(
+            "loudnorm=I={:.1}:TP={:.1}:LRA={:.1}",
+            target.integrated, target.true_peak, target.lra
+        )
+    }
+}
+
+/// Loudness of an audio stream as reported by `loudnorm`.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Loudness {
+    /// Integrated loudness in LUFS
+    pub integrated: f64,
+    /// True peak in dBTP
+    pub true_peak: f64,
+    /// Loudness range in LU
+    pub lra: f64,
+    /// Gating threshold in LUFS
+    pub threshold: f64,
+}
+
+/// Normalization of one file, for the conversion report.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LoudnessReport {
+    /// Loudness of the source, `None` when the measuring pass failed and a single pass was used
+    pub measured: Option<Loudness>,
+    /// Loudness of the output
+    pub result: Option<Loudness>,
+}
+
+/// What the first pass gives the second one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct LoudnessMeasurement {
+    pub(crate) input: Loudness,
+    /// Gain the second pass has to add to hit the target, in LU
+    pub(crate) offset: f64,
+}
+
+/// The JSON block `loudnorm` prints at the end of the log with `print_format=json`.
+#[derive(Debug, Deserialize)]
+struct LoudnormStats {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    output_i: String,
+    output_tp: String,
+    output_lra: String,
+    output_thresh: String,
+    target_offset: String,
+}
+
+/// Input and output loudness with the target offset, from ffmpeg's stderr.
+pub(crate) fn parse_loudnorm(stderr: &str) -> Option<(LoudnessMeasurement, Loudness)> {
+    let block = stderr.get(stderr.rfind("[Parsed_loudnorm")?..)?;
+    let start = block.find('{')?;
+    let end = block.find('}')?;
+    let stats: LoudnormStats = serde_json::from_str(block.get(start..=end)?).ok()?;
+
+    // Silence is reported as -inf
+    let value = |value: &str| value.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+
+    let input = Loudness {
+        integrated: value(&stats.input_i)?,
+        true_peak: value(&stats.input_tp)?,
+        lra: value(&stats.input_lra)?,
+        threshold: value(&stats.input_thresh)?,
+    };
+    let output = Loudness {
+        integrated: value(&stats.output_i)?,
+        true_peak: value(&stats.output_tp)?,
+        lra: value(&stats.output_lra)?,
+        threshold: value(&stats.output_thresh)?,
+    };
+
+    Some((
+        LoudnessMeasurement {
+            input,
+            offset: value(&stats.target_offset)?,
+        },
+        output,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_loudnorm, LoudnessTarget};
+    use crate::exec::fake::FAKE_LOUDNORM as LOG;
+
+    #[test]
+    fn parses_the_stats_block() {
+        let (measurement, output) = parse_loudnorm(LOG).unwrap();
+
+        assert_eq!(measurement.input.integrated, -27.61);
+        assert_eq!(measurement.input.threshold, -39.2);
+        assert_eq!(measurement.offset, 0.02);
+        assert_eq!(output.true_peak, -1.5);
+
+        assert!(parse_loudnorm("no loudnorm here").is_none());
+        assert!(parse_loudnorm(&LOG.replace("-27.61", "-inf")).is_none());
+    }
+
+    #[test]
+    fn second_pass_uses_the_measurement() {
+        let target = LoudnessTarget::default();
+        let (measurement, _) = parse_loudnorm(LOG).unwrap();
+
+        assert_eq!(
+            target.measure_filter(),
+            "loudnorm=I=-23.0:TP=-1.0:LRA=7.0:print_format=json"
+        );
+        assert_eq!(
+            target.apply_filter(Some(&measurement), 48000),
+            "loudnorm=I=-23.0:TP=-1.0:LRA=7.0:measured_I=-27.61:measured_TP=-4.47:measured_LRA=18.06\
+             :measured_thresh=-39.20:offset=0.02:linear=true:print_format=json,aresample=48000"
+        );
+    }
+}
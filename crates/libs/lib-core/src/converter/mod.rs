@@ -1,21 +1,61 @@
+mod audio;
+mod filter;
+mod frame_rate;
+mod loudness;
 mod options;
+mod plan;
+mod profile;
+mod rate_control;
+mod report;
+mod streams;
+mod tags;
+mod thumbnail;
 
 use crate::{
-    copiee::copy_files, exec::exec_batch_ffmpeg, progress::JobInfo, Error, ProgressSystem, Result,
+    control::ConversionControl,
+    copiee::copy_files,
+    exec::{encoder_version, exec_batch_ffmpeg, exec_thumbnails, Executor, FfmpegExecutor},
+    journal::Journal,
+    manifest::Manifest,
+    probe::MediaInfo,
+    progress::JobInfo,
+    scheduler::Scheduler,
+    space::{check_space, SpaceCheck, SpaceWatcher},
+    temp_file::sweep,
+    Error, ProgressSystem, Result,
 };
+pub use audio::{AudioExport, AudioFormat};
+pub use filter::{Deinterlacer, Filter, FilterGraph, Rotation, ScaleMode};
+pub use frame_rate::{FrameRate, FrameRateMode, Keyframes};
+use futures::{stream, StreamExt};
 pub use lib_sorter::{Bucket, Sinker};
 use lib_utils::file::FileExt;
+pub(crate) use loudness::{parse_loudnorm, LoudnessMeasurement};
+pub use loudness::{Loudness, LoudnessReport, LoudnessTarget};
 pub use options::{
-    ArgsType, AudioCodec, ConverterOptions, FfmpegOptions, HwAccel, OutputExtension, PictureFormat,
-    Resolution, VideoCodec,
+    ArgsType, AudioCodec, CineFormQuality, CodecProfile, ConflictPolicy, ConverterOptions,
+    DnxHrProfile, EncoderFamily, FfmpegOptions, HwAccel, OutputExtension, PictureFormat,
+    ProresProfile, Resolution, RetryPolicy, SchedulerOptions, SpaceOptions, VideoCodec,
 };
+pub use plan::{BucketPlan, ConversionPlan, CopyPlan, EncodePlan};
+pub(crate) use profile::journal_key;
+pub use profile::OutputProfile;
+pub use rate_control::{EncoderPreset, EncoderTune, RateControl};
+pub(crate) use report::FileOutcome;
+pub use report::{BucketReport, ConversionReport, EncodedFile, FileFailure};
 use std::{
     fs,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
+pub use streams::{AudioSelection, AudioTrackCodec, StreamMapping};
+pub(crate) use tags::session_tags;
+pub use thumbnail::{ImageFormat, ThumbnailOptions, ThumbnailPlacement, Thumbnails, THUMBS_DIR};
 use tokio::{sync::watch::Receiver as WatchReceiver, sync::RwLock, task::JoinSet};
 
+type ProbedBuckets = Vec<(Arc<str>, Bucket<Option<MediaInfo>>)>;
+
 #[derive(Default, Debug)]
 pub enum State {
     #[default]
@@ -27,9 +67,12 @@ pub enum State {
 pub struct Converter {
     options: Option<Arc<ConverterOptions>>,
     progress_system: Option<Arc<RwLock<ProgressSystem>>>,
-    buckets: Option<Vec<(Arc<str>, Bucket)>>,
+    buckets: Option<ProbedBuckets>,
     state: State,
     stop_signal: Option<WatchReceiver<bool>>,
+    executor: Option<Arc<dyn Executor>>,
+    control: ConversionControl,
+    ignore_space_check: bool,
 }
 
 impl Converter {
@@ -49,39 +92,70 @@ impl Converter {
         a
     }
 
+    /// Run the transcoder through `executor` instead of spawning ffmpeg.
+    pub fn with_executor(mut self, executor: Arc<dyn Executor>) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    /// Start even when the output drive looks too small, e.g. after the user confirmed a
+    /// [`SpaceCheck`] shortfall.
+    pub fn ignore_space_check(mut self, ignore: bool) -> Self {
+        self.ignore_space_check = ignore;
+        self
+    }
+
+    /// Handle to pause and resume the conversion, stays valid across conversions.
+    pub fn control(&self) -> ConversionControl {
+        self.control.clone()
+    }
+
     pub fn reset(&mut self) {
         // Clear the existing state and set to default
         self.options = None;
         self.state = State::Idle;
         self.stop_signal = None;
     }
-    pub async fn prepare_task(&mut self, options: Arc<ConverterOptions>) -> Result<()> {
+    pub async fn prepare_task(
+        &mut self,
+        options: Arc<ConverterOptions>,
+        ffprobe_executable: Option<&'static PathBuf>,
+    ) -> Result<()> {
         if !options.input_dir.clone().exists() {
             return Err(Error::NotExistanceInputOutputDir);
         }
 
-        // Read the directory
-        let entries = fs::read_dir(options.input_dir.clone())
-            .map_err(|err| Error::ReadDirError(err.to_string()))?;
+        let sunk = sink_input(&options)?;
 
-        let all_entries_path: Vec<PathBuf> = entries
-            .filter_map(|entry| entry.ok())
-            .map(|entry| entry.path())
-            .collect();
+        // Temporary outputs of an earlier run that didn't get to clean up after itself
+        let swept: usize = sunk
+            .iter()
+            .map(|(title, _)| sweep(&bucket_output_dir(&options.output_dir, title)))
+            .sum();
+        if swept > 0 {
+            tracing::info!("Removed {} leftover temporary files", swept);
+        }
 
-        // Let it sink
-        let buckets: Vec<(Arc<str>, Bucket)> =
-            Sinker::sink(all_entries_path, options.need_sorting)?
-                .into_iter()
-                .collect();
+        // Probe every video so later stages can work with the real stream information
+        let mut buckets = Vec::with_capacity(sunk.len());
+        for (title, bucket) in sunk.into_iter() {
+            let media = probe_files(bucket.video_files(), ffprobe_executable).await;
+            buckets.push((title, bucket.with_media(media)));
+        }
 
         if let Some(progress_system) = &self.progress_system {
             for (title, bucket) in buckets.iter() {
+                let thumbnails = match options.thumbnail_options() {
+                    Some(_) => bucket.video_files().len(),
+                    None => 0,
+                };
+                // Every output of a video is tracked on its own
                 let job_info = JobInfo::new(
                     title.clone(),
-                    bucket.video_files().len(),
+                    bucket.video_files().len() * options.outputs_per_video(),
                     bucket.xml_files().len(),
-                );
+                )
+                .with_total_thumbnail(thumbnails);
                 progress_system
                     .read()
                     .await
@@ -96,10 +170,111 @@ impl Converter {
         Ok(())
     }
 
+    /// Work out what a conversion with `options` would do, without touching the output directory
+    /// or spawning ffmpeg.
+    pub fn plan(options: &ConverterOptions) -> Result<ConversionPlan> {
+        if !options.input_dir.exists() {
+            return Err(Error::NotExistanceInputOutputDir);
+        }
+
+        let buckets = sink_input(options)?
+            .into_iter()
+            .map(|(name, bucket)| {
+                let output_dir = bucket_output_dir(&options.output_dir, &name);
+                BucketPlan::new(
+                    &name,
+                    output_dir,
+                    &bucket,
+                    &options.ffmpeg_options,
+                    &options.profiles,
+                    options.scheduler.threads,
+                )
+            })
+            .collect();
+
+        Ok(ConversionPlan {
+            input_dir: options.input_dir.clone(),
+            output_dir: options.output_dir.clone(),
+            buckets,
+        })
+    }
+
+    /// Estimate how much the prepared task writes and compare it with the free space of the
+    /// output drive.
+    pub fn check_space(&self) -> Result<SpaceCheck> {
+        let (Some(options), Some(buckets)) = (&self.options, &self.buckets) else {
+            return Err(Error::ConverterHasNoTaskAvailable);
+        };
+
+        check_space(options, buckets)
+    }
+
+    /// Convert the prepared task. A file that fails doesn't stop the others, the returned report
+    /// lists what happened to every file.
+    ///
+    /// Fails with [`Error::NotEnoughSpace`] when the output drive looks too small, unless
+    /// [`Converter::ignore_space_check`] was set.
     pub async fn start_conversion(
         &mut self,
         ffmpeg_executable: Option<&'static PathBuf>,
-    ) -> Result<()> {
+    ) -> Result<ConversionReport> {
+        // Ensure that a task is available before proceeding
+        let Some(options) = self.options.clone() else {
+            return Err(Error::ConverterHasNoTaskAvailable);
+        };
+
+        if !self.ignore_space_check {
+            match self.check_space() {
+                Ok(check) if check.shortfall().is_some() => {
+                    return Err(Error::NotEnoughSpace(check));
+                }
+                Ok(check) => tracing::info!("Space check passed : {}", check),
+                // Some drives don't report their free space, don't stop the import for it
+                Err(err) => tracing::warn!("Skipping space check : {}", err),
+            }
+        }
+
+        create_directory_with_permissions(&options.output_dir)?;
+        let journal = Journal::create(&options)?;
+
+        let executor = self.executor_or(ffmpeg_executable);
+        self.run_conversion(executor, journal).await
+    }
+
+    /// Continue a conversion from the journal left in the output directory. Finished files are
+    /// skipped and half written outputs are redone. Fails when the options of the prepared task
+    /// differ from the ones the journal was written with.
+    pub async fn resume(
+        &mut self,
+        ffmpeg_executable: Option<&'static PathBuf>,
+    ) -> Result<ConversionReport> {
+        let Some(options) = self.options.clone() else {
+            return Err(Error::ConverterHasNoTaskAvailable);
+        };
+
+        let journal = Journal::load(&options)?;
+
+        tracing::info!(
+            "Resuming conversion from {:?}",
+            Journal::path_for(&options.output_dir)
+        );
+
+        let executor = self.executor_or(ffmpeg_executable);
+        self.run_conversion(executor, journal).await
+    }
+
+    /// The executor given with [`Converter::with_executor`], ffmpeg otherwise.
+    fn executor_or(&self, ffmpeg_executable: Option<&'static PathBuf>) -> Arc<dyn Executor> {
+        self.executor
+            .clone()
+            .unwrap_or_else(|| Arc::new(FfmpegExecutor::new(ffmpeg_executable)))
+    }
+
+    async fn run_conversion(
+        &mut self,
+        executor: Arc<dyn Executor>,
+        journal: Journal,
+    ) -> Result<ConversionReport> {
         let converter_opts = format!(
             "Converting started with options : {}",
             serde_json::to_string(&self.options.as_deref().unwrap()).unwrap()
@@ -115,117 +290,317 @@ impl Converter {
             return Err(Error::ConverterHasNoTaskAvailable);
         };
 
-        let max_concurrency = std::thread::available_parallelism()
-            .map(|n| n.get())
-            .unwrap_or(2);
+        let journal = Arc::new(journal);
+
+        let output_dir = self.options.as_ref().unwrap().output_dir.clone();
+        let bucket_dirs: Vec<PathBuf> = buckets
+            .iter()
+            .map(|(name, _)| bucket_output_dir(&output_dir, name))
+            .collect();
+
+        // Recorded in every bucket's manifest
+        let encoder_version = encoder_version(executor.as_ref()).await;
+
+        // One scheduler for every bucket so the limits hold for the whole job
+        let scheduler = Arc::new(Scheduler::new(self.options.as_ref().unwrap().scheduler));
+
+        // Mirror the paused state into the progress system
+        let pause_reporter = self.progress_system.clone().map(|progress_system| {
+            let mut pause = self.control.subscribe();
+            tokio::spawn(async move {
+                loop {
+                    let paused = *pause.borrow_and_update();
+                    if let Err(err) = progress_system.read().await.set_paused(paused).await {
+                        tracing::warn!("Failed to report pause : {}", err);
+                    }
+
+                    if pause.changed().await.is_err() {
+                        break;
+                    }
+                }
+            })
+        });
+
+        // Pause while the output drive is almost full
+        let space_watcher = SpaceWatcher::spawn(
+            self.options.clone().unwrap(),
+            self.control.clone(),
+            self.progress_system.clone(),
+        );
 
         let mut join_set = JoinSet::new();
-        let semaphore = Arc::new(tokio::sync::Semaphore::new(max_concurrency));
-        for (name, bucket) in buckets.into_iter() {
-            let semaphore = semaphore.clone();
+        for (bucket_index, (name, bucket)) in buckets.into_iter().enumerate() {
+            let scheduler = scheduler.clone();
+            let encoder_version = encoder_version.clone();
+            let pause = self.control.subscribe();
             let progress_system = self.progress_system.clone();
             let stop_signal = self.stop_signal.clone().unwrap();
             let options = self.options.clone().unwrap();
+            let journal = journal.clone();
+            let executor = executor.clone();
 
+            let media = bucket.media();
             let (folder_name, xml, video) = bucket.into_parts();
 
             join_set.spawn(async move {
                 tracing::info!("Spawning new thread for bucket : {}", name);
 
-                let permit = semaphore.acquire_owned().await.unwrap();
-                let _ = Converter::convert(
+                let report = Converter::convert(
                     options.as_ref(),
                     folder_name,
                     xml,
                     video,
+                    media,
                     stop_signal,
-                    ffmpeg_executable,
+                    pause,
+                    executor,
                     progress_system,
+                    journal,
+                    scheduler,
+                    bucket_index,
+                    encoder_version,
                 )
                 .await;
 
-                drop(permit);
+                (bucket_index, report)
             });
         }
 
         // Wait for all tasks to complete
-        join_set.join_all().await;
+        let mut buckets = join_set.join_all().await;
+        buckets.sort_by_key(|(bucket_index, _)| *bucket_index);
+
+        space_watcher.stop();
+        if let Some(pause_reporter) = pause_reporter {
+            pause_reporter.abort();
+        }
+
+        // Cancelled encodes may have been detached before removing their temporary output
+        let cancelled = self.stop_signal.as_ref().is_some_and(|s| *s.borrow());
+        if cancelled {
+            for bucket_dir in bucket_dirs.iter() {
+                sweep(bucket_dir);
+            }
+        }
 
         // Reset internal state after spawning all tasks
         self.state = State::Idle;
         self.stop_signal = None;
 
-        Ok(())
+        Ok(ConversionReport {
+            output_dir,
+            buckets: buckets.into_iter().map(|(_, report)| report).collect(),
+            cancelled,
+        })
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn convert(
         options: &ConverterOptions,
         name: Arc<str>,
         xml_files: Arc<[PathBuf]>,
         video_files: Arc<[FileExt]>,
+        media: Arc<[Option<MediaInfo>]>,
         stop_signal: WatchReceiver<bool>,
-        ffmpeg_executable: Option<&'static PathBuf>,
+        pause: WatchReceiver<bool>,
+        executor: Arc<dyn Executor>,
         progress_system: Option<Arc<RwLock<ProgressSystem>>>,
-    ) -> Result<()> {
+        journal: Arc<Journal>,
+        scheduler: Arc<Scheduler>,
+        bucket_index: usize,
+        encoder_version: Option<String>,
+    ) -> BucketReport {
         tracing::info!("Converting files in bucket : {}", name.clone());
 
         // Create the output directory
-        let mut output = options.output_dir.clone();
+        let output = bucket_output_dir(&options.output_dir, &name);
         let folder_name = name.clone();
-        let mut name = name.to_string();
-        name.push_str(" 原");
-        output.push(name);
+        let mut report = BucketReport::new(&name, output.clone());
 
-        if let Err(e) = create_directory_with_permissions(&output) {
-            tracing::error!("Failed to create directory {:?}: {:?}", output, e);
-            return Err(e);
-        }
+        // A video has an entry for its main output and one for each profile
+        let profiles: Vec<Option<String>> = std::iter::once(None)
+            .chain(options.profiles.iter().map(|p| Some(p.name.clone())))
+            .collect();
+        let sources: Vec<(PathBuf, Option<String>, Option<Duration>)> = xml_files
+            .iter()
+            .map(|file| (file.clone(), None, None))
+            .chain(
+                video_files
+                    .iter()
+                    .zip(media.iter())
+                    .flat_map(|(file, media)| {
+                        let duration = media.as_ref().and_then(|m| m.duration);
+                        profiles.iter().map(move |profile| {
+                            (file.path_with_extension(), profile.clone(), duration)
+                        })
+                    }),
+            )
+            .collect();
 
-        // Create the XML directory inside the output directory
-        let mut xml_dir = output.clone();
-        xml_dir.push("xml");
-        if let Err(e) = create_directory_with_permissions(&xml_dir) {
-            tracing::error!("Failed to create directory {:?}: {:?}", xml_dir, e);
-            return Err(e);
-        }
+        let result: Result<()> = async {
+            if let Err(e) = create_directory_with_permissions(&output) {
+                tracing::error!("Failed to create directory {:?}: {:?}", output, e);
+                return Err(e);
+            }
 
-        // Copy the sorted XML files into the XML directory
-        if let Err(e) = copy_files(
-            xml_files,
-            &xml_dir,
-            folder_name.clone(),
-            progress_system.clone(),
-        )
-        .await
-        {
-            tracing::error!("Failed to copy files: {:?}", e);
-            return Err(e);
-        }
+            // Create the XML directory inside the output directory
+            let mut xml_dir = output.clone();
+            xml_dir.push("xml");
+            if let Err(e) = create_directory_with_permissions(&xml_dir) {
+                tracing::error!("Failed to create directory {:?}: {:?}", xml_dir, e);
+                return Err(e);
+            }
+
+            // Copy the sorted XML files into the XML directory
+            let copies = copy_files(
+                xml_files,
+                &xml_dir,
+                folder_name.clone(),
+                progress_system.clone(),
+                journal.clone(),
+                options.conflict_policy,
+            )
+            .await
+            .inspect_err(|e| tracing::error!("Failed to copy files: {:?}", e))?;
+            copies
+                .into_iter()
+                .for_each(|outcome| report.record(outcome));
+
+            tracing::info!("done copying files in bucket : {}", folder_name);
+
+            // Create the folders of the extra outputs
+            for profile in options.profiles.iter() {
+                let profile_dir = profile.output_dir(&output);
+                if let Err(e) = create_directory_with_permissions(&profile_dir) {
+                    tracing::error!("Failed to create directory {:?}: {:?}", profile_dir, e);
+                    return Err(e);
+                }
+            }
+
+            // Execute the FFmpeg batch processing with a stop signal
+            let encodes = exec_batch_ffmpeg(
+                video_files.clone(),
+                media.clone(),
+                output.clone(),
+                options.ffmpeg_options.clone(),
+                options.profiles.iter().cloned().collect(),
+                stop_signal.clone(),
+                pause.clone(),
+                executor.clone(),
+                progress_system.clone(),
+                folder_name.clone(),
+                journal.clone(),
+                options.conflict_policy,
+                scheduler.clone(),
+                bucket_index,
+            )
+            .await;
+            encodes
+                .into_iter()
+                .for_each(|outcome| report.record(outcome));
+
+            tracing::info!("done converting files in bucket : {}", folder_name);
+
+            if let Some(thumbnails) = options.thumbnail_options() {
+                // Create the thumbs directory next to the XML directory
+                if thumbnails.placement == ThumbnailPlacement::ThumbsFolder {
+                    let thumbs_dir = output.join(THUMBS_DIR);
+                    if let Err(e) = create_directory_with_permissions(&thumbs_dir) {
+                        tracing::error!("Failed to create directory {:?}: {:?}", thumbs_dir, e);
+                        return Err(e);
+                    }
+                }
+
+                exec_thumbnails(
+                    video_files,
+                    media,
+                    &mut report.encoded,
+                    &output,
+                    thumbnails,
+                    stop_signal.clone(),
+                    pause,
+                    executor,
+                    progress_system.clone(),
+                    folder_name.clone(),
+                    scheduler,
+                    bucket_index,
+                )
+                .await;
+
+                tracing::info!("done writing thumbnails in bucket : {}", folder_name);
+            }
 
-        tracing::info!("done copying files in bucket : {}", folder_name);
+            Ok(())
+        }
+        .await;
 
-        // Execute the FFmpeg batch processing with a stop signal
-        exec_batch_ffmpeg(
-            video_files,
-            output,
-            options.ffmpeg_options,
-            stop_signal.clone(),
-            ffmpeg_executable,
-            progress_system.clone(),
-            folder_name.clone(),
-        )
-        .await?;
+        if let Err(err) = result {
+            report.error = Some(err.to_string());
+        }
 
-        tracing::info!("done converting files in bucket : {}", folder_name);
+        if output.is_dir() {
+            let manifest =
+                Manifest::build(&name, options, encoder_version, sources, &journal).await;
+            match manifest.write(&output) {
+                Ok(path) => report.manifest = Some(path),
+                Err(err) => tracing::error!("Failed to write manifest [{}] : {}", name, err),
+            }
+        }
 
+        // Even a failed bucket is done, nothing more is going to happen to it
         if let Some(progress_system) = progress_system {
-            progress_system.read().await.done(folder_name).await?
+            if let Err(err) = progress_system.read().await.done(folder_name).await {
+                tracing::warn!("Failed to report bucket done : {}", err);
+            }
         }
 
-        Ok(())
+        report
     }
 }
 
+/// Read the input directory and sort its files into buckets.
+fn sink_input(options: &ConverterOptions) -> Result<Vec<(Arc<str>, Bucket)>> {
+    // Read the directory
+    let entries = fs::read_dir(options.input_dir.clone())
+        .map_err(|err| Error::ReadDirError(err.to_string()))?;
+
+    let all_entries_path: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+
+    // Let it sink
+    Ok(Sinker::sink(all_entries_path, options.need_sorting)?
+        .into_iter()
+        .collect())
+}
+
+/// Folder a bucket is written to inside the output directory.
+pub(crate) fn bucket_output_dir(output_dir: &Path, name: &str) -> PathBuf {
+    output_dir.join(format!("{name} 原"))
+}
+
+/// Probe the files a few at a time, keeping their order. A file that can't be probed gets `None`
+/// so a broken clip doesn't prevent the rest of the import.
+async fn probe_files(
+    files: Arc<[FileExt]>,
+    ffprobe_executable: Option<&'static PathBuf>,
+) -> Vec<Option<MediaInfo>> {
+    stream::iter(files.iter().cloned())
+        .map(|file| async move {
+            match MediaInfo::probe(&file, ffprobe_executable).await {
+                Ok(info) => Some(info),
+                Err(err) => {
+                    tracing::warn!("Failed to probe {:?} : {}", file.file_name(), err);
+                    None
+                }
+            }
+        })
+        .buffered(4)
+        .collect()
+        .await
+}
+
 #[allow(clippy::permissions_set_readonly_false)]
 fn create_directory_with_permissions(path: &Path) -> Result<()> {
     // Create directory and set permissions
@@ -1,9 +1,24 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{fmt::Display, path::PathBuf, time::Duration};
 use typeshare::typeshare;
 
 use lib_utils::arg::Arg;
 use serde::{Deserialize, Serialize};
 
+use super::{
+    audio::{AudioExport, AudioFormat},
+    filter::{Filter, FilterGraph, ScaleMode},
+    frame_rate::{FrameRate, FrameRateMode, Keyframes},
+    loudness::{LoudnessMeasurement, LoudnessTarget},
+    profile::OutputProfile,
+    rate_control::{EncoderPreset, EncoderTune, RateControl},
+    streams::StreamMapping,
+    thumbnail::ThumbnailOptions,
+};
+use crate::probe::MediaInfo;
+
+/// Rate of the normalized audio when neither the options nor ffprobe give one
+const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+
 #[typeshare]
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -14,6 +29,18 @@ pub enum Resolution {
     R4K,
 }
 
+impl Resolution {
+    /// Width and height in pixels.
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Resolution::R720P => (1280, 720),
+            Resolution::R1080P => (1920, 1080),
+            Resolution::R1440P => (2560, 1440),
+            Resolution::R4K => (4096, 2160),
+        }
+    }
+}
+
 impl Display for Resolution {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -33,6 +60,28 @@ pub enum AudioCodec {
     Flac,
     Aac,
     Ipcm,
+    Mp3,
+    Opus,
+    /// 16 bit little endian PCM, what WAV files hold
+    Wav,
+    Alac,
+}
+
+impl AudioCodec {
+    pub const ALL: [AudioCodec; 7] = [
+        AudioCodec::Flac,
+        AudioCodec::Aac,
+        AudioCodec::Ipcm,
+        AudioCodec::Mp3,
+        AudioCodec::Opus,
+        AudioCodec::Wav,
+        AudioCodec::Alac,
+    ];
+
+    /// Whether a bitrate applies to the codec.
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, AudioCodec::Aac | AudioCodec::Mp3 | AudioCodec::Opus)
+    }
 }
 
 impl Display for AudioCodec {
@@ -41,6 +90,10 @@ impl Display for AudioCodec {
             AudioCodec::Flac => f.write_str("flac"),
             AudioCodec::Ipcm => f.write_str("pcm_s24be"),
             AudioCodec::Aac => f.write_str("aac"),
+            AudioCodec::Mp3 => f.write_str("libmp3lame"),
+            AudioCodec::Opus => f.write_str("libopus"),
+            AudioCodec::Wav => f.write_str("pcm_s16le"),
+            AudioCodec::Alac => f.write_str("alac"),
         }
     }
 }
@@ -53,6 +106,7 @@ pub enum PictureFormat {
     Pf4228B,
     Pf42010B,
     Pf4208B,
+    Pf44410B,
 }
 
 impl Display for PictureFormat {
@@ -62,6 +116,7 @@ impl Display for PictureFormat {
             PictureFormat::Pf4228B => f.write_str("yuv422p"),
             PictureFormat::Pf42010B => f.write_str("yuv420p10le"),
             PictureFormat::Pf4208B => f.write_str("yuv420p"),
+            PictureFormat::Pf44410B => f.write_str("yuv444p10le"),
         }
     }
 }
@@ -81,7 +136,50 @@ pub enum VideoCodec {
     H265QSV,
     CineForm,
     Prores,
+    /// DNxHR through `dnxhd`, HQ unless another [`DnxHrProfile`] is given
+    DnxHr,
+}
+impl VideoCodec {
+    pub const ALL: [VideoCodec; 11] = [
+        VideoCodec::H264,
+        VideoCodec::H264NVENC,
+        VideoCodec::H264AMF,
+        VideoCodec::H264QSV,
+        VideoCodec::H265,
+        VideoCodec::H265NVENC,
+        VideoCodec::H265AMF,
+        VideoCodec::H265QSV,
+        VideoCodec::CineForm,
+        VideoCodec::Prores,
+        VideoCodec::DnxHr,
+    ];
+
+    pub fn family(&self) -> EncoderFamily {
+        match self {
+            VideoCodec::H264NVENC | VideoCodec::H265NVENC => EncoderFamily::Nvenc,
+            VideoCodec::H264QSV | VideoCodec::H265QSV => EncoderFamily::Qsv,
+            VideoCodec::H264AMF | VideoCodec::H265AMF => EncoderFamily::Amf,
+            VideoCodec::H264
+            | VideoCodec::H265
+            | VideoCodec::CineForm
+            | VideoCodec::Prores
+            | VideoCodec::DnxHr => EncoderFamily::Software,
+        }
+    }
+}
+
+/// Where an encoder runs. Each family has its own concurrency limit, hardware encoders only
+/// allow a few sessions at once.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum EncoderFamily {
+    Software,
+    Nvenc,
+    Qsv,
+    Amf,
 }
+
 #[typeshare]
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[non_exhaustive]
@@ -118,10 +216,167 @@ impl Display for VideoCodec {
             VideoCodec::H265QSV => f.write_str("hevc_qsv"),
             VideoCodec::CineForm => f.write_str("cfhd"),
             VideoCodec::Prores => f.write_str("prores"),
+            VideoCodec::DnxHr => f.write_str("dnxhd"),
         }
     }
 }
 
+/// ProRes flavours of `prores_ks`, lightest first.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ProresProfile {
+    Proxy,
+    Lt,
+    /// ProRes 422
+    Standard,
+    Hq,
+    /// ProRes 4444, without alpha
+    P4444,
+}
+
+impl ProresProfile {
+    pub fn picture_format(&self) -> PictureFormat {
+        match self {
+            ProresProfile::P4444 => PictureFormat::Pf44410B,
+            _ => PictureFormat::Pf42210B,
+        }
+    }
+}
+
+impl Display for ProresProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProresProfile::Proxy => f.write_str("proxy"),
+            ProresProfile::Lt => f.write_str("lt"),
+            ProresProfile::Standard => f.write_str("standard"),
+            ProresProfile::Hq => f.write_str("hq"),
+            ProresProfile::P4444 => f.write_str("4444"),
+        }
+    }
+}
+
+/// Quality levels of `cfhd`, lowest first.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CineFormQuality {
+    Low,
+    Medium,
+    High,
+    Film1,
+    Film2,
+    Film3,
+}
+
+impl CineFormQuality {
+    pub fn picture_format(&self) -> PictureFormat {
+        PictureFormat::Pf42210B
+    }
+}
+
+impl Display for CineFormQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CineFormQuality::Low => f.write_str("low"),
+            CineFormQuality::Medium => f.write_str("medium"),
+            CineFormQuality::High => f.write_str("high"),
+            CineFormQuality::Film1 => f.write_str("film1"),
+            CineFormQuality::Film2 => f.write_str("film2"),
+            CineFormQuality::Film3 => f.write_str("film3"),
+        }
+    }
+}
+
+/// DNxHR profiles of `dnxhd`, lightest first. LB, SQ and HQ are 8 bit.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DnxHrProfile {
+    Lb,
+    Sq,
+    Hq,
+    Hqx,
+    P444,
+}
+
+impl DnxHrProfile {
+    pub fn picture_format(&self) -> PictureFormat {
+        match self {
+            DnxHrProfile::Lb | DnxHrProfile::Sq | DnxHrProfile::Hq => PictureFormat::Pf4228B,
+            DnxHrProfile::Hqx => PictureFormat::Pf42210B,
+            DnxHrProfile::P444 => PictureFormat::Pf44410B,
+        }
+    }
+}
+
+impl Display for DnxHrProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DnxHrProfile::Lb => f.write_str("dnxhr_lb"),
+            DnxHrProfile::Sq => f.write_str("dnxhr_sq"),
+            DnxHrProfile::Hq => f.write_str("dnxhr_hq"),
+            DnxHrProfile::Hqx => f.write_str("dnxhr_hqx"),
+            DnxHrProfile::P444 => f.write_str("dnxhr_444"),
+        }
+    }
+}
+
+/// Profile of an intermediate codec. Each one only applies to its own [`VideoCodec`] and sets
+/// the pixel format of the output.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "codec", content = "profile", rename_all = "camelCase")]
+pub enum CodecProfile {
+    Prores(ProresProfile),
+    CineForm(CineFormQuality),
+    DnxHr(DnxHrProfile),
+}
+
+impl CodecProfile {
+    /// What `codec` gets without a profile, DNxHR can't encode without one.
+    pub fn default_for(codec: VideoCodec) -> Option<Self> {
+        match codec {
+            VideoCodec::DnxHr => Some(CodecProfile::DnxHr(DnxHrProfile::Hq)),
+            _ => None,
+        }
+    }
+
+    pub fn codec(&self) -> VideoCodec {
+        match self {
+            CodecProfile::Prores(_) => VideoCodec::Prores,
+            CodecProfile::CineForm(_) => VideoCodec::CineForm,
+            CodecProfile::DnxHr(_) => VideoCodec::DnxHr,
+        }
+    }
+
+    /// The encoder implementing the profiles, `prores_ks` for ProRes.
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            CodecProfile::Prores(_) => "prores_ks",
+            CodecProfile::CineForm(_) => "cfhd",
+            CodecProfile::DnxHr(_) => "dnxhd",
+        }
+    }
+
+    pub fn picture_format(&self) -> PictureFormat {
+        match self {
+            CodecProfile::Prores(profile) => profile.picture_format(),
+            CodecProfile::CineForm(quality) => quality.picture_format(),
+            CodecProfile::DnxHr(profile) => profile.picture_format(),
+        }
+    }
+
+    pub(crate) fn args(&self) -> Vec<String> {
+        match self {
+            CodecProfile::Prores(profile) => Arg::new("profile:v").value(profile.to_string()),
+            CodecProfile::CineForm(quality) => Arg::new("quality").value(quality.to_string()),
+            CodecProfile::DnxHr(profile) => Arg::new("profile:v").value(profile.to_string()),
+        }
+        .build()
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 #[typeshare]
 #[serde(tag = "type", content = "content")]
@@ -150,6 +405,16 @@ pub enum HwAccel {
     Vaapi,
     Vulkan,
 }
+
+impl HwAccel {
+    pub const ALL: [HwAccel; 4] = [
+        HwAccel::Cuda,
+        HwAccel::Directx,
+        HwAccel::Vaapi,
+        HwAccel::Vulkan,
+    ];
+}
+
 impl Display for HwAccel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -161,8 +426,40 @@ impl Display for HwAccel {
     }
 }
 
+/// How often a failed encode is tried again, and with which encoders.
+///
+/// Every encoder of the chain, the configured one first, gets up to `max_attempts` tries. The
+/// wait before a retry doubles each time, starting at `backoff_ms`.
 #[typeshare]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub backoff_ms: u32,
+    /// Encoders to fall back to, in order, e.g. `H264QSV` then `H264` after `H264NVENC`
+    pub fallback: Vec<VideoCodec>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff_ms: 1000,
+            fallback: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before the `retry`-th retry of an encoder, starting at 1.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        Duration::from_millis(self.backoff_ms as u64 * factor as u64)
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct FfmpegOptions {
     pub resolution: ArgsType<Resolution>,
@@ -173,9 +470,58 @@ pub struct FfmpegOptions {
     pub video_bitrate: ArgsType<u32>,
     pub picture_format: ArgsType<PictureFormat>,
     pub output_extension: OutputExtension,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Takes precedence over `video_bitrate`
+    #[serde(default)]
+    pub rate_control: Option<RateControl>,
+    #[serde(default)]
+    pub preset: Option<EncoderPreset>,
+    #[serde(default)]
+    pub tune: Option<EncoderTune>,
+    /// Video filters on top of `resolution` and `picture_format`, an explicit scale or pixel
+    /// format here replaces theirs
+    #[serde(default)]
+    pub filters: Vec<Filter>,
+    /// Only applied when the audio is re-encoded
+    #[serde(default)]
+    pub loudness: Option<LoudnessTarget>,
+    /// Export the audio alone, the video options are ignored
+    #[serde(default)]
+    pub audio_only: Option<AudioExport>,
+    /// Streams of the source to keep, ffmpeg's default selection when `None`. Ignored by
+    /// audio-only exports, they pick their own track
+    #[serde(default)]
+    pub streams: Option<StreamMapping>,
+    /// The rate of the source when `None`
+    #[serde(default)]
+    pub frame_rate: Option<FrameRate>,
+    #[serde(default)]
+    pub frame_rate_mode: FrameRateMode,
+    /// The encoder's own GOP when `None`
+    #[serde(default)]
+    pub keyframes: Option<Keyframes>,
+    /// Move the index of MOV and MP4 outputs to the front so they play before being fully read
+    #[serde(default)]
+    pub faststart: bool,
+    /// Ignored unless it is a profile of `video_codec`, see [`FfmpegOptions::video_profile`]
+    #[serde(default)]
+    pub codec_profile: Option<CodecProfile>,
 }
 
 impl FfmpegOptions {
+    /// Family of the video encoder, stream copies and audio-only exports count as software.
+    pub fn encoder_family(&self) -> EncoderFamily {
+        if self.audio_export().is_some() {
+            return EncoderFamily::Software;
+        }
+
+        self.video_codec
+            .to_option()
+            .map(|codec| codec.family())
+            .unwrap_or(EncoderFamily::Software)
+    }
+
     pub fn new(
         resolution: ArgsType<Resolution>,
         hwaccel: Option<HwAccel>,
@@ -195,16 +541,268 @@ impl FfmpegOptions {
             video_bitrate,
             picture_format,
             output_extension,
+            retry: RetryPolicy::default(),
+            rate_control: None,
+            preset: None,
+            tune: None,
+            filters: Vec::new(),
+            loudness: None,
+            audio_only: None,
+            streams: None,
+            frame_rate: None,
+            frame_rate_mode: FrameRateMode::Auto,
+            keyframes: None,
+            faststart: false,
+            codec_profile: None,
+        }
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_rate_control(mut self, rate_control: RateControl) -> Self {
+        self.rate_control = Some(rate_control);
+        self
+    }
+
+    pub fn with_preset(mut self, preset: EncoderPreset) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+
+    pub fn with_tune(mut self, tune: EncoderTune) -> Self {
+        self.tune = Some(tune);
+        self
+    }
+
+    pub fn with_loudness(mut self, loudness: LoudnessTarget) -> Self {
+        self.loudness = Some(loudness);
+        self
+    }
+
+    pub fn with_audio_only(mut self, audio_only: AudioExport) -> Self {
+        self.audio_only = Some(audio_only);
+        self
+    }
+
+    pub fn with_streams(mut self, streams: StreamMapping) -> Self {
+        self.streams = Some(streams);
+        self
+    }
+
+    pub fn with_frame_rate(mut self, frame_rate: FrameRate) -> Self {
+        self.frame_rate = Some(frame_rate);
+        self
+    }
+
+    pub fn with_frame_rate_mode(mut self, frame_rate_mode: FrameRateMode) -> Self {
+        self.frame_rate_mode = frame_rate_mode;
+        self
+    }
+
+    pub fn with_keyframes(mut self, keyframes: Keyframes) -> Self {
+        self.keyframes = Some(keyframes);
+        self
+    }
+
+    pub fn with_faststart(mut self, faststart: bool) -> Self {
+        self.faststart = faststart;
+        self
+    }
+
+    pub fn with_codec_profile(mut self, codec_profile: CodecProfile) -> Self {
+        self.codec_profile = Some(codec_profile);
+        self
+    }
+
+    /// Profile the video is encoded with, `codec_profile` when it belongs to the video codec.
+    /// Fallback encoders of another codec drop it.
+    pub fn video_profile(&self) -> Option<CodecProfile> {
+        let codec = self.video_codec.to_option()?;
+        self.codec_profile
+            .filter(|profile| profile.codec() == codec)
+            .or_else(|| CodecProfile::default_for(codec))
+    }
+
+    /// Pixel format of the output, the one of the profile when there is one.
+    pub fn pixel_format(&self) -> Option<PictureFormat> {
+        match self.video_profile() {
+            Some(profile) => Some(profile.picture_format()),
+            None => self.picture_format.to_option(),
         }
     }
 
-    pub fn build_with_io(self, input: PathBuf, output: PathBuf) -> Vec<String> {
+    /// Name of the video encoder, e.g. `prores_ks` for a ProRes profile.
+    fn video_encoder(&self) -> String {
+        match self.video_profile() {
+            Some(profile) => profile.encoder().to_string(),
+            None => self.video_codec.to_string(),
+        }
+    }
+
+    /// The audio-only export, an `mp3` output extension implies one.
+    pub fn audio_export(&self) -> Option<AudioExport> {
+        match (self.audio_only, self.output_extension) {
+            (Some(export), _) => Some(export),
+            (None, OutputExtension::Mp3) => Some(AudioExport::new(AudioFormat::Mp3)),
+            (None, _) => None,
+        }
+    }
+
+    /// Extension of the outputs.
+    pub fn extension(&self) -> String {
+        match self.audio_export() {
+            Some(export) => export.format.extension().to_string(),
+            None => self.output_extension.to_string(),
+        }
+    }
+
+    /// The encoder doing the work, the audio one for audio-only exports.
+    pub fn encoder_name(&self) -> String {
+        match self.audio_export() {
+            Some(export) => export.encoder().to_string(),
+            None => self.video_encoder(),
+        }
+    }
+
+    /// Whether the audio goes through an encoder, anything but a stream copy.
+    pub fn reencodes_audio(&self) -> bool {
+        self.audio_export().is_some()
+            || !matches!(
+                (self.audio_codec, self.audio_bitrate),
+                (ArgsType::MatchSource, ArgsType::MatchSource)
+            )
+    }
+
+    /// The loudness target when it applies, see [`FfmpegOptions::reencodes_audio`].
+    pub fn loudness_normalization(&self) -> Option<LoudnessTarget> {
+        self.loudness.filter(|_| self.reencodes_audio())
+    }
+
+    /// Audio track of the source the loudness normalization measures, counted from 0. `None`
+    /// leaves it to ffmpeg, the output then has a single audio track. With a
+    /// [`StreamMapping`] only the first audio track of the output is normalized.
+    pub(crate) fn loudness_track(&self, media: Option<&MediaInfo>) -> Option<u32> {
+        match (self.audio_export(), &self.streams) {
+            (Some(export), _) => Some(export.stream),
+            (None, Some(streams)) => Some(streams.first_audio_track(media)),
+            (None, None) => None,
+        }
+    }
+
+    /// Rate the normalized audio is resampled back to : the one of the export, else the one of
+    /// the source.
+    fn loudness_sample_rate(&self, media: Option<&MediaInfo>) -> u32 {
+        let track = self.loudness_track(media).unwrap_or_default() as usize;
+        let source = media
+            .and_then(|media| media.audio.get(track))
+            .and_then(|track| track.sample_rate);
+
+        self.audio_export()
+            .and_then(|export| export.sample_rate)
+            .or(source)
+            .unwrap_or(DEFAULT_SAMPLE_RATE)
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Every video filter of the encode, `filters` plus the scale to `resolution` and the
+    /// conversion to [`FfmpegOptions::pixel_format`]. Empty when the video is stream copied,
+    /// ffmpeg can't filter a copy.
+    pub fn filter_graph(&self) -> FilterGraph {
+        if self.video_codec == ArgsType::MatchSource {
+            return FilterGraph::new();
+        }
+
+        let mut graph: FilterGraph = self.filters.iter().cloned().collect();
+
+        if let ArgsType::Custom(resolution) = self.resolution {
+            let (width, height) = resolution.dimensions();
+            let scale = Filter::Scale {
+                width,
+                height,
+                mode: ScaleMode::Stretch,
+            };
+            if !graph.contains_kind(&scale) {
+                graph.push(scale);
+            }
+        }
+
+        if let Some(picture_format) = self.pixel_format() {
+            let format = Filter::PixelFormat(picture_format);
+            if !graph.contains_kind(&format) {
+                graph.push(format);
+            }
+        }
+
+        graph
+    }
+
+    /// The options of every encoder to try, the configured one first then the fallbacks.
+    /// Hardware decoding is dropped for software fallbacks and for other encoder families, the
+    /// device may be what failed.
+    pub fn encoder_chain(&self) -> Vec<FfmpegOptions> {
+        let mut chain = vec![self.clone()];
+
+        // No video encoder to fall back from
+        if self.audio_export().is_some() {
+            return chain;
+        }
+
+        let family = self.encoder_family();
+
+        for codec in self.retry.fallback.iter() {
+            if chain
+                .iter()
+                .any(|o| o.video_codec == ArgsType::Custom(*codec))
+            {
+                continue;
+            }
+
+            let mut options = self.clone();
+            options.video_codec = ArgsType::Custom(*codec);
+            if codec.family() == EncoderFamily::Software || codec.family() != family {
+                options.hwaccel = None;
+            }
+            chain.push(options);
+        }
+
+        chain
+    }
+
+    pub fn build_with_io(&self, input: PathBuf, output: PathBuf) -> Vec<String> {
         let (mut args, input_offset, _) = self.build();
         args.insert(input_offset, input.to_str().unwrap().to_string());
         args.push(output.to_str().unwrap().to_string());
         args
     }
     pub fn build(&self) -> (Vec<String>, usize, usize) {
+        self.build_measured(None, None)
+    }
+
+    /// Options of the input, everything before `-i`.
+    pub(crate) fn input_args(&self) -> Vec<String> {
+        let (mut args, input_offset, _) = self.build();
+        args.truncate(input_offset - 1);
+        args
+    }
+
+    /// [`FfmpegOptions::build`] with the first pass of the loudness normalization, `media`
+    /// gives the sample rate of the normalized track.
+    pub(crate) fn build_measured(
+        &self,
+        measured: Option<&LoudnessMeasurement>,
+        media: Option<&MediaInfo>,
+    ) -> (Vec<String>, usize, usize) {
+        if let Some(export) = self.audio_export() {
+            return self.build_audio_only(export, measured, media);
+        }
+
         let mut args = Vec::new();
 
         if let Some(hwaccel) = self.hwaccel {
@@ -222,50 +820,51 @@ impl FfmpegOptions {
 
         let input_offset = args.len();
 
-        if !matches!(self.resolution, ArgsType::MatchSource) {
-            args.extend(
-                Arg::new("vf")
-                    .value(
-                        Arg::new(
-                            Arg::new("scale")
-                                .without_dash()
-                                .value(self.resolution.to_string())
-                                .with_value_spacer("= ")
-                                .build()
-                                .join(""),
-                        )
-                        .without_dash()
-                        .value(
-                            Arg::new("flags")
-                                .without_dash()
-                                .value("lanczos")
-                                .with_value_spacer("=")
-                                .build()
-                                .join(""),
-                        )
-                        .with_value_spacer(":")
-                        .build()
-                        .join(""),
-                    )
-                    .build(),
-            );
+        if let Some(streams) = &self.streams {
+            args.extend(streams.map_args(self.output_extension, media));
+        }
+
+        if let Some(graph) = self.filter_graph().render() {
+            args.extend(Arg::new("vf").value(graph).build());
         }
 
-        //Vidoe codec and bitrate
+        //Vidoe codec and rate control
         match (self.video_codec, self.video_bitrate) {
-            (ArgsType::Custom(codec), ArgsType::Custom(bitrate)) => {
-                args.extend(Arg::new("c:v").value(codec.to_string()).build());
+            (ArgsType::Custom(codec), bitrate) => {
+                args.extend(Arg::new("c:v").value(self.video_encoder()).build());
+                if let Some(profile) = self.video_profile() {
+                    args.extend(profile.args());
+                }
 
-                args.extend(Arg::new("b:v").value(bitrate.to_string() + "k").build());
+                match (self.rate_control, bitrate) {
+                    (Some(rate_control), _) => args.extend(rate_control.args(codec)),
+                    (None, ArgsType::Custom(bitrate)) => {
+                        args.extend(Arg::new("b:v").value(bitrate.to_string() + "k").build());
+                    }
+                    (None, ArgsType::MatchSource) => {}
+                }
+
+                if let Some(preset) = self.preset {
+                    args.extend(preset.args(codec));
+                }
+                if let Some(tune) = self.tune {
+                    args.extend(tune.args(codec));
+                }
+
+                // Timing only changes when the video is encoded, a stream copy keeps the source's
+                if let Some(frame_rate) = self.frame_rate {
+                    args.extend(Arg::new("r").value(frame_rate.to_string()).build());
+                }
+                args.extend(self.frame_rate_mode.args());
+                if let Some(keyframes) = self.keyframes {
+                    args.extend(keyframes.args(codec));
+                }
             }
             (ArgsType::MatchSource, ArgsType::MatchSource) => {
                 args.extend(Arg::new("c:v").value(self.video_codec.to_string()).build());
             }
-            (_, ArgsType::Custom(bitrate)) => {
-                args.extend(Arg::new("b:a").value(bitrate.to_string() + "k").build());
-            }
-            (ArgsType::Custom(codec), _) => {
-                args.extend(Arg::new("c:v").value(codec.to_string()).build());
+            (ArgsType::MatchSource, ArgsType::Custom(bitrate)) => {
+                args.extend(Arg::new("b:v").value(bitrate.to_string() + "k").build());
             }
         }
 
@@ -287,22 +886,165 @@ impl FfmpegOptions {
             }
         }
 
+        if let Some(streams) = &self.streams {
+            args.extend(streams.codec_args(self.output_extension, media));
+        }
+
+        if let Some(loudness) = self.loudness_normalization() {
+            // The other kept tracks weren't measured, they stay as they are
+            let flag = match self.streams {
+                Some(_) => "filter:a:0",
+                None => "af",
+            };
+            let sample_rate = self.loudness_sample_rate(media);
+            args.extend(
+                Arg::new(flag)
+                    .value(loudness.apply_filter(measured, sample_rate))
+                    .build(),
+            );
+        }
+
+        if self.faststart
+            && matches!(
+                self.output_extension,
+                OutputExtension::Mov | OutputExtension::Mp4
+            )
+        {
+            args.extend(Arg::new("movflags").value("+faststart").build());
+        }
+
         // args.push(output.to_str().unwrap().to_string());
 
         let output_offset = args.len();
 
         (args, input_offset, output_offset)
     }
+
+    fn build_audio_only(
+        &self,
+        export: AudioExport,
+        measured: Option<&LoudnessMeasurement>,
+        media: Option<&MediaInfo>,
+    ) -> (Vec<String>, usize, usize) {
+        let mut args = Arg::new("i").build();
+        let input_offset = args.len();
+
+        args.extend(export.args(self.audio_bitrate.to_option()));
+
+        if let Some(loudness) = self.loudness {
+            let sample_rate = self.loudness_sample_rate(media);
+            args.extend(
+                Arg::new("af")
+                    .value(loudness.apply_filter(measured, sample_rate))
+                    .build(),
+            );
+        }
+
+        let output_offset = args.len();
+
+        (args, input_offset, output_offset)
+    }
+}
+
+/// What to do when a file we are about to write already exists.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    /// Keep the existing file. Sidecar files are only kept when identical to their source,
+    /// a different one is replaced
+    #[default]
+    Skip,
+    Overwrite,
+    /// Write next to it with a numeric suffix, e.g. `clip_1.mkv`
+    Rename,
+    Fail,
+}
+
+/// How many encodes may run at once, across all buckets.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerOptions {
+    pub max_jobs: u32,
+    pub software_slots: u32,
+    pub nvenc_slots: u32,
+    pub qsv_slots: u32,
+    pub amf_slots: u32,
+    /// Passed to ffmpeg as `-threads`
+    pub threads: Option<u32>,
+}
+
+impl Default for SchedulerOptions {
+    fn default() -> Self {
+        Self {
+            max_jobs: 4,
+            software_slots: 2,
+            nvenc_slots: 3,
+            qsv_slots: 2,
+            amf_slots: 2,
+            threads: None,
+        }
+    }
 }
 
+impl SchedulerOptions {
+    pub fn slots(&self, family: EncoderFamily) -> u32 {
+        match family {
+            EncoderFamily::Software => self.software_slots,
+            EncoderFamily::Nvenc => self.nvenc_slots,
+            EncoderFamily::Qsv => self.qsv_slots,
+            EncoderFamily::Amf => self.amf_slots,
+        }
+    }
+}
+
+/// When a running conversion pauses itself because the output drive is filling up.
 #[typeshare]
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceOptions {
+    /// Pause below this much free space on the output drive, in MB
+    pub min_free_mb: u32,
+    /// Seconds between two free space checks
+    pub check_interval_secs: u32,
+}
+
+impl Default for SpaceOptions {
+    fn default() -> Self {
+        Self {
+            min_free_mb: 2048,
+            check_interval_secs: 10,
+        }
+    }
+}
+
+impl SpaceOptions {
+    pub fn min_free_bytes(&self) -> u64 {
+        self.min_free_mb as u64 * 1024 * 1024
+    }
+}
+
+#[typeshare]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct ConverterOptions {
     pub input_dir: PathBuf,
     pub output_dir: PathBuf,
     pub need_sorting: bool,
     pub ffmpeg_options: FfmpegOptions,
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    #[serde(default)]
+    pub scheduler: SchedulerOptions,
+    #[serde(default)]
+    pub space: SpaceOptions,
+    /// Poster frames and contact sheets of the converted clips, none when `None`
+    #[serde(default)]
+    pub thumbnails: Option<ThumbnailOptions>,
+    /// Extra outputs of every video next to the one of `ffmpeg_options`
+    #[serde(default)]
+    pub profiles: Vec<OutputProfile>,
 }
 
 impl ConverterOptions {
@@ -317,8 +1059,49 @@ impl ConverterOptions {
             output_dir,
             need_sorting,
             ffmpeg_options,
+            conflict_policy: ConflictPolicy::default(),
+            scheduler: SchedulerOptions::default(),
+            space: SpaceOptions::default(),
+            thumbnails: None,
+            profiles: Vec::new(),
         }
     }
+
+    pub fn with_conflict_policy(mut self, conflict_policy: ConflictPolicy) -> Self {
+        self.conflict_policy = conflict_policy;
+        self
+    }
+
+    pub fn with_scheduler(mut self, scheduler: SchedulerOptions) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    pub fn with_space(mut self, space: SpaceOptions) -> Self {
+        self.space = space;
+        self
+    }
+
+    pub fn with_thumbnails(mut self, thumbnails: ThumbnailOptions) -> Self {
+        self.thumbnails = Some(thumbnails);
+        self
+    }
+
+    pub fn with_profile(mut self, profile: OutputProfile) -> Self {
+        self.profiles.push(profile);
+        self
+    }
+
+    /// Number of files every video is converted to.
+    pub fn outputs_per_video(&self) -> usize {
+        1 + self.profiles.len()
+    }
+
+    /// The thumbnails to write, never for an audio-only export.
+    pub fn thumbnail_options(&self) -> Option<ThumbnailOptions> {
+        self.thumbnails
+            .filter(|_| self.ffmpeg_options.audio_export().is_none())
+    }
 }
 
 impl Display for ArgsType<AudioCodec> {
@@ -359,10 +1142,17 @@ impl Display for ArgsType<Resolution> {
 #[cfg(test)]
 mod test {
     use crate::converter::options::Resolution;
-    use crate::types::{ArgsType, AudioCodec, OutputExtension, VideoCodec};
-    use std::{path::PathBuf, str::FromStr};
+    use crate::types::{
+        ArgsType, AudioCodec, AudioExport, AudioFormat, AudioSelection, AudioStream,
+        AudioTrackCodec, Deinterlacer, Filter, FrameRate, FrameRateMode, Keyframes, LoudnessTarget,
+        MediaInfo, OutputExtension, PictureFormat, ScaleMode, StreamMapping, VideoCodec,
+    };
+    use std::{path::PathBuf, str::FromStr, time::Duration};
 
-    use super::{FfmpegOptions, HwAccel};
+    use super::{
+        CineFormQuality, CodecProfile, DnxHrProfile, EncoderPreset, EncoderTune, FfmpegOptions,
+        HwAccel, ProresProfile, RateControl, RetryPolicy,
+    };
 
     #[test]
     fn test_ffmpeg_build() {
@@ -375,6 +1165,19 @@ mod test {
             audio_codec: ArgsType::Custom(AudioCodec::Flac),
             picture_format: ArgsType::MatchSource,
             output_extension: OutputExtension::Default,
+            retry: Default::default(),
+            rate_control: None,
+            preset: None,
+            tune: None,
+            filters: Vec::new(),
+            loudness: None,
+            audio_only: None,
+            streams: None,
+            frame_rate: None,
+            frame_rate_mode: FrameRateMode::Auto,
+            keyframes: None,
+            faststart: false,
+            codec_profile: None,
         };
 
         let args = options.build_with_io(
@@ -388,20 +1191,129 @@ mod test {
                 "-hwaccel",
                 "cuda",
                 "-hwaccel_output_format",
-                "cuda",
+                "auto",
                 "-i",
                 "/s/video/a.mp4",
                 "-vf",
-                "\"scale:1920x1080:flags=lanczos\"",
+                "scale=w=1920:h=1080:flags=lanczos",
                 "-c:v",
                 "h264_nvenc",
-                "-c:v",
+                "-c:a",
                 "flac",
                 "/s/video/a.mkv"
             ]
         )
     }
 
+    #[test]
+    fn mp3_output_is_audio_only() {
+        let options = FfmpegOptions::new(
+            ArgsType::Custom(Resolution::R1080P),
+            Some(HwAccel::Cuda),
+            ArgsType::Custom(AudioCodec::Flac),
+            ArgsType::Custom(VideoCodec::H264NVENC),
+            ArgsType::Custom(256),
+            ArgsType::Custom(8000),
+            ArgsType::MatchSource,
+            OutputExtension::Mp3,
+        );
+
+        assert_eq!(options.extension(), "mp3");
+        assert_eq!(options.encoder_name(), "libmp3lame");
+        assert_eq!(
+            options.build_with_io(PathBuf::from("a.mp4"), PathBuf::from("a.mp3")),
+            [
+                "-i",
+                "a.mp4",
+                "-map",
+                "0:a:0",
+                "-vn",
+                "-sn",
+                "-dn",
+                "-c:a",
+                "libmp3lame",
+                "-b:a",
+                "256k",
+                "-id3v2_version",
+                "3",
+                "a.mp3"
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_replace_the_resolution_scale() {
+        let options = FfmpegOptions::new(
+            ArgsType::Custom(Resolution::R720P),
+            None,
+            ArgsType::MatchSource,
+            ArgsType::Custom(VideoCodec::H265),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::Custom(PictureFormat::Pf42010B),
+            OutputExtension::Mkv,
+        )
+        .with_filter(Filter::Deinterlace(Deinterlacer::Bwdif))
+        .with_filter(Filter::Scale {
+            width: 1920,
+            height: 1080,
+            mode: ScaleMode::Fit,
+        });
+
+        let (args, _, _) = options.build();
+        let vf = args.iter().position(|arg| arg == "-vf").unwrap();
+
+        assert_eq!(
+            args[vf + 1],
+            "bwdif=mode=send_frame:parity=auto:deint=interlaced,\
+             scale=w=1920:h=1080:force_original_aspect_ratio=decrease:force_divisible_by=2:flags=lanczos,\
+             format=yuv420p10le"
+        );
+        assert_eq!(args.iter().filter(|arg| *arg == "-vf").count(), 1);
+    }
+
+    #[test]
+    fn encoder_chain() {
+        let options = FfmpegOptions::new(
+            ArgsType::MatchSource,
+            Some(HwAccel::Cuda),
+            ArgsType::MatchSource,
+            ArgsType::Custom(VideoCodec::H264NVENC),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            OutputExtension::Mkv,
+        )
+        .with_retry(RetryPolicy {
+            fallback: vec![
+                VideoCodec::H264NVENC,
+                VideoCodec::H265NVENC,
+                VideoCodec::H264QSV,
+                VideoCodec::H264,
+            ],
+            ..Default::default()
+        });
+
+        let chain: Vec<_> = options
+            .encoder_chain()
+            .into_iter()
+            .map(|o| (o.video_codec, o.hwaccel))
+            .collect();
+
+        assert_eq!(
+            chain,
+            vec![
+                (ArgsType::Custom(VideoCodec::H264NVENC), Some(HwAccel::Cuda)),
+                (ArgsType::Custom(VideoCodec::H265NVENC), Some(HwAccel::Cuda)),
+                (ArgsType::Custom(VideoCodec::H264QSV), None),
+                (ArgsType::Custom(VideoCodec::H264), None),
+            ]
+        );
+
+        assert_eq!(options.retry.backoff(1), Duration::from_millis(1000));
+        assert_eq!(options.retry.backoff(3), Duration::from_millis(4000));
+    }
+
     #[test]
     fn test_arg() {
         let options = FfmpegOptions::new(
@@ -435,6 +1347,316 @@ mod test {
             ]
         )
     }
+
+    #[test]
+    fn rate_control_takes_precedence_over_bitrate() {
+        let options = FfmpegOptions::new(
+            ArgsType::MatchSource,
+            None,
+            ArgsType::MatchSource,
+            ArgsType::Custom(VideoCodec::H265),
+            ArgsType::MatchSource,
+            ArgsType::Custom(10000),
+            ArgsType::MatchSource,
+            OutputExtension::Mkv,
+        )
+        .with_rate_control(RateControl::Quality { value: 20 })
+        .with_preset(EncoderPreset::Slow)
+        .with_tune(EncoderTune::Grain);
+
+        let args = options.build_with_io(
+            PathBuf::from_str("/s/video/a.mp4").unwrap(),
+            PathBuf::from_str("/s/video/a.mkv").unwrap(),
+        );
+
+        assert_eq!(
+            args,
+            vec![
+                "-i",
+                "/s/video/a.mp4",
+                "-c:v",
+                "libx265",
+                "-crf",
+                "20",
+                "-preset",
+                "slow",
+                "-tune",
+                "grain",
+                "-c:a",
+                "copy",
+                "/s/video/a.mkv"
+            ]
+        )
+    }
+
+    #[test]
+    fn stream_mapping_surrounds_the_codecs() {
+        let options = FfmpegOptions::new(
+            ArgsType::MatchSource,
+            None,
+            ArgsType::Custom(AudioCodec::Ipcm),
+            ArgsType::Custom(VideoCodec::Prores),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            OutputExtension::Mov,
+        )
+        .with_streams(
+            StreamMapping::all()
+                .with_audio(AudioSelection::Indices(vec![0, 1]))
+                .with_audio_codec(AudioTrackCodec {
+                    track: 1,
+                    codec: AudioCodec::Aac,
+                    bitrate: Some(256),
+                }),
+        );
+
+        let args = options.build_with_io(
+            PathBuf::from_str("/s/video/a.mp4").unwrap(),
+            PathBuf::from_str("/s/video/a.mov").unwrap(),
+        );
+
+        assert_eq!(
+            args,
+            vec![
+                "-i",
+                "/s/video/a.mp4",
+                "-map",
+                "0:v",
+                "-map",
+                "0:a:0?",
+                "-map",
+                "0:a:1?",
+                "-map",
+                "0:d:m:timecode?",
+                "-map_chapters",
+                "0",
+                "-map_metadata",
+                "0",
+                "-c:v",
+                "prores",
+                "-c:a",
+                "pcm_s24be",
+                "-c:a:1",
+                "aac",
+                "-b:a:1",
+                "256k",
+                "-c:d",
+                "copy",
+                "/s/video/a.mov"
+            ]
+        );
+
+        // Audio-only exports pick their own track
+        let export = options.with_audio_only(AudioExport::new(AudioFormat::Flac));
+        assert!(!export.build().0.contains(&"-map_chapters".to_string()));
+    }
+
+    #[test]
+    fn loudness_keeps_the_sample_rate() {
+        let track = |language: &str, sample_rate| AudioStream {
+            index: 1,
+            codec: "pcm_s16le".into(),
+            sample_rate: Some(sample_rate),
+            channels: 2,
+            channel_layout: None,
+            bit_rate: None,
+            language: Some(language.into()),
+        };
+        let media = MediaInfo {
+            format_name: "mov,mp4,m4a,3gp,3g2,mj2".into(),
+            duration: Some(Duration::from_secs(10)),
+            size: None,
+            bit_rate: None,
+            creation_time: None,
+            video: Vec::new(),
+            audio: vec![track("eng", 44100), track("jpn", 32000)],
+            subtitles: Vec::new(),
+        };
+        let options = FfmpegOptions::new(
+            ArgsType::MatchSource,
+            None,
+            ArgsType::Custom(AudioCodec::Flac),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            OutputExtension::Mkv,
+        )
+        .with_loudness(LoudnessTarget::default());
+
+        // loudnorm outputs 192 kHz, FLAC would keep it
+        assert_eq!(
+            options.build_measured(None, Some(&media)).0,
+            [
+                "-i",
+                "-c:v",
+                "copy",
+                "-c:a",
+                "flac",
+                "-af",
+                "loudnorm=I=-23.0:TP=-1.0:LRA=7.0:print_format=json,aresample=44100"
+            ]
+        );
+
+        // Only the measured track is normalized, at its own rate
+        let japanese = options.clone().with_streams(
+            StreamMapping::all().with_audio(AudioSelection::Languages(vec!["jpn".into()])),
+        );
+        assert_eq!(japanese.loudness_track(Some(&media)), Some(1));
+        let args = japanese.build_measured(None, Some(&media)).0;
+        assert!(args.windows(2).any(|w| w[0] == "-filter:a:0"
+            && w[1] == "loudnorm=I=-23.0:TP=-1.0:LRA=7.0:print_format=json,aresample=32000"));
+        assert!(!args.contains(&"-af".to_string()));
+
+        // The export's own rate wins
+        let export = options.with_audio_only(AudioExport {
+            stream: 1,
+            sample_rate: Some(96000),
+            ..AudioExport::new(AudioFormat::Wav)
+        });
+        assert_eq!(export.loudness_track(Some(&media)), Some(1));
+        let args = export.build_measured(None, Some(&media)).0;
+        assert!(args
+            .last()
+            .is_some_and(|filter| filter.ends_with(",aresample=96000")));
+    }
+
+    #[test]
+    fn constant_frame_rate_for_editing() {
+        let options = FfmpegOptions::new(
+            ArgsType::MatchSource,
+            None,
+            ArgsType::MatchSource,
+            ArgsType::Custom(VideoCodec::H264),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            OutputExtension::Mp4,
+        )
+        .with_frame_rate(FrameRate::ntsc(30))
+        .with_frame_rate_mode(FrameRateMode::Constant)
+        .with_keyframes(Keyframes::Interval(30))
+        .with_faststart(true);
+
+        assert_eq!(
+            options.build().0,
+            vec![
+                "-i",
+                "-c:v",
+                "libx264",
+                "-r",
+                "30000/1001",
+                "-fps_mode",
+                "cfr",
+                "-g",
+                "30",
+                "-keyint_min",
+                "30",
+                "-sc_threshold",
+                "0",
+                "-c:a",
+                "copy",
+                "-movflags",
+                "+faststart"
+            ]
+        );
+
+        // ProRes has no GOP, the frame rate still applies
+        let prores = FfmpegOptions {
+            video_codec: ArgsType::Custom(VideoCodec::Prores),
+            output_extension: OutputExtension::Mov,
+            ..options.clone()
+        };
+        let args = prores.build().0;
+        assert!(args.windows(2).any(|w| w == ["-fps_mode", "cfr"]));
+        assert!(!args.contains(&"-g".to_string()));
+
+        // A stream copy can't change the timing, Matroska has no faststart
+        let copy = FfmpegOptions {
+            video_codec: ArgsType::MatchSource,
+            output_extension: OutputExtension::Mkv,
+            ..options
+        };
+        assert_eq!(copy.build().0, ["-i", "-c:v", "copy", "-c:a", "copy"]);
+    }
+
+    #[test]
+    fn stream_copy_is_not_filtered() {
+        let options = FfmpegOptions::new(
+            ArgsType::Custom(Resolution::R720P),
+            None,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::Custom(PictureFormat::Pf4208B),
+            OutputExtension::Mkv,
+        )
+        .with_filter(Filter::Deinterlace(Deinterlacer::Yadif));
+
+        assert!(options.filter_graph().render().is_none());
+        assert_eq!(options.build().0, ["-i", "-c:v", "copy", "-c:a", "copy"]);
+    }
+
+    #[test]
+    fn intermediate_codec_profiles() {
+        let options = FfmpegOptions::new(
+            ArgsType::MatchSource,
+            None,
+            ArgsType::MatchSource,
+            ArgsType::Custom(VideoCodec::Prores),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::Custom(PictureFormat::Pf4208B),
+            OutputExtension::Mov,
+        )
+        .with_codec_profile(CodecProfile::Prores(ProresProfile::Hq));
+
+        // The profile picks the encoder and overrides the pixel format
+        assert_eq!(
+            options.build().0,
+            [
+                "-i",
+                "-vf",
+                "format=yuv422p10le",
+                "-c:v",
+                "prores_ks",
+                "-profile:v",
+                "hq",
+                "-c:a",
+                "copy"
+            ]
+        );
+        assert_eq!(options.encoder_name(), "prores_ks");
+
+        // DNxHR needs a profile, HQ is assumed
+        let dnxhr = FfmpegOptions {
+            video_codec: ArgsType::Custom(VideoCodec::DnxHr),
+            ..options.clone()
+        };
+        assert_eq!(
+            dnxhr.video_profile(),
+            Some(CodecProfile::DnxHr(DnxHrProfile::Hq))
+        );
+        assert_eq!(dnxhr.pixel_format(), Some(PictureFormat::Pf4228B));
+
+        let cineform = FfmpegOptions {
+            video_codec: ArgsType::Custom(VideoCodec::CineForm),
+            codec_profile: Some(CodecProfile::CineForm(CineFormQuality::Film2)),
+            ..options.clone()
+        };
+        let args = cineform.build().0;
+        assert!(args.windows(2).any(|w| w == ["-quality", "film2"]));
+
+        // A profile of another codec is left out
+        let h264 = FfmpegOptions {
+            video_codec: ArgsType::Custom(VideoCodec::H264),
+            ..options
+        };
+        assert_eq!(h264.video_profile(), None);
+        assert_eq!(h264.pixel_format(), Some(PictureFormat::Pf4208B));
+    }
 }
 
 impl From<u32> for ArgsType<u32> {
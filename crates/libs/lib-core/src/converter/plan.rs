@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use lib_sorter::Bucket;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use crate::exec::{batches, run_args, targets};
+
+use super::{session_tags, FfmpegOptions, OutputProfile};
+
+/// An XML sidecar copied into the bucket's `xml` folder.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyPlan {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    /// The target is already there, the conflict policy decides what happens to it
+    pub exists: bool,
+}
+
+/// A video encoded by ffmpeg.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodePlan {
+    pub source: PathBuf,
+    /// `None` for the output of the main options
+    #[serde(default)]
+    pub profile: Option<String>,
+    pub output: PathBuf,
+    /// The whole run, shared with the other outputs written along with this one. The sources
+    /// aren't probed for a plan, so the stream mapping and the loudness normalization that
+    /// depend on them are left out, and ffmpeg really writes to a temporary file first.
+    pub args: Vec<String>,
+    /// The output is already there, the conflict policy decides what happens to it
+    pub exists: bool,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketPlan {
+    pub name: String,
+    pub output_dir: PathBuf,
+    pub copies: Vec<CopyPlan>,
+    pub encodes: Vec<EncodePlan>,
+}
+
+impl BucketPlan {
+    pub(crate) fn new(
+        name: &str,
+        output_dir: PathBuf,
+        bucket: &Bucket,
+        ffmpeg_options: &FfmpegOptions,
+        profiles: &[OutputProfile],
+        threads: Option<u32>,
+    ) -> Self {
+        let xml_dir = output_dir.join("xml");
+
+        let copies = bucket
+            .xml_files()
+            .iter()
+            .map(|source| {
+                let target = xml_dir.join(source.file_name().unwrap());
+                CopyPlan {
+                    source: source.clone(),
+                    exists: target.exists(),
+                    target,
+                }
+            })
+            .collect();
+
+        let encodes = bucket
+            .video_files()
+            .iter()
+            .flat_map(|file| {
+                let source = file.path_with_extension();
+                let tags = session_tags(&source, None, name);
+
+                batches(targets(file, &output_dir, ffmpeg_options, profiles))
+                    .into_iter()
+                    .flat_map(|batch| {
+                        let outputs: Vec<_> = batch
+                            .iter()
+                            .map(|target| (&target.options, target.output.as_path()))
+                            .collect();
+                        let args = run_args(&source, &outputs, &tags, None, None, threads);
+
+                        batch
+                            .into_iter()
+                            .map(|target| EncodePlan {
+                                source: source.clone(),
+                                profile: target.profile,
+                                args: args.clone(),
+                                exists: target.output.exists(),
+                                output: target.output,
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            output_dir,
+            copies,
+            encodes,
+        }
+    }
+}
+
+/// What [`super::Converter::start_conversion`] would do with a set of options.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionPlan {
+    pub input_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub buckets: Vec<BucketPlan>,
+}
+
+impl ConversionPlan {
+    /// Every path the conversion would write to that already exists.
+    pub fn existing_outputs(&self) -> impl Iterator<Item = &Path> {
+        self.buckets.iter().flat_map(|bucket| {
+            let copies = bucket
+                .copies
+                .iter()
+                .filter(|c| c.exists)
+                .map(|c| c.target.as_path());
+            let encodes = bucket
+                .encodes
+                .iter()
+                .filter(|e| e.exists)
+                .map(|e| e.output.as_path());
+            copies.chain(encodes)
+        })
+    }
+}
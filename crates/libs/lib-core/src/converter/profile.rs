@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use lib_utils::file::FileExt;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::FfmpegOptions;
+
+/// An extra output of every video next to the main one, e.g. an editing proxy.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputProfile {
+    /// Shown in the progress and the reports, e.g. `proxy`
+    pub name: String,
+    pub ffmpeg_options: FfmpegOptions,
+    /// Folder inside the bucket the outputs go to, the bucket itself when `None`
+    #[serde(default)]
+    pub subfolder: Option<String>,
+    /// Appended to the file name, e.g. `_proxy` gives `C0001_proxy.mov`
+    #[serde(default)]
+    pub suffix: String,
+}
+
+impl OutputProfile {
+    pub fn new(name: impl Into<String>, ffmpeg_options: FfmpegOptions) -> Self {
+        Self {
+            name: name.into(),
+            ffmpeg_options,
+            subfolder: None,
+            suffix: String::new(),
+        }
+    }
+
+    pub fn with_subfolder(mut self, subfolder: impl Into<String>) -> Self {
+        self.subfolder = Some(subfolder.into());
+        self
+    }
+
+    pub fn with_suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    /// Folder the outputs of the profile go to inside `bucket_dir`.
+    pub fn output_dir(&self, bucket_dir: &Path) -> PathBuf {
+        match &self.subfolder {
+            Some(subfolder) => bucket_dir.join(subfolder),
+            None => bucket_dir.to_path_buf(),
+        }
+    }
+
+    /// Where the profile's output of `source` ends up inside `bucket_dir`.
+    pub fn output_path(&self, source: &FileExt, bucket_dir: &Path) -> PathBuf {
+        let file_name = source.file_name();
+        let stem = Path::new(file_name.as_ref())
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+
+        self.output_dir(bucket_dir).join(format!(
+            "{stem}{}.{}",
+            self.suffix,
+            self.ffmpeg_options.extension()
+        ))
+    }
+}
+
+/// Journal entry of the output of `source` for `profile`, the source itself for the main output.
+pub(crate) fn journal_key(source: &Path, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(profile) => {
+            let name = source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            source.with_file_name(format!("{name}#{profile}"))
+        }
+        None => source.to_path_buf(),
+    }
+}
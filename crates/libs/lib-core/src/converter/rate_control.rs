@@ -0,0 +1,229 @@
+use lib_utils::arg::Arg;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::VideoCodec;
+
+/// How the video encoder spends bits. Bitrates are in kbit/s.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "mode", content = "settings", rename_all = "camelCase")]
+pub enum RateControl {
+    /// Constant bitrate
+    Cbr { bitrate: u32 },
+    /// Variable bitrate averaging `bitrate`, never above `max_bitrate` over `buffer_size`
+    #[serde(rename_all = "camelCase")]
+    Vbr {
+        bitrate: u32,
+        max_bitrate: u32,
+        buffer_size: u32,
+    },
+    /// Constant quality, lower is better. CRF for x264/x265, CQ for NVENC, `global_quality` for
+    /// QSV and QP for AMF
+    Quality { value: u8 },
+}
+
+impl RateControl {
+    /// Flags selecting this rate control on `codec`. ProRes, CineForm and DNxHR pick their
+    /// bitrate from their profile, they get none.
+    pub(crate) fn args(&self, codec: VideoCodec) -> Vec<String> {
+        let mut args = Vec::new();
+        let kbps = |value: u32| value.to_string() + "k";
+
+        match (self, codec) {
+            (_, VideoCodec::CineForm | VideoCodec::Prores | VideoCodec::DnxHr) => {}
+
+            (RateControl::Cbr { bitrate }, _) => {
+                args.extend(rc_mode(codec, "cbr", "cbr"));
+                args.extend(Arg::new("b:v").value(kbps(*bitrate)).build());
+                args.extend(Arg::new("minrate").value(kbps(*bitrate)).build());
+                args.extend(Arg::new("maxrate").value(kbps(*bitrate)).build());
+                args.extend(Arg::new("bufsize").value(kbps(bitrate * 2)).build());
+            }
+
+            (
+                RateControl::Vbr {
+                    bitrate,
+                    max_bitrate,
+                    buffer_size,
+                },
+                _,
+            ) => {
+                args.extend(rc_mode(codec, "vbr", "vbr_peak"));
+                args.extend(Arg::new("b:v").value(kbps(*bitrate)).build());
+                args.extend(Arg::new("maxrate").value(kbps(*max_bitrate)).build());
+                args.extend(Arg::new("bufsize").value(kbps(*buffer_size)).build());
+            }
+
+            (RateControl::Quality { value }, VideoCodec::H264 | VideoCodec::H265) => {
+                args.extend(Arg::new("crf").value(value.to_string()).build());
+            }
+            (RateControl::Quality { value }, VideoCodec::H264NVENC | VideoCodec::H265NVENC) => {
+                args.extend(Arg::new("rc").value("vbr").build());
+                args.extend(Arg::new("cq").value(value.to_string()).build());
+                // Let the quality alone decide the bitrate
+                args.extend(Arg::new("b:v").value("0").build());
+            }
+            (RateControl::Quality { value }, VideoCodec::H264QSV | VideoCodec::H265QSV) => {
+                args.extend(Arg::new("global_quality").value(value.to_string()).build());
+            }
+            (RateControl::Quality { value }, VideoCodec::H264AMF | VideoCodec::H265AMF) => {
+                args.extend(Arg::new("rc").value("cqp").build());
+                args.extend(Arg::new("qp_i").value(value.to_string()).build());
+                args.extend(Arg::new("qp_p").value(value.to_string()).build());
+                if codec == VideoCodec::H264AMF {
+                    args.extend(Arg::new("qp_b").value(value.to_string()).build());
+                }
+            }
+        }
+
+        args
+    }
+}
+
+/// `-rc` for the hardware encoders that need it, software and QSV infer the mode from the rates.
+fn rc_mode(codec: VideoCodec, nvenc: &str, amf: &str) -> Vec<String> {
+    match codec {
+        VideoCodec::H264NVENC | VideoCodec::H265NVENC => Arg::new("rc").value(nvenc).build(),
+        VideoCodec::H264AMF | VideoCodec::H265AMF => Arg::new("rc").value(amf).build(),
+        _ => Vec::new(),
+    }
+}
+
+/// Speed against compression, mapped to each encoder's own preset names.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EncoderPreset {
+    Fastest,
+    Fast,
+    Medium,
+    Slow,
+    Slowest,
+}
+
+impl EncoderPreset {
+    pub(crate) fn args(&self, codec: VideoCodec) -> Vec<String> {
+        let index = *self as usize;
+
+        match codec {
+            VideoCodec::H264 | VideoCodec::H265 => Arg::new("preset")
+                .value(["ultrafast", "fast", "medium", "slow", "veryslow"][index])
+                .build(),
+            VideoCodec::H264NVENC | VideoCodec::H265NVENC => Arg::new("preset")
+                .value(["p1", "p3", "p4", "p6", "p7"][index])
+                .build(),
+            VideoCodec::H264QSV | VideoCodec::H265QSV => Arg::new("preset")
+                .value(["veryfast", "fast", "medium", "slow", "veryslow"][index])
+                .build(),
+            VideoCodec::H264AMF | VideoCodec::H265AMF => Arg::new("quality")
+                .value(["speed", "speed", "balanced", "quality", "quality"][index])
+                .build(),
+            VideoCodec::CineForm | VideoCodec::Prores | VideoCodec::DnxHr => Vec::new(),
+        }
+    }
+}
+
+/// What the source looks like, for the encoders that can tune for it.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EncoderTune {
+    Film,
+    Animation,
+    Grain,
+    /// Slides and screen recordings
+    StillImage,
+    FastDecode,
+    ZeroLatency,
+}
+
+impl EncoderTune {
+    /// `-tune` for `codec`, nothing when the encoder has no matching tune.
+    pub(crate) fn args(&self, codec: VideoCodec) -> Vec<String> {
+        let tune = match (codec, self) {
+            (VideoCodec::H264, tune) => Some(match tune {
+                EncoderTune::Film => "film",
+                EncoderTune::Animation => "animation",
+                EncoderTune::Grain => "grain",
+                EncoderTune::StillImage => "stillimage",
+                EncoderTune::FastDecode => "fastdecode",
+                EncoderTune::ZeroLatency => "zerolatency",
+            }),
+            (VideoCodec::H265, EncoderTune::Animation) => Some("animation"),
+            (VideoCodec::H265, EncoderTune::Grain) => Some("grain"),
+            (VideoCodec::H265, EncoderTune::FastDecode) => Some("fastdecode"),
+            (VideoCodec::H265, EncoderTune::ZeroLatency) => Some("zerolatency"),
+            (VideoCodec::H264NVENC | VideoCodec::H265NVENC, EncoderTune::ZeroLatency) => {
+                Some("ull")
+            }
+            (VideoCodec::H264NVENC | VideoCodec::H265NVENC, EncoderTune::FastDecode) => None,
+            (VideoCodec::H264NVENC | VideoCodec::H265NVENC, _) => Some("hq"),
+            _ => None,
+        };
+
+        match tune {
+            Some(tune) => Arg::new("tune").value(tune).build(),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EncoderPreset, EncoderTune, RateControl};
+    use crate::types::VideoCodec;
+
+    #[test]
+    fn quality_maps_to_each_encoder() {
+        let quality = RateControl::Quality { value: 23 };
+
+        assert_eq!(quality.args(VideoCodec::H265), ["-crf", "23"]);
+        assert_eq!(
+            quality.args(VideoCodec::H264NVENC),
+            ["-rc", "vbr", "-cq", "23", "-b:v", "0"]
+        );
+        assert_eq!(quality.args(VideoCodec::H264QSV), ["-global_quality", "23"]);
+        assert_eq!(
+            quality.args(VideoCodec::H265AMF),
+            ["-rc", "cqp", "-qp_i", "23", "-qp_p", "23"]
+        );
+        assert!(quality.args(VideoCodec::Prores).is_empty());
+    }
+
+    #[test]
+    fn bitrate_modes() {
+        let vbr = RateControl::Vbr {
+            bitrate: 8000,
+            max_bitrate: 12000,
+            buffer_size: 16000,
+        };
+        assert_eq!(
+            vbr.args(VideoCodec::H264AMF),
+            ["-rc", "vbr_peak", "-b:v", "8000k", "-maxrate", "12000k", "-bufsize", "16000k"]
+        );
+
+        let cbr = RateControl::Cbr { bitrate: 5000 };
+        assert_eq!(
+            cbr.args(VideoCodec::H264),
+            ["-b:v", "5000k", "-minrate", "5000k", "-maxrate", "5000k", "-bufsize", "10000k"]
+        );
+    }
+
+    #[test]
+    fn presets_and_tunes() {
+        assert_eq!(
+            EncoderPreset::Slow.args(VideoCodec::H265NVENC),
+            ["-preset", "p6"]
+        );
+        assert_eq!(
+            EncoderPreset::Fastest.args(VideoCodec::H264AMF),
+            ["-quality", "speed"]
+        );
+        assert_eq!(
+            EncoderTune::StillImage.args(VideoCodec::H264),
+            ["-tune", "stillimage"]
+        );
+        assert!(EncoderTune::StillImage.args(VideoCodec::H265).is_empty());
+    }
+}
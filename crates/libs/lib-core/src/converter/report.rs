@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::{LoudnessReport, Thumbnails};
+use crate::journal::EncodeAttempt;
+
+/// A video that was encoded, with the encoder that produced it.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodedFile {
+    pub source: PathBuf,
+    pub output: PathBuf,
+    pub encoder: String,
+    /// Name of the [`super::OutputProfile`], `None` for the main output
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// `None` when the audio wasn't normalized
+    pub loudness: Option<LoudnessReport>,
+    /// `None` when no thumbnails were asked for
+    #[serde(default)]
+    pub thumbnails: Option<Thumbnails>,
+}
+
+/// A file that couldn't be copied or encoded.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileFailure {
+    pub source: PathBuf,
+    /// Name of the [`super::OutputProfile`] that failed, `None` for the main output
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Exit code of the last ffmpeg run, `None` when there was none or it was ended by a signal
+    pub exit_code: Option<i32>,
+    /// Why it failed, the end of ffmpeg's log for encodes
+    pub reason: String,
+    /// Every ffmpeg run, empty when ffmpeg wasn't involved
+    pub attempts: Vec<EncodeAttempt>,
+}
+
+impl FileFailure {
+    pub(crate) fn new(source: PathBuf, reason: impl Into<String>) -> Self {
+        Self {
+            source,
+            profile: None,
+            exit_code: None,
+            reason: reason.into(),
+            attempts: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// The last line of the reason, usually the actual error.
+    pub fn summary(&self) -> &str {
+        self.reason.lines().last().unwrap_or_default()
+    }
+}
+
+/// What happened to a single file of a bucket.
+#[derive(Debug)]
+pub(crate) enum FileOutcome {
+    Copied(PathBuf),
+    Encoded(EncodedFile),
+    /// Already done by an earlier run or kept by the conflict policy
+    Skipped(PathBuf),
+    Failed(FileFailure),
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketReport {
+    pub name: String,
+    pub output_dir: PathBuf,
+    pub copied: Vec<PathBuf>,
+    pub encoded: Vec<EncodedFile>,
+    pub skipped: Vec<PathBuf>,
+    pub failures: Vec<FileFailure>,
+    /// The bucket couldn't be processed at all, e.g. its folder couldn't be created
+    pub error: Option<String>,
+    /// Where the bucket's [`crate::types::Manifest`] was written
+    pub manifest: Option<PathBuf>,
+}
+
+impl BucketReport {
+    pub(crate) fn new(name: &str, output_dir: PathBuf) -> Self {
+        Self {
+            name: name.to_string(),
+            output_dir,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn record(&mut self, outcome: FileOutcome) {
+        match outcome {
+            FileOutcome::Copied(source) => self.copied.push(source),
+            FileOutcome::Encoded(file) => self.encoded.push(file),
+            FileOutcome::Skipped(source) => self.skipped.push(source),
+            FileOutcome::Failed(failure) => self.failures.push(failure),
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty() && self.error.is_none()
+    }
+}
+
+/// What [`super::Converter::start_conversion`] did, file by file.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionReport {
+    pub output_dir: PathBuf,
+    pub buckets: Vec<BucketReport>,
+    /// The conversion was stopped before every file was processed
+    pub cancelled: bool,
+}
+
+impl ConversionReport {
+    /// Every failed file with the name of its bucket.
+    pub fn failures(&self) -> impl Iterator<Item = (&str, &FileFailure)> {
+        self.buckets.iter().flat_map(|bucket| {
+            bucket
+                .failures
+                .iter()
+                .map(|failure| (bucket.name.as_str(), failure))
+        })
+    }
+
+    pub fn is_success(&self) -> bool {
+        !self.cancelled && self.buckets.iter().all(|bucket| bucket.is_success())
+    }
+}
@@ -0,0 +1,356 @@
+use lib_utils::arg::Arg;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+use super::{AudioCodec, OutputExtension};
+use crate::probe::{MediaInfo, SubtitleStream};
+
+/// Which audio tracks of the source end up in the output.
+#[typeshare]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", content = "content", rename_all = "camelCase")]
+pub enum AudioSelection {
+    #[default]
+    All,
+    /// Audio tracks counted from 0, in the order of the source
+    Indices(Vec<u32>),
+    /// ISO 639-2 codes as tagged in the source, e.g. `eng`
+    Languages(Vec<String>),
+}
+
+/// Codec of a single audio track of the output, instead of the one of the options.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTrackCodec {
+    /// Audio track of the output counted from 0, after the selection
+    pub track: u32,
+    pub codec: AudioCodec,
+    /// In kbit/s, lossy codecs only
+    #[serde(default)]
+    pub bitrate: Option<u32>,
+}
+
+/// Streams of the source written to the output. Without a mapping ffmpeg keeps a single video
+/// and a single audio stream.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamMapping {
+    #[serde(default)]
+    pub audio: AudioSelection,
+    #[serde(default)]
+    pub audio_codecs: Vec<AudioTrackCodec>,
+    /// Copied into Matroska, `mov_text` ones as SubRip. MOV and MP4 only get the text ones found
+    /// by ffprobe, as `mov_text`
+    pub subtitles: bool,
+    /// The timecode track, only MOV and MP4 hold it. Camera metadata tracks such as `rtmd` or
+    /// `gpmd` are left out, the muxers reject them. Matroska keeps the timecode through
+    /// `metadata`
+    pub data: bool,
+    pub chapters: bool,
+    /// Global tags of the source, e.g. `creation_time` and `timecode`
+    pub metadata: bool,
+}
+
+impl Default for StreamMapping {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl StreamMapping {
+    /// Every stream the container can hold, with the chapters and the tags.
+    pub fn all() -> Self {
+        Self {
+            audio: AudioSelection::All,
+            audio_codecs: Vec::new(),
+            subtitles: true,
+            data: true,
+            chapters: true,
+            metadata: true,
+        }
+    }
+
+    pub fn with_audio(mut self, audio: AudioSelection) -> Self {
+        self.audio = audio;
+        self
+    }
+
+    pub fn with_audio_codec(mut self, codec: AudioTrackCodec) -> Self {
+        self.audio_codecs.push(codec);
+        self
+    }
+
+    /// Audio track of the source that becomes the first one of the output, counted from 0. The
+    /// languages are looked up in `media`, the first track is assumed without it.
+    pub(crate) fn first_audio_track(&self, media: Option<&MediaInfo>) -> u32 {
+        match &self.audio {
+            AudioSelection::All => 0,
+            AudioSelection::Indices(indices) => indices.first().copied().unwrap_or_default(),
+            AudioSelection::Languages(languages) => {
+                let tracks = media
+                    .map(|media| media.audio.as_slice())
+                    .unwrap_or_default();
+                languages
+                    .iter()
+                    .find_map(|language| {
+                        tracks
+                            .iter()
+                            .position(|track| track.language.as_ref() == Some(language))
+                    })
+                    .unwrap_or_default() as u32
+            }
+        }
+    }
+
+    /// `-map` flags of the streams, written before the codecs. `media` tells the text subtitles
+    /// apart.
+    pub(crate) fn map_args(
+        &self,
+        container: OutputExtension,
+        media: Option<&MediaInfo>,
+    ) -> Vec<String> {
+        let mut args = Arg::new("map").value("0:v").build();
+
+        // Optional so a source without the track or the language still converts
+        let audio: Vec<String> = match &self.audio {
+            AudioSelection::All => vec!["0:a?".to_string()],
+            AudioSelection::Indices(indices) => indices
+                .iter()
+                .map(|index| format!("0:a:{index}?"))
+                .collect(),
+            AudioSelection::Languages(languages) => languages
+                .iter()
+                .map(|language| format!("0:a:m:language:{language}?"))
+                .collect(),
+        };
+        for stream in audio {
+            args.extend(Arg::new("map").value(stream).build());
+        }
+
+        if self.subtitles {
+            match container {
+                OutputExtension::Default | OutputExtension::Mkv => {
+                    args.extend(Arg::new("map").value("0:s?").build());
+                }
+                OutputExtension::Mov | OutputExtension::Mp4 => {
+                    for subtitle in text_subtitles(media) {
+                        args.extend(
+                            Arg::new("map")
+                                .value(format!("0:{}", subtitle.index))
+                                .build(),
+                        );
+                    }
+                }
+                OutputExtension::Mp3 => {}
+            }
+        }
+        // The demuxer tags timecode tracks with their start, the other data tracks have none
+        if self.data && holds_data(container) {
+            args.extend(Arg::new("map").value("0:d:m:timecode?").build());
+        }
+
+        args.extend(
+            Arg::new("map_chapters")
+                .value(if self.chapters { "0" } else { "-1" })
+                .build(),
+        );
+        args.extend(
+            Arg::new("map_metadata")
+                .value(if self.metadata { "0" } else { "-1" })
+                .build(),
+        );
+
+        args
+    }
+
+    /// Codecs of the subtitle and data streams and the per track audio codecs, written after the
+    /// codecs of the options so they win.
+    pub(crate) fn codec_args(
+        &self,
+        container: OutputExtension,
+        media: Option<&MediaInfo>,
+    ) -> Vec<String> {
+        let mut args = Vec::new();
+
+        for track in self.audio_codecs.iter() {
+            let index = track.track;
+            args.extend(
+                Arg::new(format!("c:a:{index}"))
+                    .value(track.codec.to_string())
+                    .build(),
+            );
+            if let Some(bitrate) = track.bitrate.filter(|_| track.codec.is_lossy()) {
+                args.extend(
+                    Arg::new(format!("b:a:{index}"))
+                        .value(bitrate.to_string() + "k")
+                        .build(),
+                );
+            }
+        }
+
+        if self.subtitles {
+            match container {
+                OutputExtension::Default | OutputExtension::Mkv => {
+                    args.extend(Arg::new("c:s").value("copy").build());
+                    // Matroska has no mov_text, every other subtitle is copied as it is
+                    let subtitles = media.map(|m| m.subtitles.as_slice()).unwrap_or_default();
+                    for (track, subtitle) in subtitles.iter().enumerate() {
+                        if subtitle.codec == "mov_text" {
+                            args.extend(Arg::new(format!("c:s:{track}")).value("srt").build());
+                        }
+                    }
+                }
+                OutputExtension::Mov | OutputExtension::Mp4 => {
+                    if text_subtitles(media).next().is_some() {
+                        args.extend(Arg::new("c:s").value("mov_text").build());
+                    }
+                }
+                OutputExtension::Mp3 => {}
+            }
+        }
+        if self.data && holds_data(container) {
+            args.extend(Arg::new("c:d").value("copy").build());
+        }
+
+        args
+    }
+}
+
+/// Subtitles `mov_text` can hold, none without `media`.
+fn text_subtitles(media: Option<&MediaInfo>) -> impl Iterator<Item = &SubtitleStream> {
+    media
+        .into_iter()
+        .flat_map(|media| media.subtitles.iter())
+        .filter(|subtitle| subtitle.is_text())
+}
+
+fn holds_data(container: OutputExtension) -> bool {
+    matches!(container, OutputExtension::Mov | OutputExtension::Mp4)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AudioSelection, AudioTrackCodec, StreamMapping};
+    use crate::converter::{AudioCodec, OutputExtension};
+    use crate::probe::{MediaInfo, SubtitleStream};
+
+    /// A source with SubRip, PGS and mov_text subtitles as streams 3, 4 and 5.
+    fn media() -> MediaInfo {
+        let subtitle = |index, codec: &str| SubtitleStream {
+            index,
+            codec: codec.into(),
+            language: None,
+        };
+
+        MediaInfo {
+            format_name: "matroska,webm".into(),
+            duration: None,
+            size: None,
+            bit_rate: None,
+            creation_time: None,
+            video: Vec::new(),
+            audio: Vec::new(),
+            subtitles: vec![
+                subtitle(3, "subrip"),
+                subtitle(4, "hdmv_pgs_subtitle"),
+                subtitle(5, "mov_text"),
+            ],
+        }
+    }
+
+    #[test]
+    fn maps_what_the_container_holds() {
+        let all = StreamMapping::all();
+        let media = media();
+
+        // Text subtitles only, and the timecode among the data tracks
+        assert_eq!(
+            all.map_args(OutputExtension::Mov, Some(&media)),
+            [
+                "-map",
+                "0:v",
+                "-map",
+                "0:a?",
+                "-map",
+                "0:3",
+                "-map",
+                "0:5",
+                "-map",
+                "0:d:m:timecode?",
+                "-map_chapters",
+                "0",
+                "-map_metadata",
+                "0"
+            ]
+        );
+        assert_eq!(
+            all.codec_args(OutputExtension::Mp4, Some(&media)),
+            ["-c:s", "mov_text", "-c:d", "copy"]
+        );
+
+        // Without ffprobe the subtitles can't be told apart
+        let args = all.map_args(OutputExtension::Mp4, None);
+        assert!(!args
+            .iter()
+            .any(|arg| arg.starts_with("0:s") || arg == "0:3"));
+        assert_eq!(all.codec_args(OutputExtension::Mp4, None), ["-c:d", "copy"]);
+
+        // Matroska holds every subtitle but mov_text, and no data tracks
+        let args = all.map_args(OutputExtension::Mkv, Some(&media));
+        assert!(args.windows(2).any(|w| w == ["-map", "0:s?"]));
+        assert!(!args.iter().any(|arg| arg.starts_with("0:d")));
+        assert_eq!(
+            all.codec_args(OutputExtension::Mkv, Some(&media)),
+            ["-c:s", "copy", "-c:s:2", "srt"]
+        );
+    }
+
+    #[test]
+    fn selects_and_overrides_audio_tracks() {
+        let mapping = StreamMapping {
+            subtitles: false,
+            data: false,
+            chapters: false,
+            metadata: false,
+            ..StreamMapping::all()
+        }
+        .with_audio(AudioSelection::Languages(vec!["eng".into(), "jpn".into()]))
+        .with_audio_codec(AudioTrackCodec {
+            track: 1,
+            codec: AudioCodec::Aac,
+            bitrate: Some(192),
+        })
+        .with_audio_codec(AudioTrackCodec {
+            track: 0,
+            codec: AudioCodec::Flac,
+            bitrate: Some(192),
+        });
+
+        assert_eq!(
+            mapping.map_args(OutputExtension::Mkv, None),
+            [
+                "-map",
+                "0:v",
+                "-map",
+                "0:a:m:language:eng?",
+                "-map",
+                "0:a:m:language:jpn?",
+                "-map_chapters",
+                "-1",
+                "-map_metadata",
+                "-1"
+            ]
+        );
+        // Lossless codecs take no bitrate
+        assert_eq!(
+            mapping.codec_args(OutputExtension::Mkv, None),
+            ["-c:a:1", "aac", "-b:a:1", "192k", "-c:a:0", "flac"]
+        );
+
+        let indices = StreamMapping::all().with_audio(AudioSelection::Indices(vec![0, 3]));
+        let args = indices.map_args(OutputExtension::Mp4, None);
+        assert!(args.windows(2).any(|w| w == ["-map", "0:a:3?"]));
+    }
+}
@@ -0,0 +1,99 @@
+use std::{fs, path::Path};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use lib_utils::arg::Arg;
+
+use crate::probe::MediaInfo;
+
+/// Tags of a converted video : the capture date of `source` and the session of `bucket`, e.g.
+/// `title=241106B` and `comment=Session B`. Converted files then sort like their sources.
+pub(crate) fn session_tags(source: &Path, media: Option<&MediaInfo>, bucket: &str) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if let Some(creation_time) = creation_time(source, media) {
+        args.extend(
+            Arg::new("metadata")
+                .value(format!("creation_time={creation_time}"))
+                .build(),
+        );
+    }
+
+    args.extend(
+        Arg::new("metadata")
+            .value(format!("title={bucket}"))
+            .build(),
+    );
+
+    if let Some(session) = session_letter(bucket) {
+        args.extend(
+            Arg::new("metadata")
+                .value(format!("comment=Session {session}"))
+                .build(),
+        );
+    }
+
+    args
+}
+
+/// The camera's `creation_time`, the modification time of `source` when it has none.
+fn creation_time(source: &Path, media: Option<&MediaInfo>) -> Option<String> {
+    if let Some(creation_time) = media.and_then(|media| media.creation_time.clone()) {
+        return Some(creation_time);
+    }
+
+    let modified = fs::metadata(source).and_then(|m| m.modified()).ok()?;
+    Some(DateTime::<Utc>::from(modified).to_rfc3339_opts(SecondsFormat::Micros, true))
+}
+
+/// Session letter ending a sorted bucket title, `241106B` gives `B`.
+fn session_letter(bucket: &str) -> Option<char> {
+    let (date, letter) = bucket.split_at_checked(bucket.len().checked_sub(1)?)?;
+    let letter = letter.chars().next()?;
+
+    let dated = !date.is_empty() && date.chars().all(|c| c.is_ascii_digit());
+    (dated && letter.is_ascii_uppercase()).then_some(letter)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, time::Duration};
+
+    use super::{session_letter, session_tags};
+    use crate::probe::MediaInfo;
+
+    #[test]
+    fn tags_the_capture_date_and_the_session() {
+        let media = MediaInfo {
+            format_name: "mov,mp4,m4a,3gp,3g2,mj2".into(),
+            duration: Some(Duration::from_secs(10)),
+            size: None,
+            bit_rate: None,
+            creation_time: Some("2024-11-06T05:40:23.000000Z".into()),
+            video: Vec::new(),
+            audio: Vec::new(),
+            subtitles: Vec::new(),
+        };
+
+        assert_eq!(
+            session_tags(Path::new("/in/C0001.MP4"), Some(&media), "241106B"),
+            [
+                "-metadata",
+                "creation_time=2024-11-06T05:40:23.000000Z",
+                "-metadata",
+                "title=241106B",
+                "-metadata",
+                "comment=Session B"
+            ]
+        );
+
+        // Unsorted buckets have no session, a missing source no date
+        assert_eq!(
+            session_tags(Path::new("/in/missing.MP4"), None, "241106"),
+            ["-metadata", "title=241106"]
+        );
+
+        assert_eq!(session_letter("241106C"), Some('C'));
+        assert_eq!(session_letter("B"), None);
+        assert_eq!(session_letter("day B"), None);
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use lib_utils::arg::Arg;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+
+/// Name of the folder inside the bucket thumbnails go to with [`ThumbnailPlacement::ThumbsFolder`].
+pub const THUMBS_DIR: &str = "thumbs";
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+/// Where the thumbnails of a clip are written.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ThumbnailPlacement {
+    /// Beside the converted clip, with the same name
+    NextToOutput,
+    /// In a `thumbs` folder of the bucket, next to the `xml` one
+    #[default]
+    ThumbsFolder,
+}
+
+/// A poster frame for every converted clip and optionally a contact sheet, taken from the output
+/// once it has been encoded.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailOptions {
+    #[serde(default)]
+    pub format: ImageFormat,
+    #[serde(default)]
+    pub placement: ThumbnailPlacement,
+    /// Width in pixels of the poster and of every frame of the sheet, the height keeps the aspect
+    /// ratio
+    pub width: u32,
+    /// Number of evenly spaced frames of the contact sheet, no sheet when `None`
+    #[serde(default)]
+    pub contact_sheet: Option<u32>,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            format: ImageFormat::default(),
+            placement: ThumbnailPlacement::default(),
+            width: 640,
+            contact_sheet: None,
+        }
+    }
+}
+
+impl ThumbnailOptions {
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_placement(mut self, placement: ThumbnailPlacement) -> Self {
+        self.placement = placement;
+        self
+    }
+
+    pub fn with_width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_contact_sheet(mut self, frames: u32) -> Self {
+        self.contact_sheet = Some(frames);
+        self
+    }
+
+    /// Poster of the clip converted to `output`, `bucket_dir` being the folder of its bucket.
+    pub fn poster_path(&self, output: &Path, bucket_dir: &Path) -> PathBuf {
+        self.path(output, bucket_dir, "")
+    }
+
+    pub fn contact_sheet_path(&self, output: &Path, bucket_dir: &Path) -> PathBuf {
+        self.path(output, bucket_dir, "_sheet")
+    }
+
+    fn path(&self, output: &Path, bucket_dir: &Path, suffix: &str) -> PathBuf {
+        let stem = output
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = format!("{stem}{suffix}.{}", self.format.extension());
+
+        match self.placement {
+            ThumbnailPlacement::NextToOutput => output.with_file_name(name),
+            ThumbnailPlacement::ThumbsFolder => bucket_dir.join(THUMBS_DIR).join(name),
+        }
+    }
+
+    /// A single frame a tenth into the clip, past the slate or the fade in. Without a duration
+    /// ffmpeg's `thumbnail` filter picks a representative frame of the beginning instead.
+    pub(crate) fn poster_args(
+        &self,
+        video: &Path,
+        image: &Path,
+        duration: Option<Duration>,
+    ) -> Vec<String> {
+        let mut args = Arg::new("progress").value("pipe:1").build();
+        args.extend(Arg::new("nostats").build());
+        args.extend(Arg::new("y").build());
+
+        let mut filter = String::new();
+        match duration {
+            Some(duration) => {
+                let offset = duration.as_secs_f64() / 10.;
+                args.extend(Arg::new("ss").value(format!("{offset:.3}")).build());
+            }
+            None => filter.push_str("thumbnail,"),
+        }
+        filter += &format!("scale=w={}:h=-2", self.width);
+
+        args.extend(Arg::new("i").value(video.to_str().unwrap()).build());
+        args.extend(self.image_args(filter, image));
+        args
+    }
+
+    /// `frames` frames evenly spread over the clip, tiled into a single image. `None` without a
+    /// contact sheet or without a duration to spread the frames over.
+    pub(crate) fn contact_sheet_args(
+        &self,
+        video: &Path,
+        image: &Path,
+        duration: Option<Duration>,
+    ) -> Option<Vec<String>> {
+        let frames = self.contact_sheet.filter(|frames| *frames > 0)?;
+        let duration = duration.filter(|duration| !duration.is_zero())?;
+
+        let (columns, rows) = grid(frames);
+        let filter = format!(
+            "fps={frames}/{:.3},scale=w={}:h=-2,tile={columns}x{rows}:padding=4:margin=4",
+            duration.as_secs_f64(),
+            self.width
+        );
+
+        let mut args = Arg::new("progress").value("pipe:1").build();
+        args.extend(Arg::new("nostats").build());
+        args.extend(Arg::new("y").build());
+        args.extend(Arg::new("i").value(video.to_str().unwrap()).build());
+        args.extend(self.image_args(filter, image));
+        Some(args)
+    }
+
+    /// Everything after the input for a single image written to `image`.
+    fn image_args(&self, filter: String, image: &Path) -> Vec<String> {
+        let mut args = Arg::new("an").build();
+        args.extend(Arg::new("sn").build());
+        args.extend(Arg::new("dn").build());
+        args.extend(Arg::new("vf").value(filter).build());
+        args.extend(Arg::new("frames:v").value("1").build());
+        if self.format == ImageFormat::Jpeg {
+            args.extend(Arg::new("q:v").value("2").build());
+        }
+        // A single file, not an image sequence
+        args.extend(Arg::new("update").value("1").build());
+        args.push(image.to_str().unwrap().to_string());
+        args
+    }
+}
+
+/// Columns and rows of a sheet of `frames` frames, as square as possible and wider than tall.
+fn grid(frames: u32) -> (u32, u32) {
+    let columns = (frames as f64).sqrt().ceil() as u32;
+    (columns, frames.div_ceil(columns))
+}
+
+/// Images written for one clip, for the conversion report.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Thumbnails {
+    pub poster: Option<PathBuf>,
+    pub contact_sheet: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::Path, time::Duration};
+
+    use super::{grid, ImageFormat, ThumbnailOptions, ThumbnailPlacement};
+
+    #[test]
+    fn paths_follow_the_placement() {
+        let output = Path::new("/out/day 原/C0001.mp4");
+        let bucket = Path::new("/out/day 原");
+
+        let thumbs = ThumbnailOptions::default();
+        assert_eq!(
+            thumbs.poster_path(output, bucket),
+            Path::new("/out/day 原/thumbs/C0001.jpg")
+        );
+
+        let next_to = thumbs
+            .with_placement(ThumbnailPlacement::NextToOutput)
+            .with_format(ImageFormat::Png);
+        assert_eq!(
+            next_to.contact_sheet_path(output, bucket),
+            Path::new("/out/day 原/C0001_sheet.png")
+        );
+    }
+
+    #[test]
+    fn poster_seeks_a_tenth_in() {
+        let thumbs = ThumbnailOptions::default().with_width(320);
+        let args = thumbs.poster_args(
+            Path::new("/out/C0001.mp4"),
+            Path::new("/out/C0001.jpg"),
+            Some(Duration::from_secs(25)),
+        );
+
+        assert_eq!(
+            args,
+            [
+                "-progress",
+                "pipe:1",
+                "-nostats",
+                "-y",
+                "-ss",
+                "2.500",
+                "-i",
+                "/out/C0001.mp4",
+                "-an",
+                "-sn",
+                "-dn",
+                "-vf",
+                "scale=w=320:h=-2",
+                "-frames:v",
+                "1",
+                "-q:v",
+                "2",
+                "-update",
+                "1",
+                "/out/C0001.jpg"
+            ]
+        );
+
+        let unknown = thumbs.poster_args(Path::new("a.mp4"), Path::new("a.jpg"), None);
+        assert!(!unknown.contains(&"-ss".to_string()));
+        assert!(unknown.contains(&"thumbnail,scale=w=320:h=-2".to_string()));
+    }
+
+    #[test]
+    fn contact_sheet_spreads_the_frames() {
+        let video = Path::new("a.mp4");
+        let image = Path::new("a_sheet.png");
+        let duration = Some(Duration::from_secs(60));
+
+        let thumbs = ThumbnailOptions::default().with_format(ImageFormat::Png);
+        assert_eq!(thumbs.contact_sheet_args(video, image, duration), None);
+
+        let args = thumbs
+            .with_contact_sheet(12)
+            .contact_sheet_args(video, image, duration)
+            .unwrap();
+        assert!(args
+            .contains(&"fps=12/60.000,scale=w=640:h=-2,tile=4x3:padding=4:margin=4".to_string()));
+        assert!(!args.contains(&"-q:v".to_string()));
+
+        // Nothing to spread the frames over
+        assert_eq!(
+            thumbs
+                .with_contact_sheet(12)
+                .contact_sheet_args(video, image, None),
+            None
+        );
+
+        assert_eq!(grid(1), (1, 1));
+        assert_eq!(grid(9), (3, 3));
+        assert_eq!(grid(10), (4, 3));
+    }
+}
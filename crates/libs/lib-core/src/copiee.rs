@@ -1,15 +1,29 @@
+use crate::checksum::{sha256_file, Checksum};
+use crate::conflict::{resolve_conflict, ConflictDecision};
+use crate::converter::{ConflictPolicy, FileFailure, FileOutcome};
+use crate::journal::{FileState, Journal};
+use crate::temp_file::{discard, persist, temp_path};
 use crate::{Error, ProgressSystem, Result, Stage};
+use std::fs::{self, FileTimes, Metadata};
 use std::path::Path;
-use std::{fs::File, io, path::PathBuf, sync::Arc};
-use tokio::io::BufReader;
+use std::{io, path::PathBuf, sync::Arc};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::sync::RwLock;
 
+/// How many times a copy is redone when it doesn't match its source
+const COPY_ATTEMPTS: u32 = 3;
+const CHUNK_SIZE: usize = 1024 * 1024;
+/// Bytes copied between two progress reports
+const REPORT_EVERY: u64 = 8 * CHUNK_SIZE as u64;
+
 pub(crate) async fn copy_files(
     files: Arc<[PathBuf]>,
     des: &Path,
     folder_name: Arc<str>,
     tracker: Option<Arc<RwLock<ProgressSystem>>>,
-) -> Result<()> {
+    journal: Arc<Journal>,
+    conflict_policy: ConflictPolicy,
+) -> Result<Vec<FileOutcome>> {
     tracing::info!("Copying files [{}]", folder_name);
 
     // let result: Vec<Result<()>> = stream::iter(files)
@@ -48,46 +62,362 @@ pub(crate) async fn copy_files(
     //     r?
     // }
 
+    let mut outcomes = Vec::with_capacity(files.len());
+
     for file in files.iter() {
         let tracker = tracker.clone();
+        let file_name = file.file_name().unwrap().to_str().unwrap().to_lowercase();
+
+        let outcome =
+            match copy_one(file, des, &folder_name, &tracker, &journal, conflict_policy).await {
+                Ok(outcome) => outcome,
+                Err(err) => {
+                    tracing::error!("Failed to copy {:?} [{}] : {}", file, folder_name, err);
+                    if let Err(err) = journal
+                        .set_state(file, FileState::Failed(err.to_string()), None)
+                        .await
+                    {
+                        tracing::warn!("Failed to journal {:?} : {}", file, err);
+                    }
+
+                    if let Some(tracker) = tracker {
+                        if let Err(err) = tracker
+                            .read()
+                            .await
+                            .report_error(
+                                folder_name.clone(),
+                                Stage::Xml,
+                                &file_name,
+                                &err.to_string(),
+                            )
+                            .await
+                        {
+                            tracing::warn!("Failed to report error : {}", err);
+                        }
+                    }
+
+                    outcomes.push(FileOutcome::Failed(FileFailure::new(
+                        file.clone(),
+                        err.to_string(),
+                    )));
+                    continue;
+                }
+            };
 
-        copy_file(&file, des)?;
         if let Some(tracker) = tracker {
             tracing::info!("Updating tracker for file : {:?} [{}]", file, folder_name);
 
-            tracker
+            if let Err(err) = tracker
                 .read()
                 .await
-                .update_progress(
-                    folder_name.clone(),
-                    Stage::Xml,
-                    &file.file_name().unwrap().to_str().unwrap().to_lowercase(),
-                )
-                .await?;
+                .update_progress(folder_name.clone(), Stage::Xml, &file_name)
+                .await
+            {
+                tracing::warn!("Failed to report copy progress : {}", err);
+            }
         }
+
+        outcomes.push(outcome);
     }
-    Ok(())
+    Ok(outcomes)
 }
 
-pub(crate) fn copy_file(file: &Path, des: &Path) -> Result<()> {
-    // Open the source file
-    let source_name = file.file_name().unwrap();
-    let mut source_file = File::open(file)
-        .map_err(|err| Error::CopyError(format!("Failed to open source file: {}", err)))?;
+async fn copy_one(
+    file: &Path,
+    des: &Path,
+    folder_name: &Arc<str>,
+    tracker: &Option<Arc<RwLock<ProgressSystem>>>,
+    journal: &Journal,
+    conflict_policy: ConflictPolicy,
+) -> Result<FileOutcome> {
+    let file_name = file.file_name().unwrap().to_str().unwrap().to_lowercase();
+
+    if journal.should_skip(file).await {
+        tracing::info!(
+            "Skipping already copied file : {:?} [{}]",
+            file,
+            folder_name
+        );
+        return Ok(FileOutcome::Skipped(file.to_path_buf()));
+    }
+
+    let file_des = des.join(file.file_name().unwrap());
+
+    // An identical copy is already there, e.g. from an earlier run that lost its journal
+    if file_des.exists() && same_content(file, &file_des).await? {
+        tracing::info!("Already copied : {:?} [{}]", file_des, folder_name);
+        journal
+            .set_state(file, FileState::Done, Some(&file_des))
+            .await?;
+        return Ok(FileOutcome::Skipped(file.to_path_buf()));
+    }
+
+    // Anything else is a stale or truncated copy, keeping it would lose the sidecar
+    let conflict_policy = match conflict_policy {
+        ConflictPolicy::Skip => ConflictPolicy::Overwrite,
+        policy => policy,
+    };
+
+    let decision = resolve_conflict(conflict_policy, &file_des)?;
+    if let (Some(decision), Some(tracker)) = (&decision, tracker) {
+        if let Err(err) = tracker
+            .read()
+            .await
+            .report_conflict(folder_name.clone(), &file_name, decision.clone())
+            .await
+        {
+            tracing::warn!("Failed to report conflict : {}", err);
+        }
+    }
+
+    let target = decision
+        .as_ref()
+        .and_then(ConflictDecision::target)
+        .unwrap_or(&file_des);
+
+    journal
+        .set_state(file, FileState::Copying, Some(target))
+        .await?;
+
+    let reporter = CopyReporter {
+        tracker: tracker.as_deref(),
+        folder_name,
+        file_name: &file_name,
+    };
+    copy_file(file, target, &reporter).await?;
+
+    journal.set_state(file, FileState::Done, None).await?;
+
+    Ok(FileOutcome::Copied(file.to_path_buf()))
+}
+
+/// Whether `a` and `b` have the same size and checksum.
+async fn same_content(a: &Path, b: &Path) -> Result<bool> {
+    let (a, b) = (a.to_path_buf(), b.to_path_buf());
+
+    tokio::task::spawn_blocking(move || -> io::Result<bool> {
+        if fs::metadata(&a)?.len() != fs::metadata(&b)?.len() {
+            return Ok(false);
+        }
+        Ok(sha256_file(&a)? == sha256_file(&b)?)
+    })
+    .await
+    .map_err(|err| Error::CopyError(format!("Failed to compare with existing file: {err}")))?
+    .map_err(|err| Error::CopyError(format!("Failed to compare with existing file: {err}")))
+}
+
+/// Forwards the bytes copied of a file to the progress system.
+struct CopyReporter<'a> {
+    tracker: Option<&'a RwLock<ProgressSystem>>,
+    folder_name: &'a Arc<str>,
+    file_name: &'a str,
+}
+
+impl CopyReporter<'_> {
+    async fn report(&self, copied: u64, total: u64) {
+        let Some(tracker) = self.tracker else {
+            return;
+        };
+
+        if let Err(err) = tracker
+            .read()
+            .await
+            .update_copy(self.folder_name.clone(), self.file_name, copied, total)
+            .await
+        {
+            tracing::warn!("Failed to report copy of {} : {}", self.file_name, err);
+        }
+    }
+}
 
-    // Create the destination file (fail if it already exists)
-    let mut file_des = des.to_path_buf();
-    file_des.push(source_name);
+/// Copy `file` to `file_des`, replacing whatever is there. Conflicts are resolved by the caller.
+/// The data goes to a hidden temporary file first so a partial copy never carries the final name,
+/// it only gets it once its checksum matches the source's. A mismatch is retried.
+async fn copy_file(file: &Path, file_des: &Path, reporter: &CopyReporter<'_>) -> Result<()> {
+    let temp = temp_path(file_des);
+    let mut attempt = 1;
 
-    if file_des.exists() {
-        return Ok(());
+    loop {
+        let result = copy_verified(file, &temp, reporter)
+            .await
+            .and_then(|_| persist(&temp, file_des));
+
+        match result {
+            Err(err @ Error::ChecksumMismatch(_)) if attempt < COPY_ATTEMPTS => {
+                tracing::warn!("Copy attempt {} of {:?} failed : {}", attempt, file, err);
+                discard(&temp);
+                attempt += 1;
+            }
+            Err(err) => {
+                discard(&temp);
+                return Err(err);
+            }
+            Ok(()) => return Ok(()),
+        }
     }
-    let mut dest_file = File::create(&file_des)
+}
+
+/// Copy `file` to `des` while hashing it, then read `des` back and compare. `des` gets the access
+/// and modification times of `file`.
+async fn copy_verified(file: &Path, des: &Path, reporter: &CopyReporter<'_>) -> Result<()> {
+    let metadata = tokio::fs::metadata(file)
+        .await
+        .map_err(|err| Error::CopyError(format!("Failed to read source file: {}", err)))?;
+    let total = metadata.len();
+
+    let mut source_file = tokio::fs::File::open(file)
+        .await
+        .map_err(|err| Error::CopyError(format!("Failed to open source file: {}", err)))?;
+
+    let mut dest_file = tokio::fs::File::create(des)
+        .await
         .map_err(|err| Error::CopyError(format!("Failed to create destination file: {}", err)))?;
 
-    // Perform the file copy operation
-    io::copy(&mut source_file, &mut dest_file)
-        .map_err(|err| Error::CopyError(format!("Failed to copy file: {}", err)))?;
+    let mut checksum = Checksum::default();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    let (mut copied, mut reported) = (0, 0);
+
+    loop {
+        let read = source_file
+            .read(&mut buffer)
+            .await
+            .map_err(|err| Error::CopyError(format!("Failed to copy file: {}", err)))?;
+        if read == 0 {
+            break;
+        }
+
+        checksum.update(&buffer[..read]);
+        dest_file
+            .write_all(&buffer[..read])
+            .await
+            .map_err(|err| Error::CopyError(format!("Failed to copy file: {}", err)))?;
+
+        copied += read as u64;
+        if copied - reported >= REPORT_EVERY {
+            reporter.report(copied, total).await;
+            reported = copied;
+        }
+    }
+
+    dest_file
+        .sync_all()
+        .await
+        .map_err(|err| Error::CopyError(format!("Failed to flush destination file: {}", err)))?;
+    drop(dest_file);
+
+    let expected = checksum.finish();
+    let written = {
+        let des = des.to_path_buf();
+        tokio::task::spawn_blocking(move || sha256_file(&des))
+            .await
+            .map_err(|err| Error::CopyError(format!("Failed to verify copy: {}", err)))?
+            .map_err(|err| Error::CopyError(format!("Failed to verify copy: {}", err)))?
+    };
+    if written != expected {
+        return Err(Error::ChecksumMismatch(format!("{:?}", file)));
+    }
+
+    keep_times(des, &metadata)
+        .map_err(|err| Error::CopyError(format!("Failed to set file times: {}", err)))?;
+
+    reporter.report(copied, total).await;
 
     Ok(())
 }
+
+pub(crate) fn keep_times(des: &Path, source: &Metadata) -> io::Result<()> {
+    let times = FileTimes::new()
+        .set_accessed(source.accessed()?)
+        .set_modified(source.modified()?);
+
+    fs::File::options().write(true).open(des)?.set_times(times)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::PathBuf,
+        sync::Arc,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::{
+        converter::{ConflictPolicy, FileOutcome},
+        journal::Journal,
+        types::{ArgsType, ConverterOptions, FfmpegOptions, OutputExtension},
+    };
+
+    use super::copy_files;
+
+    fn journal(output_dir: PathBuf) -> Journal {
+        Journal::create(&ConverterOptions::new(
+            PathBuf::from("input"),
+            output_dir,
+            false,
+            FfmpegOptions::new(
+                ArgsType::MatchSource,
+                None,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                OutputExtension::Mkv,
+            ),
+        ))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn verified_copy_keeps_times_and_replaces_stale_copies() {
+        let dir = std::env::temp_dir().join("reconv_copiee_verified");
+        let _ = fs::remove_dir_all(&dir);
+        let (input, output) = (dir.join("input"), dir.join("output"));
+        fs::create_dir_all(&input).unwrap();
+        fs::create_dir_all(&output).unwrap();
+
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        let fresh = input.join("C0001M01.XML");
+        fs::write(&fresh, b"<xml>fresh</xml>").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&fresh)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let truncated = input.join("C0002M01.XML");
+        fs::write(&truncated, b"<xml>complete</xml>").unwrap();
+        fs::write(output.join("C0002M01.XML"), b"<xml>comp").unwrap();
+
+        let identical = input.join("C0003M01.XML");
+        fs::write(&identical, b"<xml/>").unwrap();
+        fs::write(output.join("C0003M01.XML"), b"<xml/>").unwrap();
+
+        let outcomes = copy_files(
+            Arc::from([fresh.clone(), truncated.clone(), identical.clone()]),
+            &output,
+            Arc::from("bucket"),
+            None,
+            Arc::new(journal(dir.clone())),
+            ConflictPolicy::Skip,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(&outcomes[0], FileOutcome::Copied(p) if *p == fresh));
+        assert!(matches!(&outcomes[1], FileOutcome::Copied(p) if *p == truncated));
+        assert!(matches!(&outcomes[2], FileOutcome::Skipped(p) if *p == identical));
+
+        let copy = output.join("C0001M01.XML");
+        assert_eq!(fs::read(&copy).unwrap(), b"<xml>fresh</xml>");
+        assert_eq!(fs::metadata(&copy).unwrap().modified().unwrap(), modified);
+        assert_eq!(
+            fs::read(output.join("C0002M01.XML")).unwrap(),
+            b"<xml>complete</xml>"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
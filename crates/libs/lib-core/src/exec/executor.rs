@@ -0,0 +1,157 @@
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+use std::{fmt::Debug, io, path::PathBuf, process::Stdio, sync::Arc};
+
+use futures::future::BoxFuture;
+use tokio::{
+    io::AsyncRead,
+    process::{Child, Command},
+};
+
+use crate::{Error, Result};
+
+pub type BoxedReader = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Spawns transcoder processes. [`FfmpegExecutor`] is the real one, tests can swap in
+/// [`super::fake::ScriptedExecutor`].
+pub trait Executor: Send + Sync + Debug {
+    /// Start the transcoder with `args`, stdout and stderr have to be captured.
+    fn spawn(&self, args: Vec<String>) -> Result<Process>;
+}
+
+/// Waiting on and killing a spawned process.
+pub trait ProcessControl: Send {
+    /// Wait for the process to exit, returning its exit code. `None` when it was ended by a
+    /// signal.
+    fn wait(&mut self) -> BoxFuture<'_, io::Result<Option<i32>>>;
+
+    fn kill(&mut self) -> BoxFuture<'_, io::Result<()>>;
+}
+
+/// Suspends and continues a process, usable while it is being waited on.
+pub trait Suspender: Send + Sync {
+    fn set_suspended(&self, suspended: bool) -> io::Result<()>;
+}
+
+/// A running transcoder process with its output streams.
+pub struct Process {
+    stdout: Option<BoxedReader>,
+    stderr: Option<BoxedReader>,
+    control: Box<dyn ProcessControl>,
+    suspender: Option<Arc<dyn Suspender>>,
+}
+
+impl Process {
+    pub fn new(stdout: BoxedReader, stderr: BoxedReader, control: Box<dyn ProcessControl>) -> Self {
+        Self {
+            stdout: Some(stdout),
+            stderr: Some(stderr),
+            control,
+            suspender: None,
+        }
+    }
+
+    pub fn with_suspender(mut self, suspender: Arc<dyn Suspender>) -> Self {
+        self.suspender = Some(suspender);
+        self
+    }
+
+    /// `None` when the process can't be suspended on this platform.
+    pub fn suspender(&self) -> Option<Arc<dyn Suspender>> {
+        self.suspender.clone()
+    }
+
+    pub fn take_stdout(&mut self) -> Option<BoxedReader> {
+        self.stdout.take()
+    }
+
+    pub fn take_stderr(&mut self) -> Option<BoxedReader> {
+        self.stderr.take()
+    }
+
+    pub async fn wait(&mut self) -> io::Result<Option<i32>> {
+        self.control.wait().await
+    }
+
+    pub async fn kill(&mut self) -> io::Result<()> {
+        self.control.kill().await
+    }
+}
+
+/// Runs the ffmpeg binary. When no executable is given, `ffmpeg` is looked up in `PATH`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FfmpegExecutor {
+    executable: Option<&'static PathBuf>,
+}
+
+impl FfmpegExecutor {
+    pub fn new(executable: Option<&'static PathBuf>) -> Self {
+        Self { executable }
+    }
+}
+
+impl Executor for FfmpegExecutor {
+    fn spawn(&self, args: Vec<String>) -> Result<Process> {
+        let mut command = match self.executable {
+            Some(executable) => Command::new(executable),
+            None => Command::new("ffmpeg"),
+        };
+
+        #[cfg(target_os = "windows")]
+        command.creation_flags(CREATE_NO_WINDOW);
+
+        let mut child = command
+            .args(args)
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| Error::FfmpegError(format!("Failed to execute ffmpeg: {err:?}")))?;
+
+        let stdout = child.stdout.take().unwrap();
+        let stderr = child.stderr.take().unwrap();
+
+        #[cfg(unix)]
+        let suspender = child.id().map(SignalSuspender);
+
+        let process = Process::new(Box::new(stdout), Box::new(stderr), Box::new(child));
+
+        #[cfg(unix)]
+        if let Some(suspender) = suspender {
+            return Ok(process.with_suspender(Arc::new(suspender)));
+        }
+
+        Ok(process)
+    }
+}
+
+/// Suspends with `SIGSTOP` and continues with `SIGCONT`.
+#[cfg(unix)]
+struct SignalSuspender(u32);
+
+#[cfg(unix)]
+impl Suspender for SignalSuspender {
+    fn set_suspended(&self, suspended: bool) -> io::Result<()> {
+        let signal = if suspended {
+            libc::SIGSTOP
+        } else {
+            libc::SIGCONT
+        };
+
+        // SAFETY: kill has no memory safety requirements
+        if unsafe { libc::kill(self.0 as libc::pid_t, signal) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+impl ProcessControl for Child {
+    fn wait(&mut self) -> BoxFuture<'_, io::Result<Option<i32>>> {
+        Box::pin(async move { Ok(Child::wait(self).await?.code()) })
+    }
+
+    fn kill(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        Box::pin(Child::kill(self))
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::future::BoxFuture;
+use tokio::{
+    io::{AsyncWriteExt, DuplexStream},
+    sync::watch,
+    task::JoinHandle,
+    time::sleep,
+};
+
+use super::executor::{Executor, Process, ProcessControl, Suspender};
+use crate::Result;
+
+/// How a fake transcoder run behaves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    /// Duration announced on stderr, like ffmpeg's input banner
+    pub duration: Duration,
+    /// Number of `-progress` blocks written before finishing
+    pub steps: u32,
+    /// Pause before every progress block
+    pub step_delay: Duration,
+    /// `None` for success, otherwise the stderr the run fails with
+    pub failure: Option<String>,
+}
+
+impl Default for Script {
+    fn default() -> Self {
+        Self::success()
+    }
+}
+
+impl Script {
+    pub fn success() -> Self {
+        Self {
+            duration: Duration::from_secs(10),
+            steps: 4,
+            step_delay: Duration::ZERO,
+            failure: None,
+        }
+    }
+
+    pub fn failure(stderr: impl Into<String>) -> Self {
+        Self {
+            failure: Some(stderr.into()),
+            ..Self::success()
+        }
+    }
+
+    /// Emit `steps` progress blocks, waiting `step_delay` before each one.
+    pub fn with_steps(mut self, steps: u32, step_delay: Duration) -> Self {
+        self.steps = steps;
+        self.step_delay = step_delay;
+        self
+    }
+
+    /// Write the progress blocks ffmpeg would, then the output files when the run succeeds.
+    /// Runs with a `loudnorm` filter end their log with [`FAKE_LOUDNORM`].
+    async fn run(
+        self,
+        outputs: Vec<PathBuf>,
+        loudnorm: bool,
+        mut stdout: DuplexStream,
+        mut stderr: DuplexStream,
+        mut suspended: watch::Receiver<bool>,
+    ) -> bool {
+        let duration = self.duration.as_secs_f64();
+        let banner = format!(
+            "  Duration: {:02}:{:02}:{:05.2}, start: 0.000000, bitrate: 1000 kb/s\n",
+            (duration / 3600.) as u64,
+            (duration / 60.) as u64 % 60,
+            duration % 60.
+        );
+        let _ = stderr.write_all(banner.as_bytes()).await;
+
+        // ffmpeg creates the output right away, a killed run leaves it half written
+        for output in outputs.iter() {
+            if std::fs::write(output, b"partial").is_err() {
+                let _ = stderr.write_all(b"Could not open output\n").await;
+                return false;
+            }
+        }
+
+        for step in 1..=self.steps {
+            sleep(self.step_delay).await;
+            let _ = suspended.wait_for(|suspended| !suspended).await;
+
+            let out_time = self.duration.mul_f64(step as f64 / self.steps as f64);
+            let block = format!(
+                "frame={}\nfps=30.00\nbitrate=1000.0kbits/s\nout_time_us={}\nspeed=1.00x\nprogress={}\n",
+                step * 30,
+                out_time.as_micros(),
+                if step == self.steps { "end" } else { "continue" }
+            );
+            let _ = stdout.write_all(block.as_bytes()).await;
+        }
+
+        if let Some(failure) = self.failure {
+            let _ = stderr.write_all(failure.as_bytes()).await;
+            let _ = stderr.write_all(b"\n").await;
+            return false;
+        }
+
+        if loudnorm {
+            let _ = stderr.write_all(FAKE_LOUDNORM.as_bytes()).await;
+        }
+
+        outputs
+            .iter()
+            .all(|output| std::fs::write(output, b"converted").is_ok())
+    }
+}
+
+/// What the fake logs for `loudnorm`, the source measures -27.61 LUFS and the output -23.02.
+pub const FAKE_LOUDNORM: &str = r#"[Parsed_loudnorm_0 @ 0x5581c4a2c880]
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "18.06",
+	"input_thresh" : "-39.20",
+	"output_i" : "-23.02",
+	"output_tp" : "-1.50",
+	"output_lra" : "7.80",
+	"output_thresh" : "-33.71",
+	"normalization_type" : "linear",
+	"target_offset" : "0.02"
+}
+"#;
+
+/// In-process stand-in for ffmpeg. Every spawn runs the [`Script`] registered for the input's
+/// file name, else the one registered for its video encoder, else the default one. The arguments
+/// are recorded for inspection.
+#[derive(Debug, Default)]
+pub struct ScriptedExecutor {
+    default: Script,
+    scripts: HashMap<String, Script>,
+    encoder_scripts: HashMap<String, Script>,
+    calls: Mutex<Vec<Vec<String>>>,
+}
+
+impl ScriptedExecutor {
+    pub fn new(default: Script) -> Self {
+        Self {
+            default,
+            ..Default::default()
+        }
+    }
+
+    /// Use `script` for the input named `file_name`, e.g. `C0001.MP4`.
+    pub fn with_script(mut self, file_name: impl Into<String>, script: Script) -> Self {
+        self.scripts.insert(file_name.into(), script);
+        self
+    }
+
+    /// Use `script` for runs with the video encoder `encoder`, e.g. `h264_nvenc`.
+    pub fn with_encoder_script(mut self, encoder: impl Into<String>, script: Script) -> Self {
+        self.encoder_scripts.insert(encoder.into(), script);
+        self
+    }
+
+    /// Arguments of every spawn so far.
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+/// Version banner of the fake, what `ffmpeg -version` prints first.
+pub const FAKE_VERSION: &str = "ffmpeg version scripted";
+
+impl Executor for ScriptedExecutor {
+    fn spawn(&self, args: Vec<String>) -> Result<Process> {
+        if args == ["-version"] {
+            return Ok(version_process());
+        }
+
+        self.calls.lock().unwrap().push(args.clone());
+
+        let input_index = args.iter().position(|arg| arg == "-i").map(|i| i + 1);
+        let input = input_index
+            .and_then(|i| args.get(i))
+            .and_then(|input| Path::new(input).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let encoder = args
+            .iter()
+            .position(|arg| arg == "-c:v")
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+            .unwrap_or_default();
+        // Every path after the input is an output, `-f null -` only measures
+        let outputs = args
+            .iter()
+            .skip(input_index.map_or(0, |i| i + 1))
+            .map(PathBuf::from)
+            .filter(|output| output.is_absolute())
+            .collect();
+        let loudnorm = args.iter().any(|arg| arg.starts_with("loudnorm="));
+
+        let script = self
+            .scripts
+            .get(&input)
+            .or_else(|| self.encoder_scripts.get(&encoder))
+            .unwrap_or(&self.default)
+            .clone();
+
+        let (stdout, stdout_writer) = tokio::io::duplex(64 * 1024);
+        let (stderr, stderr_writer) = tokio::io::duplex(64 * 1024);
+
+        let (suspend_tx, suspended) = watch::channel(false);
+
+        let handle =
+            tokio::spawn(script.run(outputs, loudnorm, stdout_writer, stderr_writer, suspended));
+
+        Ok(Process::new(
+            Box::new(stdout),
+            Box::new(stderr),
+            Box::new(FakeProcess { handle }),
+        )
+        .with_suspender(Arc::new(FakeSuspender(suspend_tx))))
+    }
+}
+
+fn version_process() -> Process {
+    let (stdout, mut stdout_writer) = tokio::io::duplex(1024);
+    let (stderr, _) = tokio::io::duplex(1024);
+
+    let handle = tokio::spawn(async move {
+        let banner = format!("{FAKE_VERSION}\nbuilt with a test harness\n");
+        stdout_writer.write_all(banner.as_bytes()).await.is_ok()
+    });
+
+    Process::new(
+        Box::new(stdout),
+        Box::new(stderr),
+        Box::new(FakeProcess { handle }),
+    )
+}
+
+struct FakeSuspender(watch::Sender<bool>);
+
+impl Suspender for FakeSuspender {
+    fn set_suspended(&self, suspended: bool) -> io::Result<()> {
+        self.0.send_replace(suspended);
+        Ok(())
+    }
+}
+
+struct FakeProcess {
+    handle: JoinHandle<bool>,
+}
+
+impl ProcessControl for FakeProcess {
+    fn wait(&mut self) -> BoxFuture<'_, io::Result<Option<i32>>> {
+        Box::pin(async move {
+            let success = (&mut self.handle).await.map_err(io::Error::other)?;
+            Ok(Some(if success { 0 } else { 1 }))
+        })
+    }
+
+    fn kill(&mut self) -> BoxFuture<'_, io::Result<()>> {
+        self.handle.abort();
+        Box::pin(async move {
+            // Like a killed process, wait until the run actually stopped
+            let _ = (&mut self.handle).await;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,917 @@
+mod executor;
+pub mod fake;
+mod output;
+mod parser;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+pub use executor::{BoxedReader, Executor, FfmpegExecutor, Process, ProcessControl, Suspender};
+use lib_utils::{arg::Arg, file::FileExt};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    sync::RwLock,
+};
+
+use crate::{
+    conflict::{resolve_conflict, ConflictDecision},
+    converter::{
+        journal_key, parse_loudnorm, session_tags, ConflictPolicy, EncodedFile, EncoderFamily,
+        FfmpegOptions, FileFailure, FileOutcome, LoudnessMeasurement, LoudnessReport,
+        LoudnessTarget, OutputProfile, ThumbnailOptions, Thumbnails,
+    },
+    copiee::keep_times,
+    journal::{EncodeAttempt, FileState, Journal},
+    probe::MediaInfo,
+    scheduler::{Scheduler, Ticket},
+    temp_file::{discard, persist, temp_path},
+    ProgressSystem, Result, Stage,
+};
+use output::{batch_chain, label, Target};
+pub(crate) use output::{batches, run_args, targets};
+use parser::{parse_duration_line, ProgressParser};
+use tokio::{select, sync::watch::Receiver, task::JoinSet, time::sleep};
+
+#[allow(clippy::too_many_arguments)]
+pub async fn exec_batch_ffmpeg(
+    files: Arc<[FileExt]>,
+    media: Arc<[Option<MediaInfo>]>,
+    des: PathBuf,
+    flag: FfmpegOptions,
+    profiles: Arc<[OutputProfile]>,
+    stop_signal: Receiver<bool>, // Add the stop signal
+    pause: Receiver<bool>,
+    executor: Arc<dyn Executor>,
+    progress_system: Option<Arc<RwLock<ProgressSystem>>>,
+    folder_name: Arc<str>,
+    journal: Arc<Journal>,
+    conflict_policy: ConflictPolicy,
+    scheduler: Arc<Scheduler>,
+    bucket_index: usize,
+) -> Vec<FileOutcome> {
+    tracing::info!(
+        "converting with options : {:?} [{}]",
+        flag.build(),
+        folder_name
+    );
+
+    let mut join_set = JoinSet::new();
+
+    let files = files.clone();
+
+    for (round, (file, media)) in files.iter().zip(media.iter()).enumerate() {
+        let scheduler = scheduler.clone();
+        let mut stop_signal = stop_signal.clone();
+        let mut pause = pause.clone();
+        let file_name = file
+            .file_name()
+            .to_str()
+            .unwrap()
+            .to_string()
+            .to_lowercase();
+        let folder_name = folder_name.to_owned();
+        let progress_system = progress_system.clone();
+
+        let journal = journal.clone();
+        let executor = executor.clone();
+
+        let source = file.path_with_extension();
+        let targets = targets(file, &des, &flag, &profiles);
+        let duration = media.as_ref().and_then(|m| m.duration);
+        let tags = session_tags(&source, media.as_ref(), &folder_name);
+        let media = media.clone();
+
+        join_set.spawn(async move {
+            let tracker = progress_system.clone();
+            let mut outcomes = Vec::with_capacity(targets.len());
+
+            let mut pending = Vec::with_capacity(targets.len());
+            for target in targets {
+                let profile = target.profile.clone();
+                let prepared = prepare_target(
+                    &source,
+                    target,
+                    &file_name,
+                    &journal,
+                    conflict_policy,
+                    progress_system.as_ref(),
+                    &folder_name,
+                )
+                .await;
+
+                match prepared {
+                    Ok(Some(target)) => pending.push(target),
+                    Ok(None) => outcomes.push(FileOutcome::Skipped(source.clone())),
+                    Err(err) => outcomes.push(FileOutcome::Failed(
+                        FileFailure::new(source.clone(), err.to_string()).with_profile(profile),
+                    )),
+                }
+            }
+
+            let ticket = Ticket {
+                round,
+                bucket: bucket_index,
+            };
+            // First passes of the loudness normalization, one per loudness target and track
+            let mut measurements: Vec<(LoudnessTarget, Option<u32>, Option<LoudnessMeasurement>)> =
+                Vec::new();
+
+            for batch in batches(pending) {
+                let profiles: Vec<_> = batch.iter().map(|t| t.profile.clone()).collect();
+                let labels: Vec<_> = batch.iter().map(|t| t.label(&file_name)).collect();
+
+                let result: Result<Option<Vec<FileOutcome>>> = async {
+                    // Shared by every encoder
+                    let loudness = batch[0].options.loudness_normalization();
+                    let track = batch[0].options.loudness_track(media.as_ref());
+                    let measured = match loudness {
+                        Some(target) => match measurements
+                            .iter()
+                            .find(|(t, tr, _)| *t == target && *tr == track)
+                        {
+                            Some((_, _, measured)) => *measured,
+                            None => {
+                                let Some(measured) = measure_loudness(
+                                    &source,
+                                    target,
+                                    track,
+                                    ticket,
+                                    &file_name,
+                                    duration,
+                                    &mut stop_signal,
+                                    &pause,
+                                    executor.as_ref(),
+                                    &scheduler,
+                                    &folder_name,
+                                )
+                                .await
+                                else {
+                                    return Ok(None);
+                                };
+                                measurements.push((target, track, measured));
+                                measured
+                            }
+                        },
+                        None => None,
+                    };
+
+                    let retry = &batch[0].options.retry;
+                    let mut attempts = vec![Vec::new(); batch.len()];
+
+                    for step in batch_chain(&batch) {
+                        let encoder = step[0].encoder_name();
+
+                        for retries in 0..retry.max_attempts.max(1) {
+                            if retries > 0 {
+                                let backoff = retry.backoff(retries);
+                                tracing::info!(
+                                    "Retrying {} with {} in {:?}",
+                                    file_name,
+                                    encoder,
+                                    backoff
+                                );
+
+                                select! {
+                                    _ = sleep(backoff) => {}
+                                    _ = stopped(&mut stop_signal) => return Ok(None),
+                                }
+                            }
+
+                            // The main output's encoder decides the slot
+                            let slot = scheduler.acquire(ticket, step[0].encoder_family()).await;
+
+                            // Cancelled while waiting for a slot
+                            if *stop_signal.borrow() {
+                                return Ok(None);
+                            }
+
+                            // Hold queued files while the conversion is paused
+                            if *pause.borrow_and_update() {
+                                select! {
+                                    _ = pause.wait_for(|paused| !paused) => {}
+                                    _ = stopped(&mut stop_signal) => return Ok(None),
+                                }
+                            }
+
+                            for target in batch.iter() {
+                                journal
+                                    .set_state(
+                                        &target.journal_key(&source),
+                                        FileState::Encoding,
+                                        Some(&target.output),
+                                    )
+                                    .await?;
+                            }
+
+                            // ffmpeg writes to hidden files that only get the real name once it succeeded
+                            let temps: Vec<_> = batch
+                                .iter()
+                                .map(|target| temp_path(&target.output))
+                                .collect();
+                            let outputs: Vec<_> = step
+                                .iter()
+                                .zip(temps.iter())
+                                .map(|(options, temp)| (options, temp.as_path()))
+                                .collect();
+
+                            let args = run_args(
+                                &source,
+                                &outputs,
+                                &tags,
+                                media.as_ref(),
+                                measured.as_ref(),
+                                scheduler.threads(),
+                            );
+                            tracing::info!("executing with : {:?}", args);
+
+                            let started = Instant::now();
+                            let outcome = match executor.spawn(args.clone()) {
+                                Ok(child) => {
+                                    run_encode(
+                                        child,
+                                        &mut stop_signal,
+                                        pause.clone(),
+                                        progress_system.clone(),
+                                        folder_name.clone(),
+                                        &labels,
+                                        duration,
+                                    )
+                                    .await
+                                }
+                                Err(err) => RunOutcome::Failed(None, err.to_string()),
+                            };
+
+                            let elapsed = started.elapsed();
+                            drop(slot);
+
+                            match outcome {
+                                RunOutcome::Killed => {
+                                    temps.iter().for_each(|temp| discard(temp));
+                                    tracing::info!("Killing execution for file : {}", file_name);
+                                    return Ok(None);
+                                }
+                                RunOutcome::Failed(exit_code, stderr) => {
+                                    temps.iter().for_each(|temp| discard(temp));
+                                    tracing::error!(
+                                        "File : {:?}[{}] with {}\nstderr : {}\n",
+                                        file_name,
+                                        folder_name,
+                                        encoder,
+                                        stderr
+                                    );
+
+                                    for ((target, options), attempts) in
+                                        batch.iter().zip(step.iter()).zip(attempts.iter_mut())
+                                    {
+                                        let attempt = EncodeAttempt::new(
+                                            options.encoder_name(),
+                                            args.clone(),
+                                            exit_code,
+                                            elapsed,
+                                            &stderr,
+                                        );
+                                        journal
+                                            .record_attempt(
+                                                &target.journal_key(&source),
+                                                attempt.clone(),
+                                            )
+                                            .await?;
+                                        attempts.push(attempt);
+                                    }
+                                }
+                                RunOutcome::Succeeded(stderr) => {
+                                    // Every normalized output logs the same, the source is the same
+                                    let loudness = loudness.map(|_| LoudnessReport {
+                                        measured: measured.map(|measured| measured.input),
+                                        result: parse_loudnorm(&stderr).map(|(_, output)| output),
+                                    });
+
+                                    let mut encoded = Vec::with_capacity(batch.len());
+                                    for (i, (target, options)) in
+                                        batch.iter().zip(step.iter()).enumerate()
+                                    {
+                                        let key = target.journal_key(&source);
+                                        journal
+                                            .record_attempt(
+                                                &key,
+                                                EncodeAttempt::new(
+                                                    options.encoder_name(),
+                                                    args.clone(),
+                                                    Some(0),
+                                                    elapsed,
+                                                    &stderr,
+                                                ),
+                                            )
+                                            .await?;
+
+                                        if let Err(err) = persist(&temps[i], &target.output) {
+                                            temps[i..].iter().for_each(|temp| discard(temp));
+                                            return Err(err);
+                                        }
+                                        // Dated like the source so it sorts into the same session
+                                        if let Err(err) =
+                                            std::fs::metadata(&source).and_then(|metadata| {
+                                                keep_times(&target.output, &metadata)
+                                            })
+                                        {
+                                            tracing::warn!(
+                                                "Failed to set the times of {:?} : {}",
+                                                target.output,
+                                                err
+                                            );
+                                        }
+
+                                        journal.set_state(&key, FileState::Done, None).await?;
+
+                                        if let Some(tracker) = &progress_system {
+                                            tracker
+                                                .write()
+                                                .await
+                                                .update_progress(
+                                                    folder_name.clone(),
+                                                    Stage::Video,
+                                                    &labels[i],
+                                                )
+                                                .await?;
+                                        }
+
+                                        encoded.push(FileOutcome::Encoded(EncodedFile {
+                                            source: source.clone(),
+                                            output: target.output.clone(),
+                                            encoder: options.encoder_name(),
+                                            profile: target.profile.clone(),
+                                            loudness,
+                                            thumbnails: None,
+                                        }));
+                                    }
+
+                                    return Ok(Some(encoded));
+                                }
+                            }
+                        }
+                    }
+
+                    // Every attempt with every encoder failed
+                    let failures = batch
+                        .iter()
+                        .zip(attempts)
+                        .map(|(target, attempts)| {
+                            let last = attempts.last().cloned();
+                            FileOutcome::Failed(FileFailure {
+                                source: source.clone(),
+                                profile: target.profile.clone(),
+                                exit_code: last.as_ref().and_then(|a| a.exit_code),
+                                reason: last.map(|a| a.stderr).unwrap_or_default(),
+                                attempts,
+                            })
+                        })
+                        .collect();
+                    Ok(Some(failures))
+                }
+                .await;
+
+                match result {
+                    // Cancelled
+                    Ok(None) => break,
+                    Ok(Some(batch_outcomes)) => outcomes.extend(batch_outcomes),
+                    Err(err) => outcomes.extend(profiles.into_iter().map(|profile| {
+                        FileOutcome::Failed(
+                            FileFailure::new(source.clone(), err.to_string()).with_profile(profile),
+                        )
+                    })),
+                }
+            }
+
+            for outcome in outcomes.iter() {
+                let FileOutcome::Failed(failure) = outcome else {
+                    continue;
+                };
+
+                tracing::error!(
+                    "Failed to convert {:?}[{}] : {}",
+                    source,
+                    folder_name,
+                    failure.summary()
+                );
+
+                let key = journal_key(&source, failure.profile.as_deref());
+                let state = FileState::Failed(failure.summary().to_string());
+                if let Err(err) = journal.set_state(&key, state, None).await {
+                    tracing::warn!("Failed to journal {:?} : {}", key, err);
+                }
+
+                if let Some(tracker) = &tracker {
+                    let label = label(&file_name, failure.profile.as_deref());
+                    let cause = failure.summary();
+                    if let Err(err) = tracker
+                        .read()
+                        .await
+                        .report_error(folder_name.clone(), Stage::Video, &label, cause)
+                        .await
+                    {
+                        tracing::warn!("Failed to report error : {}", err);
+                    }
+                }
+            }
+
+            outcomes
+        });
+    }
+    let mut ss = stop_signal.clone();
+    let mut outcomes = Vec::with_capacity(files.len());
+
+    loop {
+        select! {
+           _ = ss.changed() => {
+                if *ss.borrow() {
+                    // First shutdown to prevent new tasks
+                    join_set.detach_all();
+                    break;
+                }
+            }
+
+            result = join_set.join_next()=>{
+                let Some(res) = result else {
+                    break;
+                };
+
+                outcomes.extend(res.unwrap());
+            }
+        }
+    }
+
+    outcomes
+}
+
+/// Skip `target` when an earlier run finished it or the conflict policy keeps the existing
+/// output, `None` then. Otherwise the target to encode, moved when the policy renames it.
+async fn prepare_target(
+    source: &Path,
+    mut target: Target,
+    file_name: &str,
+    journal: &Journal,
+    conflict_policy: ConflictPolicy,
+    progress_system: Option<&Arc<RwLock<ProgressSystem>>>,
+    folder_name: &Arc<str>,
+) -> Result<Option<Target>> {
+    let key = target.journal_key(source);
+    let label = target.label(file_name);
+
+    if journal.should_skip(&key).await {
+        tracing::info!(
+            "Skipping already converted file : {}[{}]",
+            label,
+            folder_name
+        );
+
+        if let Some(tracker) = progress_system {
+            tracker
+                .write()
+                .await
+                .update_progress(folder_name.clone(), Stage::Video, &label)
+                .await?;
+        }
+        return Ok(None);
+    }
+
+    if let Some(decision) = resolve_conflict(conflict_policy, &target.output)? {
+        if let Some(tracker) = progress_system {
+            tracker
+                .read()
+                .await
+                .report_conflict(folder_name.clone(), &label, decision.clone())
+                .await?;
+        }
+
+        match decision {
+            ConflictDecision::Skipped(existing) => {
+                tracing::info!("Keeping existing output : {:?}[{}]", existing, folder_name);
+                journal
+                    .set_state(&key, FileState::Done, Some(&existing))
+                    .await?;
+
+                if let Some(tracker) = progress_system {
+                    tracker
+                        .write()
+                        .await
+                        .update_progress(folder_name.clone(), Stage::Video, &label)
+                        .await?;
+                }
+                return Ok(None);
+            }
+            ConflictDecision::Overwritten(written) | ConflictDecision::Renamed { written, .. } => {
+                target.output = written;
+            }
+        }
+    }
+
+    Ok(Some(target))
+}
+
+/// First pass of the loudness normalization of `source`. `None` when the conversion was stopped,
+/// `Some(None)` when nothing could be measured and the normalization falls back to one pass.
+#[allow(clippy::too_many_arguments)]
+async fn measure_loudness(
+    source: &Path,
+    target: LoudnessTarget,
+    track: Option<u32>,
+    ticket: Ticket,
+    file_name: &str,
+    duration: Option<Duration>,
+    stop_signal: &mut Receiver<bool>,
+    pause: &Receiver<bool>,
+    executor: &dyn Executor,
+    scheduler: &Arc<Scheduler>,
+    folder_name: &Arc<str>,
+) -> Option<Option<LoudnessMeasurement>> {
+    let slot = scheduler.acquire(ticket, EncoderFamily::Software).await;
+    if *stop_signal.borrow() {
+        return None;
+    }
+
+    let args = loudness_args(source, target, track);
+    tracing::info!("measuring loudness with : {:?}", args);
+
+    let outcome = match executor.spawn(args) {
+        Ok(child) => {
+            run_encode(
+                child,
+                stop_signal,
+                pause.clone(),
+                None,
+                folder_name.clone(),
+                &[file_name.to_string()],
+                duration,
+            )
+            .await
+        }
+        Err(err) => RunOutcome::Failed(None, err.to_string()),
+    };
+    drop(slot);
+
+    match outcome {
+        RunOutcome::Killed => None,
+        RunOutcome::Succeeded(stderr) => {
+            let measured = parse_loudnorm(&stderr).map(|(input, _)| input);
+            if measured.is_none() {
+                tracing::warn!(
+                    "No loudness measured for {}, normalizing in one pass",
+                    file_name
+                );
+            }
+            Some(measured)
+        }
+        RunOutcome::Failed(_, stderr) => {
+            tracing::warn!(
+                "Failed to measure loudness of {}, normalizing in one pass : {}",
+                file_name,
+                stderr.lines().last().unwrap_or_default()
+            );
+            Some(None)
+        }
+    }
+}
+
+/// Write the thumbnails of every clip of `files` that is in `encoded`, one clip at a time. The
+/// other clips only go through the stage. A thumbnail that fails is logged and left out, the clip
+/// itself is fine.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn exec_thumbnails(
+    files: Arc<[FileExt]>,
+    media: Arc<[Option<MediaInfo>]>,
+    encoded: &mut [EncodedFile],
+    bucket_dir: &Path,
+    thumbnails: ThumbnailOptions,
+    mut stop_signal: Receiver<bool>,
+    mut pause: Receiver<bool>,
+    executor: Arc<dyn Executor>,
+    progress_system: Option<Arc<RwLock<ProgressSystem>>>,
+    folder_name: Arc<str>,
+    scheduler: Arc<Scheduler>,
+    bucket_index: usize,
+) {
+    for (round, (file, media)) in files.iter().zip(media.iter()).enumerate() {
+        if *stop_signal.borrow() {
+            return;
+        }
+
+        let file_name = file
+            .file_name()
+            .to_str()
+            .unwrap()
+            .to_string()
+            .to_lowercase();
+        let source = file.path_with_extension();
+        let duration = media.as_ref().and_then(|m| m.duration);
+
+        if let Some(encoded) = encoded
+            .iter_mut()
+            .find(|e| e.source == source && e.profile.is_none())
+        {
+            let poster = thumbnails.poster_path(&encoded.output, bucket_dir);
+            let sheet = thumbnails.contact_sheet_path(&encoded.output, bucket_dir);
+
+            let images = [
+                (
+                    poster.clone(),
+                    Some(thumbnails.poster_args(&encoded.output, &temp_path(&poster), duration)),
+                ),
+                (
+                    sheet.clone(),
+                    thumbnails.contact_sheet_args(&encoded.output, &temp_path(&sheet), duration),
+                ),
+            ];
+
+            let mut written = [None, None];
+            let mut failure = None;
+            for (i, (image, args)) in images.into_iter().enumerate() {
+                let Some(args) = args else {
+                    continue;
+                };
+
+                let ticket = Ticket {
+                    round,
+                    bucket: bucket_index,
+                };
+                let slot = scheduler.acquire(ticket, EncoderFamily::Software).await;
+                if *stop_signal.borrow() {
+                    return;
+                }
+
+                // Hold queued clips while the conversion is paused
+                if *pause.borrow_and_update() {
+                    select! {
+                        _ = pause.wait_for(|paused| !paused) => {}
+                        _ = stopped(&mut stop_signal) => return,
+                    }
+                }
+
+                tracing::info!("writing thumbnail with : {:?}", args);
+                let temp = temp_path(&image);
+                let outcome = match executor.spawn(args) {
+                    Ok(child) => {
+                        run_encode(
+                            child,
+                            &mut stop_signal,
+                            pause.clone(),
+                            None,
+                            folder_name.clone(),
+                            std::slice::from_ref(&file_name),
+                            duration,
+                        )
+                        .await
+                    }
+                    Err(err) => RunOutcome::Failed(None, err.to_string()),
+                };
+                drop(slot);
+
+                match outcome {
+                    RunOutcome::Killed => {
+                        discard(&temp);
+                        return;
+                    }
+                    RunOutcome::Failed(_, stderr) => {
+                        discard(&temp);
+                        failure = Some(stderr.lines().last().unwrap_or_default().to_string());
+                    }
+                    RunOutcome::Succeeded(_) => match persist(&temp, &image) {
+                        Ok(()) => written[i] = Some(image),
+                        Err(err) => {
+                            discard(&temp);
+                            failure = Some(err.to_string());
+                        }
+                    },
+                }
+            }
+
+            let [poster, contact_sheet] = written;
+            encoded.thumbnails = Some(Thumbnails {
+                poster,
+                contact_sheet,
+            });
+
+            if let Some(cause) = failure {
+                tracing::warn!(
+                    "Failed to write thumbnails of {:?}[{}] : {}",
+                    source,
+                    folder_name,
+                    cause
+                );
+
+                if let Some(tracker) = &progress_system {
+                    if let Err(err) = tracker
+                        .read()
+                        .await
+                        .report_error(folder_name.clone(), Stage::Thumbnail, &file_name, &cause)
+                        .await
+                    {
+                        tracing::warn!("Failed to report error : {}", err);
+                    }
+                }
+                continue;
+            }
+        }
+
+        if let Some(tracker) = &progress_system {
+            if let Err(err) = tracker
+                .write()
+                .await
+                .update_progress(folder_name.clone(), Stage::Thumbnail, &file_name)
+                .await
+            {
+                tracing::warn!("Failed to report thumbnail progress : {}", err);
+            }
+        }
+    }
+}
+
+/// Resolves once the conversion is stopped, never when the stop sender is gone.
+async fn stopped(stop_signal: &mut Receiver<bool>) {
+    if stop_signal.wait_for(|stop| *stop).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+/// How a single run of the transcoder ended.
+enum RunOutcome {
+    Killed,
+    /// With the exit code and the collected stderr
+    Failed(Option<i32>, String),
+    Succeeded(String),
+}
+
+/// Wait for `child` while reporting its progress, suspending it along with the conversion and
+/// killing it when the conversion is stopped.
+async fn run_encode(
+    mut child: Process,
+    stop_signal: &mut Receiver<bool>,
+    mut pause: Receiver<bool>,
+    progress_system: Option<Arc<RwLock<ProgressSystem>>>,
+    folder_name: Arc<str>,
+    labels: &[String],
+    duration: Option<Duration>,
+) -> RunOutcome {
+    let stdout = child.take_stdout().unwrap();
+    let stderr = child.take_stderr().unwrap();
+
+    // Suspend and continue the process along with the conversion
+    let suspend_task = child.suspender().map(|suspender| {
+        tokio::spawn(async move {
+            loop {
+                let paused = *pause.borrow_and_update();
+                if let Err(err) = suspender.set_suspended(paused) {
+                    tracing::warn!(
+                        "Failed to {} process : {}",
+                        if paused { "suspend" } else { "continue" },
+                        err
+                    );
+                }
+
+                if pause.changed().await.is_err() {
+                    break;
+                }
+            }
+        })
+    });
+
+    let monitor = monitor_output(
+        stdout,
+        stderr,
+        progress_system,
+        folder_name,
+        labels,
+        duration,
+    );
+
+    // Stop signalling as soon as the process is gone, its pid may be reused
+    let stop_suspending = || {
+        if let Some(suspend_task) = &suspend_task {
+            suspend_task.abort();
+        }
+    };
+
+    select! {
+        _ = stopped(stop_signal) => {
+            child.kill().await.unwrap();
+            stop_suspending();
+            RunOutcome::Killed
+        }
+
+        (status, err_output) = async { tokio::join!(child.wait(), monitor) } => {
+            stop_suspending();
+            match status {
+                Ok(Some(0)) => RunOutcome::Succeeded(err_output),
+                Ok(exit_code) => RunOutcome::Failed(exit_code, err_output),
+                Err(err) => RunOutcome::Failed(None, format!("{err_output}{err}")),
+            }
+        }
+    }
+}
+
+/// Drain ffmpeg's stdout (`-progress` blocks) and stderr (log) until the process closes them,
+/// forwarding every progress sample to the progress system. Returns the collected stderr.
+async fn monitor_output(
+    stdout: BoxedReader,
+    stderr: BoxedReader,
+    progress_system: Option<Arc<RwLock<ProgressSystem>>>,
+    folder_name: Arc<str>,
+    labels: &[String],
+    mut duration: Option<Duration>,
+) -> String {
+    let mut stdout = BufReader::new(stdout).lines();
+    let mut stderr = BufReader::new(stderr).lines();
+
+    let mut parser = ProgressParser::default();
+    let mut err_output = String::new();
+
+    let (mut stdout_open, mut stderr_open) = (true, true);
+
+    while stdout_open || stderr_open {
+        select! {
+            line = stdout.next_line(), if stdout_open => {
+                let Ok(Some(line)) = line else {
+                    stdout_open = false;
+                    continue;
+                };
+
+                let Some(sample) = parser.feed_line(&line) else {
+                    continue;
+                };
+
+                if let Some(progress_system) = &progress_system {
+                    let sample = sample.with_duration(duration);
+                    // Every output of a run is as far along as the run itself
+                    for label in labels {
+                        if let Err(err) = progress_system
+                            .read()
+                            .await
+                            .update_encode(folder_name.clone(), label, sample)
+                            .await
+                        {
+                            tracing::warn!("Failed to report encode progress : {}", err);
+                        }
+                    }
+                }
+            }
+
+            line = stderr.next_line(), if stderr_open => {
+                let Ok(Some(line)) = line else {
+                    stderr_open = false;
+                    continue;
+                };
+
+                if duration.is_none() {
+                    duration = parse_duration_line(&line);
+                }
+
+                err_output.push_str(&line);
+                err_output.push('\n');
+            }
+        }
+    }
+
+    err_output
+}
+
+/// Where the converted `source` ends up inside `des`.
+pub(crate) fn output_path(source: &FileExt, des: &Path, flag: &FfmpegOptions) -> PathBuf {
+    des.join(source.file_name().as_ref())
+        .with_extension(flag.extension())
+}
+
+/// Read the version banner of the transcoder, e.g. `ffmpeg version 7.1 Copyright ...`.
+pub(crate) async fn encoder_version(executor: &dyn Executor) -> Option<String> {
+    let mut child = executor.spawn(vec!["-version".to_string()]).ok()?;
+
+    let mut stdout = BufReader::new(child.take_stdout()?).lines();
+    let version = stdout.next_line().await.ok().flatten();
+    // Drain the rest so the process doesn't block on a full pipe
+    while let Ok(Some(_)) = stdout.next_line().await {}
+
+    if child.wait().await.ok()? != Some(0) {
+        return None;
+    }
+
+    version
+}
+
+/// Decode only the audio of `source` through `loudnorm`, which prints what it measured. `track`
+/// is the audio track to measure, ffmpeg's default one when `None`.
+fn loudness_args(source: &Path, target: LoudnessTarget, track: Option<u32>) -> Vec<String> {
+    let mut args = Arg::new("progress").value("pipe:1").build();
+    args.extend(Arg::new("nostats").build());
+    args.extend(Arg::new("i").value(source.to_str().unwrap()).build());
+    if let Some(track) = track {
+        args.extend(Arg::new("map").value(format!("0:a:{track}")).build());
+    }
+    args.extend(Arg::new("vn").build());
+    args.extend(Arg::new("sn").build());
+    args.extend(Arg::new("dn").build());
+    args.extend(Arg::new("af").value(target.measure_filter()).build());
+    args.extend(Arg::new("f").value("null").build());
+    args.push("-".to_string());
+    args
+}
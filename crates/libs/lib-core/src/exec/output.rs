@@ -0,0 +1,277 @@
+use std::path::{Path, PathBuf};
+
+use lib_utils::{arg::Arg, file::FileExt};
+
+use crate::{
+    converter::{journal_key, FfmpegOptions, LoudnessMeasurement, OutputProfile},
+    probe::MediaInfo,
+};
+
+use super::output_path;
+
+/// A file written from a source.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Target {
+    /// `None` for the output of the main options
+    pub(crate) profile: Option<String>,
+    pub(crate) options: FfmpegOptions,
+    pub(crate) output: PathBuf,
+}
+
+impl Target {
+    pub(crate) fn journal_key(&self, source: &Path) -> PathBuf {
+        journal_key(source, self.profile.as_deref())
+    }
+
+    /// Name the output is tracked under in the progress system, the file name for the main
+    /// output.
+    pub(crate) fn label(&self, file_name: &str) -> String {
+        label(file_name, self.profile.as_deref())
+    }
+}
+
+pub(crate) fn label(file_name: &str, profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("{file_name} [{profile}]"),
+        None => file_name.to_string(),
+    }
+}
+
+/// Every output of `source` inside `des`, the main one first.
+pub(crate) fn targets(
+    source: &FileExt,
+    des: &Path,
+    flag: &FfmpegOptions,
+    profiles: &[OutputProfile],
+) -> Vec<Target> {
+    let main = Target {
+        profile: None,
+        options: flag.clone(),
+        output: output_path(source, des, flag),
+    };
+
+    let profiles = profiles.iter().map(|profile| Target {
+        profile: Some(profile.name.clone()),
+        options: profile.ffmpeg_options.clone(),
+        output: profile.output_path(source, des),
+    });
+
+    std::iter::once(main).chain(profiles).collect()
+}
+
+/// Group `targets` into ffmpeg runs, keeping their order. Outputs decoded the same way and
+/// normalized to the same loudness are written by a single run, the source is only read once.
+pub(crate) fn batches(targets: Vec<Target>) -> Vec<Vec<Target>> {
+    let mut batches: Vec<Vec<Target>> = Vec::new();
+
+    for target in targets {
+        let batch = batches
+            .iter_mut()
+            .find(|batch| shares_run(&batch[0].options, &target.options));
+        match batch {
+            Some(batch) => batch.push(target),
+            None => batches.push(vec![target]),
+        }
+    }
+
+    batches
+}
+
+fn shares_run(a: &FfmpegOptions, b: &FfmpegOptions) -> bool {
+    let audio = |options: &FfmpegOptions| options.streams.as_ref().map(|s| s.audio.clone());
+    // The measurement is shared, it has to be of the same track
+    let same_track = a.loudness_normalization().is_none()
+        || (a.loudness_track(None) == b.loudness_track(None) && audio(a) == audio(b));
+
+    a.input_args() == b.input_args()
+        && a.loudness_normalization() == b.loudness_normalization()
+        && same_track
+}
+
+/// Options of every output for each encoder to try. Every output goes down its own
+/// [`FfmpegOptions::encoder_chain`] and stays on its last encoder once it runs out.
+pub(crate) fn batch_chain(batch: &[Target]) -> Vec<Vec<FfmpegOptions>> {
+    let chains: Vec<_> = batch
+        .iter()
+        .map(|target| target.options.encoder_chain())
+        .collect();
+    let steps = chains.iter().map(Vec::len).max().unwrap_or_default();
+
+    (0..steps)
+        .map(|step| {
+            chains
+                .iter()
+                .map(|chain| chain[step.min(chain.len() - 1)].clone())
+                .collect()
+        })
+        .collect()
+}
+
+/// A single run reading `source` and writing every output, decoded with the first output's
+/// options. Video outputs get `tags`, audio exports their own.
+pub(crate) fn invocation(
+    source: &Path,
+    outputs: &[(&FfmpegOptions, &Path)],
+    tags: &[String],
+    media: Option<&MediaInfo>,
+    measured: Option<&LoudnessMeasurement>,
+    threads: Option<u32>,
+) -> Vec<String> {
+    let mut args = Vec::new();
+
+    for (i, (options, output)) in outputs.iter().enumerate() {
+        let (flags, input_offset, _) = options.build_measured(measured, media);
+        if i == 0 {
+            args.extend_from_slice(&flags[..input_offset]);
+            args.push(source.to_str().unwrap().to_string());
+        }
+        args.extend_from_slice(&flags[input_offset..]);
+
+        match options.audio_export() {
+            Some(export) => args.extend(export.tag_args(source, media)),
+            None => args.extend_from_slice(tags),
+        }
+        // Output option, has to come right before the output path
+        if let Some(threads) = threads {
+            args.extend(Arg::new("threads").value(threads.to_string()).build());
+        }
+        args.push(output.to_str().unwrap().to_string());
+    }
+
+    args
+}
+
+/// [`invocation`] reporting its progress on stdout, never waiting on the overwrite prompt.
+pub(crate) fn run_args(
+    source: &Path,
+    outputs: &[(&FfmpegOptions, &Path)],
+    tags: &[String],
+    media: Option<&MediaInfo>,
+    measured: Option<&LoudnessMeasurement>,
+    threads: Option<u32>,
+) -> Vec<String> {
+    // Machine readable progress goes to stdout, the regular log stays on stderr
+    let mut args = Arg::new("progress").value("pipe:1").build();
+    args.extend(Arg::new("nostats").build());
+    // Conflicts are resolved before spawning, ffmpeg must never wait on its overwrite prompt
+    args.extend(Arg::new("y").build());
+
+    args.extend(invocation(source, outputs, tags, media, measured, threads));
+    args
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{batch_chain, batches, invocation, targets};
+    use crate::types::{
+        ArgsType, FfmpegOptions, HwAccel, OutputExtension, OutputProfile, RetryPolicy, VideoCodec,
+    };
+
+    fn options(video_codec: VideoCodec, hwaccel: Option<HwAccel>) -> FfmpegOptions {
+        FfmpegOptions::new(
+            ArgsType::MatchSource,
+            hwaccel,
+            ArgsType::MatchSource,
+            ArgsType::Custom(video_codec),
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            OutputExtension::Mov,
+        )
+    }
+
+    #[test]
+    fn proxy_shares_the_run_of_the_master() {
+        let master = options(VideoCodec::Prores, None);
+        let proxy = OutputProfile::new("proxy", options(VideoCodec::H264, None))
+            .with_subfolder("Proxy")
+            .with_suffix("_proxy");
+        let source = PathBuf::from("/in/C0001.MP4").into();
+
+        let targets = targets(&source, Path::new("/out"), &master, &[proxy]);
+        assert_eq!(targets[0].output, Path::new("/out/C0001.mov"));
+        assert_eq!(targets[1].output, Path::new("/out/Proxy/C0001_proxy.mov"));
+        assert_eq!(targets[1].label("c0001.mp4"), "c0001.mp4 [proxy]");
+
+        let batches = batches(targets);
+        assert_eq!(batches.len(), 1);
+
+        let outputs: Vec<_> = batches[0]
+            .iter()
+            .map(|target| (&target.options, target.output.as_path()))
+            .collect();
+        assert_eq!(
+            invocation(
+                Path::new("/in/C0001.MP4"),
+                &outputs,
+                &["-metadata".into(), "title=241106B".into()],
+                None,
+                None,
+                Some(4)
+            ),
+            [
+                "-i",
+                "/in/C0001.MP4",
+                "-c:v",
+                "prores",
+                "-c:a",
+                "copy",
+                "-metadata",
+                "title=241106B",
+                "-threads",
+                "4",
+                "/out/C0001.mov",
+                "-c:v",
+                "libx264",
+                "-c:a",
+                "copy",
+                "-metadata",
+                "title=241106B",
+                "-threads",
+                "4",
+                "/out/Proxy/C0001_proxy.mov"
+            ]
+        );
+    }
+
+    #[test]
+    fn different_decoding_needs_its_own_run() {
+        let master = options(VideoCodec::H264NVENC, Some(HwAccel::Cuda)).with_retry(RetryPolicy {
+            fallback: vec![VideoCodec::H264],
+            ..RetryPolicy::default()
+        });
+        let proxy = OutputProfile::new("proxy", options(VideoCodec::H264, None));
+        let other =
+            OutputProfile::new("other", options(VideoCodec::H264NVENC, Some(HwAccel::Cuda)));
+        let source = PathBuf::from("/in/C0001.MP4").into();
+
+        let batches = batches(targets(
+            &source,
+            Path::new("/out"),
+            &master,
+            &[proxy, other],
+        ));
+        let profiles: Vec<Vec<_>> = batches
+            .iter()
+            .map(|batch| batch.iter().map(|t| t.profile.clone()).collect())
+            .collect();
+        assert_eq!(
+            profiles,
+            [
+                vec![None, Some("other".to_string())],
+                vec![Some("proxy".to_string())]
+            ]
+        );
+
+        // The master falls back, the other output keeps its only encoder
+        let chain = batch_chain(&batches[0]);
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[1][0].video_codec, ArgsType::Custom(VideoCodec::H264));
+        assert_eq!(
+            chain[1][1].video_codec,
+            ArgsType::Custom(VideoCodec::H264NVENC)
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use crate::progress::EncodeSample;
+
+/// Incremental parser for the `key=value` blocks ffmpeg writes with `-progress`.
+///
+/// Every block is terminated by a `progress=continue` or `progress=end` line, at which point a
+/// sample is emitted.
+#[derive(Debug, Default)]
+pub(crate) struct ProgressParser {
+    out_time: Duration,
+    frame: u64,
+    fps: f32,
+    speed: f32,
+    bitrate: f32,
+}
+
+impl ProgressParser {
+    pub fn feed_line(&mut self, line: &str) -> Option<EncodeSample> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+
+        match key {
+            "frame" => self.frame = value.parse().unwrap_or(self.frame),
+            "fps" => self.fps = value.parse().unwrap_or(self.fps),
+            "bitrate" => {
+                self.bitrate = value
+                    .trim_end_matches("kbits/s")
+                    .parse()
+                    .unwrap_or(self.bitrate)
+            }
+            "speed" => self.speed = value.trim_end_matches('x').parse().unwrap_or(self.speed),
+            // `out_time_ms` is actually in microseconds as well, prefer the explicit key
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(us) = value.parse::<u64>() {
+                    self.out_time = Duration::from_micros(us);
+                }
+            }
+            "progress" => {
+                return Some(EncodeSample::new(
+                    self.out_time,
+                    None,
+                    self.frame,
+                    self.fps,
+                    self.speed,
+                    self.bitrate,
+                ));
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// Extract the input duration from the banner ffmpeg prints on stderr, e.g.
+/// `  Duration: 00:40:12.34, start: 0.000000, bitrate: 50012 kb/s`
+pub(crate) fn parse_duration_line(line: &str) -> Option<Duration> {
+    let rest = line.trim_start().strip_prefix("Duration:")?;
+    let timestamp = rest.split(',').next()?.trim();
+
+    parse_timestamp(timestamp)
+}
+
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let mut parts = timestamp.splitn(3, ':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{parse_duration_line, ProgressParser};
+
+    #[test]
+    fn parse_progress_block() {
+        let mut parser = ProgressParser::default();
+        let block = "frame=1200\nfps=59.94\nstream_0_0_q=23.0\nbitrate=8123.4kbits/s\n\
+             total_size=20480000\nout_time_us=20000000\nout_time_ms=20000000\n\
+             out_time=00:00:20.000000\ndup_frames=0\ndrop_frames=0\nspeed=2.5x";
+
+        for line in block.lines() {
+            assert!(parser.feed_line(line).is_none());
+        }
+
+        let sample = parser.feed_line("progress=continue").unwrap();
+
+        assert_eq!(sample.frame(), 1200);
+        assert_eq!(sample.fps(), 59.94);
+        assert_eq!(sample.bitrate(), 8123.4);
+        assert_eq!(sample.speed(), 2.5);
+        assert_eq!(sample.out_time(), Duration::from_secs(20));
+        assert_eq!(sample.percentage(), None);
+
+        let sample = sample.with_duration(Some(Duration::from_secs(80)));
+        assert_eq!(sample.percentage(), Some(25.));
+    }
+
+    #[test]
+    fn parse_progress_not_available() {
+        let mut parser = ProgressParser::default();
+
+        parser.feed_line("bitrate=N/A");
+        parser.feed_line("speed=N/A");
+        parser.feed_line("out_time_us=N/A");
+
+        let sample = parser.feed_line("progress=end").unwrap();
+        assert_eq!(sample.bitrate(), 0.);
+        assert_eq!(sample.out_time(), Duration::ZERO);
+    }
+
+    #[test]
+    fn parse_duration() {
+        assert_eq!(
+            parse_duration_line("  Duration: 00:40:12.50, start: 0.000000, bitrate: 50012 kb/s"),
+            Some(Duration::from_millis(2_412_500))
+        );
+        assert_eq!(parse_duration_line("  Duration: N/A, bitrate: N/A"), None);
+        assert_eq!(parse_duration_line("Input #0, mov,mp4"), None);
+    }
+}
@@ -0,0 +1,319 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use typeshare::typeshare;
+
+use crate::{
+    converter::{ConverterOptions, SchedulerOptions, SpaceOptions},
+    temp_file::{discard, temp_path},
+    Error, Result,
+};
+
+const JOURNAL_FILE_NAME: &str = ".reconv_journal.json";
+/// Lines of ffmpeg's log kept for every attempt
+const STDERR_TAIL_LINES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", content = "reason", rename_all = "camelCase")]
+pub enum FileState {
+    Pending,
+    Copying,
+    Encoding,
+    Done,
+    Failed(String),
+}
+
+/// One run of the transcoder on a file.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncodeAttempt {
+    /// Video encoder used, `copy` when the stream was copied
+    pub encoder: String,
+    /// The exact ffmpeg arguments
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// `None` when the process couldn't be started or was ended by a signal
+    pub exit_code: Option<i32>,
+    /// How long ffmpeg ran
+    #[serde(default)]
+    pub elapsed_ms: u32,
+    /// End of ffmpeg's log
+    pub stderr: String,
+}
+
+impl EncodeAttempt {
+    pub fn new(
+        encoder: impl Into<String>,
+        args: Vec<String>,
+        exit_code: Option<i32>,
+        elapsed: Duration,
+        stderr: &str,
+    ) -> Self {
+        Self {
+            encoder: encoder.into(),
+            args,
+            exit_code,
+            elapsed_ms: elapsed.as_millis().try_into().unwrap_or(u32::MAX),
+            stderr: stderr_tail(stderr),
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// The last lines of a transcoder log, the end is where the error is.
+pub(crate) fn stderr_tail(stderr: &str) -> String {
+    let lines: Vec<&str> = stderr.lines().collect();
+    lines[lines.len().saturating_sub(STDERR_TAIL_LINES)..].join("\n")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub state: FileState,
+    pub output: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<EncodeAttempt>,
+}
+
+impl JournalEntry {
+    fn pending() -> Self {
+        Self {
+            state: FileState::Pending,
+            output: None,
+            attempts: Vec::new(),
+        }
+    }
+
+    /// The output was being written when the job stopped, it can't be trusted.
+    pub fn is_half_written(&self) -> bool {
+        matches!(self.state, FileState::Copying | FileState::Encoding)
+    }
+
+    /// Finished work whose output is still on disk.
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, FileState::Done) && self.output.as_ref().is_some_and(|o| o.exists())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JournalData {
+    options_hash: String,
+    entries: BTreeMap<PathBuf, JournalEntry>,
+}
+
+/// Per file state of a conversion, persisted in the output directory so an interrupted job can be
+/// resumed with [`crate::types::Converter::resume`].
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    data: Mutex<JournalData>,
+}
+
+impl Journal {
+    pub fn path_for(output_dir: &Path) -> PathBuf {
+        output_dir.join(JOURNAL_FILE_NAME)
+    }
+
+    /// Start a new journal for `options`, replacing any previous one.
+    pub fn create(options: &ConverterOptions) -> Result<Self> {
+        let path = Self::path_for(&options.output_dir);
+        let data = JournalData {
+            options_hash: options_hash(options),
+            entries: BTreeMap::new(),
+        };
+
+        save(&path, &data)?;
+
+        Ok(Self {
+            path,
+            data: Mutex::new(data),
+        })
+    }
+
+    /// Load the journal of a previous run, refusing it when the options differ.
+    pub fn load(options: &ConverterOptions) -> Result<Self> {
+        let path = Self::path_for(&options.output_dir);
+
+        let content = fs::read(&path)
+            .map_err(|err| Error::JournalError(format!("Failed to read journal: {err}")))?;
+
+        let data: JournalData = serde_json::from_slice(&content)
+            .map_err(|err| Error::JournalError(format!("Invalid journal: {err}")))?;
+
+        if data.options_hash != options_hash(options) {
+            return Err(Error::JournalOptionsChanged);
+        }
+
+        Ok(Self {
+            path,
+            data: Mutex::new(data),
+        })
+    }
+
+    pub async fn entry(&self, source: &Path) -> Option<JournalEntry> {
+        self.data.lock().await.entries.get(source).cloned()
+    }
+
+    pub async fn set_state(
+        &self,
+        source: &Path,
+        state: FileState,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        let mut data = self.data.lock().await;
+
+        let entry = data
+            .entries
+            .entry(source.to_path_buf())
+            .or_insert_with(JournalEntry::pending);
+
+        entry.state = state;
+        if let Some(output) = output {
+            entry.output = Some(output.to_path_buf());
+        }
+
+        save(&self.path, &data)
+    }
+
+    /// Add an encode of `source` to its history.
+    pub async fn record_attempt(&self, source: &Path, attempt: EncodeAttempt) -> Result<()> {
+        let mut data = self.data.lock().await;
+
+        data.entries
+            .entry(source.to_path_buf())
+            .or_insert_with(JournalEntry::pending)
+            .attempts
+            .push(attempt);
+
+        save(&self.path, &data)
+    }
+
+    /// Whether `source` can be skipped. Half written outputs are removed so they get redone, the
+    /// recorded output itself is only replaced once the new one is finished.
+    pub async fn should_skip(&self, source: &Path) -> bool {
+        let Some(entry) = self.entry(source).await else {
+            return false;
+        };
+
+        if entry.is_half_written() {
+            if let Some(output) = entry.output.as_ref() {
+                discard(&temp_path(output));
+            }
+        }
+
+        entry.is_done()
+    }
+}
+
+fn save(path: &Path, data: &JournalData) -> Result<()> {
+    let content = serde_json::to_vec_pretty(data)
+        .map_err(|err| Error::JournalError(format!("Failed to serialize journal: {err}")))?;
+
+    // Write next to it first so a crash never leaves a truncated journal behind
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, content)
+        .and_then(|_| fs::rename(&tmp, path))
+        .map_err(|err| Error::JournalError(format!("Failed to write journal: {err}")))
+}
+
+/// Stable (FNV-1a) hash of the serialized options.
+pub(crate) fn options_hash(options: &ConverterOptions) -> String {
+    // Scheduling doesn't change the outputs, a resumed job may run with other limits
+    let options = ConverterOptions {
+        scheduler: SchedulerOptions::default(),
+        space: SpaceOptions::default(),
+        ..options.clone()
+    };
+    let serialized = serde_json::to_string(&options).unwrap_or_default();
+
+    let hash = serialized
+        .bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        temp_file::temp_path,
+        types::{ArgsType, ConverterOptions, FfmpegOptions, OutputExtension},
+        Error,
+    };
+
+    use super::{FileState, Journal};
+
+    fn options(output_dir: PathBuf, need_sorting: bool) -> ConverterOptions {
+        ConverterOptions::new(
+            PathBuf::from("input"),
+            output_dir,
+            need_sorting,
+            FfmpegOptions::new(
+                ArgsType::MatchSource,
+                None,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                ArgsType::MatchSource,
+                OutputExtension::Mkv,
+            ),
+        )
+    }
+
+    #[tokio::test]
+    async fn journal_roundtrip() {
+        let dir = std::env::temp_dir().join("reconv_journal_roundtrip");
+        fs::create_dir_all(&dir).unwrap();
+
+        let output = dir.join("a.mkv");
+        fs::write(&output, b"done").unwrap();
+        // Kept from before the run, the encode was going to its temporary file
+        let existing = dir.join("b.mkv");
+        fs::write(&existing, b"existing").unwrap();
+        let half_written = temp_path(&existing);
+        fs::write(&half_written, b"half").unwrap();
+
+        {
+            let journal = Journal::create(&options(dir.clone(), true)).unwrap();
+            journal
+                .set_state("a.mp4".as_ref(), FileState::Done, Some(&output))
+                .await
+                .unwrap();
+            journal
+                .set_state("b.mp4".as_ref(), FileState::Encoding, Some(&existing))
+                .await
+                .unwrap();
+        }
+
+        let journal = Journal::load(&options(dir.clone(), true)).unwrap();
+
+        assert!(journal.should_skip("a.mp4".as_ref()).await);
+        assert!(!journal.should_skip("b.mp4".as_ref()).await);
+        assert!(!half_written.exists());
+        assert_eq!(fs::read(&existing).unwrap(), b"existing");
+        assert!(!journal.should_skip("c.mp4".as_ref()).await);
+
+        assert!(matches!(
+            Journal::load(&options(dir.clone(), false)),
+            Err(Error::JournalOptionsChanged)
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,30 @@
+mod capabilities;
+mod checksum;
+mod conflict;
+mod control;
 mod converter;
 mod copiee;
 mod exec;
+mod journal;
+mod manifest;
+mod probe;
 mod progress;
+mod scheduler;
+mod space;
+mod temp_file;
 pub mod types;
 
+pub use conflict::ConflictDecision;
+pub use control::ConversionControl;
 pub use error::{Error, Result};
-pub use progress::{Progress, ProgressMonitor, ProgressSystem, Stage};
+pub use exec::{fake, BoxedReader, Executor, FfmpegExecutor, Process, ProcessControl, Suspender};
+pub use progress::{
+    CopyProgress, EncodeSample, ErrorInfo, FileProgress, Progress, ProgressMonitor, ProgressSystem,
+    Stage,
+};
 
 mod error {
-    use crate::progress;
+    use crate::{progress, space::SpaceCheck};
     use std::fmt::{Debug, Display};
 
     pub type Result<T> = core::result::Result<T, Error>;
@@ -20,6 +36,14 @@ mod error {
         CopyError(String),
         ReadDirError(String),
         FfmpegError(String),
+        ProbeError(String),
+        JournalError(String),
+        JournalOptionsChanged,
+        ManifestError(String),
+        SpaceError(String),
+        NotEnoughSpace(SpaceCheck),
+        ChecksumMismatch(String),
+        OutputExists(String),
         ConverterHasNoTaskAvailable,
         SinkerError(String),
         ProgressTrackerError(progress::Error),
@@ -39,7 +63,19 @@ mod error {
                 Error::CopyError(s) => f.write_str(s),
                 Error::CouldNotCreateDir(s) => f.write_str(s),
                 Error::FfmpegError(ff) => f.write_str(ff),
+                Error::ProbeError(p) => f.write_str(p),
+                Error::JournalError(j) => f.write_str(j),
+                Error::JournalOptionsChanged => f.write_str(
+                    "The options changed since the interrupted conversion, it can't be resumed",
+                ),
+                Error::ManifestError(m) => f.write_str(m),
+                Error::SpaceError(s) => f.write_str(s),
+                Error::NotEnoughSpace(check) => write!(f, "Not enough space : {check}"),
+                Error::ChecksumMismatch(path) => {
+                    write!(f, "Copy doesn't match its source : {path}")
+                }
                 Error::ReadDirError(r) => f.write_str(r),
+                Error::OutputExists(path) => write!(f, "Output already exists : {path}"),
                 Error::ConverterHasNoTaskAvailable => f.write_str("Internal Error"),
                 Error::SinkerError(s) => f.write_str(s),
                 Error::ProgressTrackerError(e) => std::fmt::Display::fmt(e, f),
@@ -0,0 +1,167 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checksum::sha256_file,
+    converter::{journal_key, ConverterOptions},
+    journal::{EncodeAttempt, FileState, Journal},
+    temp_file::{discard, persist, temp_path},
+    Error, Result,
+};
+
+const MANIFEST_FILE_NAME: &str = "reconv_manifest.json";
+/// Bumped whenever a field changes meaning or goes away
+const MANIFEST_VERSION: u32 = 1;
+
+/// A file on disk as it was when the bucket finished.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileInfo {
+    pub path: PathBuf,
+    pub size: u64,
+    /// RFC 3339
+    pub modified: Option<String>,
+    pub sha256: String,
+}
+
+impl FileInfo {
+    /// `None` when the file is gone or can't be read.
+    pub fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .map(|time| DateTime::<Local>::from(time).to_rfc3339_opts(SecondsFormat::Secs, false));
+
+        let sha256 = sha256_file(path)
+            .inspect_err(|err| tracing::warn!("Failed to hash {:?} : {}", path, err))
+            .ok()?;
+
+        Some(Self {
+            path: path.to_path_buf(),
+            size: metadata.len(),
+            modified,
+            sha256,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// `None` when the source couldn't be read anymore
+    pub source: Option<FileInfo>,
+    pub source_path: PathBuf,
+    /// Output profile the entry is about, `None` for the main output and the sidecars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Length of the media according to ffprobe, videos only
+    pub duration_secs: Option<f64>,
+    pub output: Option<FileInfo>,
+    /// Every ffmpeg run on the source, the last successful one produced the output
+    pub attempts: Vec<EncodeAttempt>,
+    pub outcome: FileState,
+}
+
+/// Everything a bucket folder was made of, written into it as `reconv_manifest.json` so imports
+/// can be audited later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub manifest_version: u32,
+    pub bucket: String,
+    /// RFC 3339
+    pub created_at: String,
+    pub tool_version: String,
+    /// First line of `ffmpeg -version`
+    pub encoder_version: Option<String>,
+    pub options: ConverterOptions,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn path_for(bucket_dir: &Path) -> PathBuf {
+        bucket_dir.join(MANIFEST_FILE_NAME)
+    }
+
+    /// Describe `sources`, with the output profile and the media duration of each when known, as
+    /// the journal left them. Hashes every source and output, which reads them all.
+    pub(crate) async fn build(
+        bucket: &str,
+        options: &ConverterOptions,
+        encoder_version: Option<String>,
+        sources: Vec<(PathBuf, Option<String>, Option<Duration>)>,
+        journal: &Journal,
+    ) -> Self {
+        let mut files = Vec::with_capacity(sources.len());
+
+        for (source_path, profile, duration) in sources {
+            let entry = journal
+                .entry(&journal_key(&source_path, profile.as_deref()))
+                .await;
+            let output = entry.as_ref().and_then(|e| e.output.clone());
+
+            let (source, output) = {
+                let source_path = source_path.clone();
+                tokio::task::spawn_blocking(move || {
+                    (
+                        FileInfo::read(&source_path),
+                        output.as_deref().and_then(FileInfo::read),
+                    )
+                })
+                .await
+                .unwrap_or_default()
+            };
+
+            let (attempts, outcome) = match entry {
+                Some(entry) => (entry.attempts, entry.state),
+                None => (Vec::new(), FileState::Pending),
+            };
+
+            files.push(ManifestEntry {
+                source,
+                source_path,
+                profile,
+                duration_secs: duration.map(|d| d.as_secs_f64()),
+                // A failed file may point at a previous output, it isn't this run's
+                output: output.filter(|_| matches!(outcome, FileState::Done)),
+                attempts,
+                outcome,
+            });
+        }
+
+        Self {
+            manifest_version: MANIFEST_VERSION,
+            bucket: bucket.to_string(),
+            created_at: Local::now().to_rfc3339_opts(SecondsFormat::Secs, false),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            encoder_version,
+            options: options.clone(),
+            files,
+        }
+    }
+
+    /// Write the manifest into `bucket_dir`, replacing the one of an earlier run.
+    pub(crate) fn write(&self, bucket_dir: &Path) -> Result<PathBuf> {
+        let path = Self::path_for(bucket_dir);
+        let content = serde_json::to_vec_pretty(self)
+            .map_err(|err| Error::ManifestError(format!("Failed to serialize manifest: {err}")))?;
+
+        let temp = temp_path(&path);
+        if let Err(err) = fs::write(&temp, content) {
+            discard(&temp);
+            return Err(Error::ManifestError(format!(
+                "Failed to write manifest: {err}"
+            )));
+        }
+        persist(&temp, &path)?;
+
+        Ok(path)
+    }
+}
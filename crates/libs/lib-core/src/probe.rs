@@ -0,0 +1,297 @@
+#[cfg(target_os = "windows")]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use lib_utils::{arg::Arg, file::FileExt};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoStream {
+    pub index: u32,
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: Option<f64>,
+    pub pixel_format: Option<String>,
+    pub bit_rate: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioStream {
+    pub index: u32,
+    pub codec: String,
+    pub sample_rate: Option<u32>,
+    pub channels: u32,
+    pub channel_layout: Option<String>,
+    pub bit_rate: Option<u64>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubtitleStream {
+    pub index: u32,
+    pub codec: String,
+    pub language: Option<String>,
+}
+
+impl SubtitleStream {
+    /// Text subtitles, the picture ones (PGS, DVB, DVD) can't become `mov_text`.
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self.codec.as_str(),
+            "subrip" | "srt" | "ass" | "ssa" | "webvtt" | "mov_text" | "text"
+        )
+    }
+}
+
+/// What ffprobe reports about a source file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaInfo {
+    pub format_name: String,
+    pub duration: Option<Duration>,
+    pub size: Option<u64>,
+    pub bit_rate: Option<u64>,
+    /// `creation_time` tag as written by the camera (ISO 8601)
+    pub creation_time: Option<String>,
+    pub video: Vec<VideoStream>,
+    pub audio: Vec<AudioStream>,
+    #[serde(default)]
+    pub subtitles: Vec<SubtitleStream>,
+}
+
+impl MediaInfo {
+    /// Run ffprobe against `file`. When no executable is given, `ffprobe` is looked up in `PATH`.
+    pub async fn probe(
+        file: &FileExt,
+        ffprobe_executable: Option<&'static PathBuf>,
+    ) -> Result<Self> {
+        let mut args = Arg::new("v").value("error").build();
+        args.extend(Arg::new("print_format").value("json").build());
+        args.extend(Arg::new("show_format").build());
+        args.extend(Arg::new("show_streams").build());
+        args.push(file.path_with_extension().to_str().unwrap().to_string());
+
+        let mut command = match ffprobe_executable {
+            Some(ffprobe_executable) => Command::new(ffprobe_executable),
+            None => Command::new("ffprobe"),
+        };
+
+        #[cfg(target_os = "windows")]
+        command.creation_flags(CREATE_NO_WINDOW);
+
+        let output = command
+            .args(args)
+            .output()
+            .await
+            .map_err(|err| Error::ProbeError(format!("Failed to execute ffprobe: {err:?}")))?;
+
+        if !output.status.success() {
+            return Err(Error::ProbeError(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+
+        Self::from_json(&output.stdout)
+    }
+
+    pub(crate) fn from_json(json: &[u8]) -> Result<Self> {
+        let raw: RawProbe = serde_json::from_slice(json)
+            .map_err(|err| Error::ProbeError(format!("Invalid ffprobe output: {err}")))?;
+
+        Ok(raw.into())
+    }
+
+    /// The first video stream, which is the one ffmpeg picks by default.
+    pub fn primary_video(&self) -> Option<&VideoStream> {
+        self.video.first()
+    }
+
+    /// The first audio stream, which is the one ffmpeg picks by default.
+    pub fn primary_audio(&self) -> Option<&AudioStream> {
+        self.audio.first()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawProbe {
+    #[serde(default)]
+    streams: Vec<RawStream>,
+    format: RawFormat,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStream {
+    index: u32,
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    r_frame_rate: Option<String>,
+    pix_fmt: Option<String>,
+    sample_rate: Option<String>,
+    channels: Option<u32>,
+    channel_layout: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawFormat {
+    format_name: String,
+    duration: Option<String>,
+    size: Option<String>,
+    bit_rate: Option<String>,
+    #[serde(default)]
+    tags: HashMap<String, String>,
+}
+
+impl From<RawProbe> for MediaInfo {
+    fn from(value: RawProbe) -> Self {
+        let mut video = Vec::new();
+        let mut audio = Vec::new();
+        let mut subtitles = Vec::new();
+
+        for stream in value.streams {
+            let codec = stream.codec_name.unwrap_or_default();
+            let bit_rate = stream.bit_rate.and_then(|b| b.parse().ok());
+
+            match stream.codec_type.as_deref() {
+                Some("video") => video.push(VideoStream {
+                    index: stream.index,
+                    codec,
+                    width: stream.width.unwrap_or_default(),
+                    height: stream.height.unwrap_or_default(),
+                    frame_rate: stream.r_frame_rate.as_deref().and_then(parse_rational),
+                    pixel_format: stream.pix_fmt,
+                    bit_rate,
+                }),
+                Some("audio") => audio.push(AudioStream {
+                    index: stream.index,
+                    codec,
+                    sample_rate: stream.sample_rate.and_then(|s| s.parse().ok()),
+                    channels: stream.channels.unwrap_or_default(),
+                    channel_layout: stream.channel_layout,
+                    bit_rate,
+                    language: stream.tags.get("language").cloned(),
+                }),
+                Some("subtitle") => subtitles.push(SubtitleStream {
+                    index: stream.index,
+                    codec,
+                    language: stream.tags.get("language").cloned(),
+                }),
+                _ => {}
+            }
+        }
+
+        let format = value.format;
+
+        MediaInfo {
+            format_name: format.format_name,
+            duration: format
+                .duration
+                .and_then(|d| d.parse::<f64>().ok())
+                .filter(|d| d.is_finite() && *d >= 0.)
+                .map(Duration::from_secs_f64),
+            size: format.size.and_then(|s| s.parse().ok()),
+            bit_rate: format.bit_rate.and_then(|b| b.parse().ok()),
+            creation_time: format.tags.get("creation_time").cloned(),
+            video,
+            audio,
+            subtitles,
+        }
+    }
+}
+
+/// Parse ffprobe's `num/den` frame rates, e.g. `30000/1001`
+fn parse_rational(value: &str) -> Option<f64> {
+    let (num, den) = value.split_once('/')?;
+    let (num, den): (f64, f64) = (num.parse().ok()?, den.parse().ok()?);
+
+    if den == 0. {
+        return None;
+    }
+
+    Some(num / den)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::MediaInfo;
+
+    const SONY_CLIP: &str = r#"{
+        "streams": [
+            {
+                "index": 0,
+                "codec_name": "h264",
+                "codec_type": "video",
+                "width": 1920,
+                "height": 1080,
+                "pix_fmt": "yuv420p",
+                "r_frame_rate": "30000/1001",
+                "bit_rate": "50000000"
+            },
+            {
+                "index": 1,
+                "codec_name": "pcm_s16be",
+                "codec_type": "audio",
+                "sample_rate": "48000",
+                "channels": 2,
+                "channel_layout": "stereo",
+                "bit_rate": "1536000",
+                "tags": { "language": "und" }
+            },
+            {
+                "index": 2,
+                "codec_type": "data",
+                "tags": { "timecode": "10:00:00:00" }
+            }
+        ],
+        "format": {
+            "format_name": "mov,mp4,m4a,3gp,3g2,mj2",
+            "duration": "2412.500000",
+            "size": "15104000000",
+            "bit_rate": "51530000",
+            "tags": { "creation_time": "2024-11-06T07:12:40.000000Z" }
+        }
+    }"#;
+
+    #[test]
+    fn parse_ffprobe_output() {
+        let info = MediaInfo::from_json(SONY_CLIP.as_bytes()).unwrap();
+
+        assert_eq!(info.duration, Some(Duration::from_millis(2_412_500)));
+        assert_eq!(info.size, Some(15_104_000_000));
+        assert_eq!(
+            info.creation_time.as_deref(),
+            Some("2024-11-06T07:12:40.000000Z")
+        );
+
+        let video = info.primary_video().unwrap();
+        assert_eq!((video.width, video.height), (1920, 1080));
+        assert_eq!(video.codec, "h264");
+        assert!((video.frame_rate.unwrap() - 29.97).abs() < 0.01);
+
+        let audio = info.primary_audio().unwrap();
+        assert_eq!(audio.channels, 2);
+        assert_eq!(audio.sample_rate, Some(48000));
+        assert_eq!(audio.language.as_deref(), Some("und"));
+
+        assert_eq!(info.video.len(), 1);
+        assert_eq!(info.audio.len(), 1);
+    }
+
+    #[test]
+    fn parse_invalid_output() {
+        assert!(MediaInfo::from_json(b"not json").is_err());
+    }
+}
@@ -7,7 +7,7 @@ pub use {
     monitor::ProgressMonitor,
     system::ProgressSystem,
     tracker::Stage,
-    types::{JobInfo, Message, Progress},
+    types::{CopyProgress, EncodeSample, ErrorInfo, FileProgress, JobInfo, Message, Progress},
 };
 
 pub use error::{Error, Result};
@@ -23,6 +23,7 @@ mod error {
         UpdateSignalFailed(String, Arc<str>),
         CreateSignalFailed(Arc<str>),
         DoneSignalFailed(Arc<str>),
+        PauseSignalFailed,
     }
 
     impl Display for Error {
@@ -33,6 +34,7 @@ mod error {
                 }
                 Error::CreateSignalFailed(ctx) => write!(f, "Create signal failed at {}", ctx),
                 Error::DoneSignalFailed(ctx) => write!(f, "Done signal failed at {}", ctx),
+                Error::PauseSignalFailed => f.write_str("Pause signal failed"),
             }
         }
     }
@@ -18,6 +18,8 @@ pub struct ProgressMonitor {
     message_rx: Receiver<Message>,
     progress_tx: Sender<Arc<[Progress]>>,
     update_interval: Interval,
+    paused: bool,
+    low_space: Option<u64>,
 }
 
 impl ProgressMonitor {
@@ -31,6 +33,8 @@ impl ProgressMonitor {
             message_rx,
             progress_tx,
             update_interval,
+            paused: false,
+            low_space: None,
         }
     }
 
@@ -41,53 +45,127 @@ impl ProgressMonitor {
             }
 
             select! {
-                data = self.message_rx.recv()=>{
-                    if let Some(data) = data{
-
+                data = self.message_rx.recv() => {
+                    if let Some(data) = data {
                         match data {
-                    Message::Create{job_info} => {
-                        let key = job_info.folder_name();
-                        let tracker = ProgressTracker::new(job_info);
-                        // Create the tracker object
-                        {
-                            self.progress_trackers
-                                .write()
-                                .await
-                                .entry(key)
-                                .or_insert(tracker);
-                        }
-                    }
+                            Message::Create { job_info } => {
+                                let key = job_info.folder_name();
+                                let tracker = ProgressTracker::new(job_info);
+                                // Create the tracker object
+                                {
+                                    self.progress_trackers
+                                        .write()
+                                        .await
+                                        .entry(key)
+                                        .or_insert(tracker);
+                                }
+                            }
 
-                    Message::Update {
-                        folder_name,
-                        action,
+                            Message::Update {
+                                folder_name,
+                                action,
                                 working_file,
-                    } => {
-                        self.progress_trackers
-                            .write()
-                            .await
-                            .entry(folder_name)
-                            .and_modify(|tracker| match action {
-                                Stage::Xml => tracker.update_xml(working_file).unwrap(),
-                                Stage::Video => tracker.update_video(working_file).unwrap(),
-                            });
-                    }
-                            Message::Done {folder_name} =>{
-                                self.progress_trackers.write().await.entry(folder_name).and_modify(|tracker| tracker.set_done());
+                            } => {
+                                self.progress_trackers
+                                    .write()
+                                    .await
+                                    .entry(folder_name)
+                                    .and_modify(|tracker| match action {
+                                        Stage::Xml => tracker.update_xml(working_file).unwrap(),
+                                        Stage::Video => tracker.update_video(working_file).unwrap(),
+                                        Stage::Thumbnail => {
+                                            tracker.update_thumbnail(working_file).unwrap()
+                                        }
+                                    });
+                            }
+                            Message::Encode {
+                                folder_name,
+                                working_file,
+                                sample,
+                            } => {
+                                self.progress_trackers
+                                    .write()
+                                    .await
+                                    .entry(folder_name)
+                                    .and_modify(|tracker| {
+                                        tracker.update_encode(working_file, sample)
+                                    });
+                            }
+                            Message::Copy {
+                                folder_name,
+                                working_file,
+                                copied,
+                                total,
+                            } => {
+                                self.progress_trackers
+                                    .write()
+                                    .await
+                                    .entry(folder_name)
+                                    .and_modify(|tracker| {
+                                        tracker.update_copy(working_file, copied, total)
+                                    });
+                            }
+                            Message::Conflict {
+                                folder_name,
+                                working_file,
+                                decision,
+                            } => {
+                                self.progress_trackers
+                                    .write()
+                                    .await
+                                    .entry(folder_name)
+                                    .and_modify(|tracker| {
+                                        tracker.add_conflict(working_file, decision)
+                                    });
+                            }
+                            Message::Error {
+                                folder_name,
+                                working_file,
+                                action,
+                                cause,
+                            } => {
+                                self.progress_trackers
+                                    .write()
+                                    .await
+                                    .entry(folder_name)
+                                    .and_modify(|tracker| {
+                                        if let Err(err) =
+                                            tracker.add_error(action, working_file, cause)
+                                        {
+                                            tracing::warn!("Failed to record error : {}", err);
+                                        }
+                                    });
+                            }
+                            Message::Done { folder_name } => {
+                                self.progress_trackers
+                                    .write()
+                                    .await
+                                    .entry(folder_name)
+                                    .and_modify(|tracker| tracker.set_done());
                             }
+                            Message::Pause { paused } => self.paused = paused,
+                            Message::LowSpace { available } => self.low_space = available,
+                        }
                     }
                 }
-                    }
 
-                _ = self.update_interval.tick() =>{
+                _ = self.update_interval.tick() => {
                     let progress = {
                         let progress_data = self.progress_trackers.read().await;
-                         progress_data.values().map(|tracker| tracker.progress()).collect()
+                        progress_data
+                            .values()
+                            .map(|tracker| {
+                                tracker
+                                    .progress()
+                                    .with_paused(self.paused)
+                                    .with_low_space(self.low_space)
+                            })
+                            .collect()
                     };
-                    if self.progress_tx.send(progress).await.is_err(){
+                    if self.progress_tx.send(progress).await.is_err() {
                         continue;
                     }
-                        // let _ = self.progress_tx.send(progress).await;
+                    // let _ = self.progress_tx.send(progress).await;
                 }
             }
         }
@@ -1,5 +1,6 @@
 use crate::{
-    progress::{Error, JobInfo, Message, Result},
+    conflict::ConflictDecision,
+    progress::{EncodeSample, Error, JobInfo, Message, Result},
     Progress, ProgressMonitor, Stage,
 };
 use std::sync::Arc;
@@ -69,6 +70,76 @@ impl ProgressSystem {
             .map_err(|_| Error::UpdateSignalFailed(working_file.to_string(), folder_name))
     }
 
+    pub async fn update_encode(
+        &self,
+        folder_name: Arc<str>,
+        working_file: &str,
+        sample: EncodeSample,
+    ) -> Result<()> {
+        self.message_tx
+            .send(Message::Encode {
+                folder_name: folder_name.clone(),
+                working_file: Arc::from(working_file),
+                sample,
+            })
+            .await
+            .map_err(|_| Error::UpdateSignalFailed(working_file.to_string(), folder_name))
+    }
+
+    /// Report that `copied` of the `total` bytes of `working_file` have been copied.
+    pub async fn update_copy(
+        &self,
+        folder_name: Arc<str>,
+        working_file: &str,
+        copied: u64,
+        total: u64,
+    ) -> Result<()> {
+        self.message_tx
+            .send(Message::Copy {
+                folder_name: folder_name.clone(),
+                working_file: Arc::from(working_file),
+                copied,
+                total,
+            })
+            .await
+            .map_err(|_| Error::UpdateSignalFailed(working_file.to_string(), folder_name))
+    }
+
+    pub async fn report_conflict(
+        &self,
+        folder_name: Arc<str>,
+        working_file: &str,
+        decision: ConflictDecision,
+    ) -> Result<()> {
+        self.message_tx
+            .send(Message::Conflict {
+                folder_name: folder_name.clone(),
+                working_file: Arc::from(working_file),
+                decision,
+            })
+            .await
+            .map_err(|_| Error::UpdateSignalFailed(working_file.to_string(), folder_name))
+    }
+
+    /// Report that `working_file` failed, it counts as processed.
+    pub async fn report_error(
+        &self,
+        folder_name: Arc<str>,
+        stage: Stage,
+        working_file: &str,
+        cause: &str,
+    ) -> Result<()> {
+        self.message_tx
+            .send(Message::Error {
+                folder_name: folder_name.clone(),
+                working_file: Arc::from(working_file),
+                action: stage,
+                cause: Arc::from(cause),
+            })
+            .await
+            .map_err(|_| Error::UpdateSignalFailed(working_file.to_string(), folder_name))
+    }
+
     pub async fn done(&self, folder_name: Arc<str>) -> Result<()> {
         self.message_tx
             .send(Message::Done {
@@ -78,6 +149,22 @@ impl ProgressSystem {
             .map_err(|_| Error::DoneSignalFailed(folder_name))
     }
 
+    pub async fn set_paused(&self, paused: bool) -> Result<()> {
+        self.message_tx
+            .send(Message::Pause { paused })
+            .await
+            .map_err(|_| Error::PauseSignalFailed)
+    }
+
+    /// Report that the output drive is almost full with `available` bytes left, `None` once
+    /// there is enough space again.
+    pub async fn set_low_space(&self, available: Option<u64>) -> Result<()> {
+        self.message_tx
+            .send(Message::LowSpace { available })
+            .await
+            .map_err(|_| Error::PauseSignalFailed)
+    }
+
     pub async fn get_progress(&mut self) -> Option<Arc<[Progress]>> {
         self.progress_rx.recv().await
     }
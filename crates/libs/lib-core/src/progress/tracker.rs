@@ -1,12 +1,18 @@
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
-use crate::progress::{JobInfo, Progress};
+use crate::{
+    conflict::ConflictDecision,
+    progress::{CopyProgress, EncodeSample, ErrorInfo, FileProgress, JobInfo, Progress},
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum Stage {
     Xml,
     Video,
+    /// Poster frames and contact sheets, after every video was encoded
+    Thumbnail,
 }
 
 #[derive(Debug)]
@@ -15,15 +21,13 @@ pub struct ProgressTracker {
     current_file: Arc<str>,
     current_xml: AtomicU32,
     current_video: AtomicU32,
+    current_thumbnail: AtomicU32,
     status: JobStatus,
     stage: Stage,
     errored: Vec<ErrorInfo>,
-}
-
-#[derive(Debug)]
-struct ErrorInfo {
-    _file: String,
-    _cause: String,
+    encoding: BTreeMap<Arc<str>, EncodeSample>,
+    copying: BTreeMap<Arc<str>, (u64, u64)>,
+    conflicts: Vec<(Arc<str>, ConflictDecision)>,
 }
 
 #[derive(Debug)]
@@ -35,15 +39,19 @@ enum JobStatus {
 
 impl ProgressTracker {
     pub fn new(job_info: JobInfo) -> Self {
-        let capacity = job_info.total_xml() + job_info.total_video();
+        let capacity = job_info.total_xml() + job_info.total_video() + job_info.total_thumbnail();
         ProgressTracker {
             job_info,
             status: JobStatus::Pending,
             errored: Vec::with_capacity(capacity as usize),
             current_xml: AtomicU32::new(0),
             current_video: AtomicU32::new(0),
+            current_thumbnail: AtomicU32::new(0),
             stage: Stage::Xml,
             current_file: Arc::default(),
+            encoding: BTreeMap::new(),
+            copying: BTreeMap::new(),
+            conflicts: Vec::new(),
         }
     }
     pub fn progress(&self) -> Progress {
@@ -56,6 +64,10 @@ impl ProgressTracker {
                 self.current_video.load(Ordering::SeqCst),
                 self.job_info.total_video(),
             ),
+            Stage::Thumbnail => (
+                self.current_thumbnail.load(Ordering::SeqCst),
+                self.job_info.total_thumbnail(),
+            ),
         };
         Progress::new(
             self.job_info.folder_name(),
@@ -65,6 +77,18 @@ impl ProgressTracker {
             self.errored.len() as u8,
             total as u8,
             matches!(self.status, JobStatus::Done),
+            self.encoding
+                .iter()
+                .map(|(file, sample)| FileProgress::new(file.clone(), *sample))
+                .collect(),
+            Arc::from(self.conflicts.as_slice()),
+        )
+        .with_errors(Arc::from(self.errored.as_slice()))
+        .with_copying(
+            self.copying
+                .iter()
+                .map(|(file, (copied, total))| CopyProgress::new(file.clone(), *copied, *total))
+                .collect(),
         )
     }
 
@@ -80,6 +104,10 @@ impl ProgressTracker {
                 .current_xml
                 .load(Ordering::SeqCst)
                 .eq(&self.job_info.total_xml())
+            && self
+                .current_thumbnail
+                .load(Ordering::SeqCst)
+                .eq(&self.job_info.total_thumbnail())
         {
             self.status = JobStatus::Done;
         }
@@ -94,6 +122,7 @@ impl ProgressTracker {
                     return Err("XML COPYING HAD BEEN DONE".to_string());
                 }
                 self.current_xml.fetch_add(1, Ordering::Relaxed);
+                self.copying.remove(&working_file);
                 if self
                     .current_xml
                     .load(Ordering::SeqCst)
@@ -111,6 +140,18 @@ impl ProgressTracker {
                     return Err("The things had been Done".to_string());
                 }
                 self.current_video.fetch_add(1, Ordering::SeqCst);
+                self.encoding.remove(&working_file);
+            }
+            Stage::Thumbnail => {
+                if self
+                    .current_thumbnail
+                    .load(Ordering::SeqCst)
+                    .eq(&self.job_info.total_thumbnail())
+                {
+                    return Err("THUMBNAILS HAD BEEN DONE".to_string());
+                }
+                self.current_thumbnail.fetch_add(1, Ordering::SeqCst);
+                self.stage = Stage::Thumbnail;
             }
         }
 
@@ -125,8 +166,44 @@ impl ProgressTracker {
     pub fn update_video(&mut self, working_file: Arc<str>) -> Result<(), String> {
         self.update(Stage::Video, working_file)
     }
+    pub fn update_thumbnail(&mut self, working_file: Arc<str>) -> Result<(), String> {
+        self.update(Stage::Thumbnail, working_file)
+    }
+
+    pub fn update_encode(&mut self, working_file: Arc<str>, sample: EncodeSample) {
+        if matches!(self.status, JobStatus::Pending) {
+            self.status = JobStatus::Starting;
+        }
+        self.stage = Stage::Video;
+        self.encoding.insert(working_file, sample);
+    }
+
+    pub fn update_copy(&mut self, working_file: Arc<str>, copied: u64, total: u64) {
+        if matches!(self.status, JobStatus::Pending) {
+            self.status = JobStatus::Starting;
+        }
+        self.copying.insert(working_file, (copied, total));
+    }
+
+    pub fn add_conflict(&mut self, working_file: Arc<str>, decision: ConflictDecision) {
+        self.conflicts.push((working_file, decision));
+    }
+
+    /// Count `working_file` as processed and remember why it failed.
+    pub fn add_error(
+        &mut self,
+        stage: Stage,
+        working_file: Arc<str>,
+        cause: Arc<str>,
+    ) -> Result<(), String> {
+        self.errored
+            .push(ErrorInfo::new(working_file.clone(), cause));
+        self.update(stage, working_file)
+    }
 
     pub fn set_done(&mut self) {
         self.status = JobStatus::Done;
+        self.encoding.clear();
+        self.copying.clear();
     }
 }
@@ -1,8 +1,7 @@
-use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::progress::tracker::Stage;
-use serde::{Deserialize, Serialize};
+use crate::{conflict::ConflictDecision, progress::tracker::Stage};
 use typeshare::typeshare;
 
 #[typeshare]
@@ -12,6 +11,7 @@ pub struct JobInfo {
     folder_name: Arc<str>,
     total_video: u32,
     total_xml: u32,
+    total_thumbnail: u32,
 }
 impl JobInfo {
     pub fn new(folder_name: Arc<str>, total_video: usize, total_xml: usize) -> Self {
@@ -19,8 +19,15 @@ impl JobInfo {
             folder_name,
             total_video: total_video as u32,
             total_xml: total_xml as u32,
+            total_thumbnail: 0,
         }
     }
+
+    /// Videos that go through the thumbnail stage, none by default.
+    pub fn with_total_thumbnail(mut self, total_thumbnail: usize) -> Self {
+        self.total_thumbnail = total_thumbnail as u32;
+        self
+    }
     pub fn folder_name(&self) -> Arc<str> {
         self.folder_name.clone()
     }
@@ -32,6 +39,10 @@ impl JobInfo {
     pub fn total_xml(&self) -> u32 {
         self.total_xml
     }
+
+    pub fn total_thumbnail(&self) -> u32 {
+        self.total_thumbnail
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -44,9 +55,195 @@ pub enum Message {
         working_file: Arc<str>,
         action: Stage,
     },
+    Encode {
+        folder_name: Arc<str>,
+        working_file: Arc<str>,
+        sample: EncodeSample,
+    },
+    Copy {
+        folder_name: Arc<str>,
+        working_file: Arc<str>,
+        copied: u64,
+        total: u64,
+    },
+    Conflict {
+        folder_name: Arc<str>,
+        working_file: Arc<str>,
+        decision: ConflictDecision,
+    },
+    Error {
+        folder_name: Arc<str>,
+        working_file: Arc<str>,
+        action: Stage,
+        cause: Arc<str>,
+    },
     Done {
         folder_name: Arc<str>,
     },
+    Pause {
+        paused: bool,
+    },
+    /// The output drive is almost full, `None` once there is enough space again
+    LowSpace {
+        available: Option<u64>,
+    },
+}
+
+/// A file that failed, it still counts as processed.
+#[derive(Clone, Debug)]
+pub struct ErrorInfo {
+    file: Arc<str>,
+    cause: Arc<str>,
+}
+
+impl ErrorInfo {
+    pub fn new(file: Arc<str>, cause: Arc<str>) -> Self {
+        Self { file, cause }
+    }
+
+    pub fn file(&self) -> Arc<str> {
+        self.file.clone()
+    }
+
+    pub fn cause(&self) -> Arc<str> {
+        self.cause.clone()
+    }
+}
+
+/// A single snapshot of ffmpeg's `-progress` output for the file being encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EncodeSample {
+    out_time: Duration,
+    duration: Option<Duration>,
+    frame: u64,
+    fps: f32,
+    speed: f32,
+    bitrate: f32,
+}
+
+impl EncodeSample {
+    pub fn new(
+        out_time: Duration,
+        duration: Option<Duration>,
+        frame: u64,
+        fps: f32,
+        speed: f32,
+        bitrate: f32,
+    ) -> Self {
+        Self {
+            out_time,
+            duration,
+            frame,
+            fps,
+            speed,
+            bitrate,
+        }
+    }
+
+    pub fn out_time(&self) -> Duration {
+        self.out_time
+    }
+
+    /// Total duration of the source, when ffmpeg has reported it.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Output bitrate in kbit/s.
+    pub fn bitrate(&self) -> f32 {
+        self.bitrate
+    }
+
+    /// Percentage (0-100) of the source that has been encoded.
+    pub fn percentage(&self) -> Option<f32> {
+        let duration = self.duration?.as_secs_f32();
+        if duration <= 0. {
+            return None;
+        }
+
+        Some((self.out_time.as_secs_f32() / duration * 100.).clamp(0., 100.))
+    }
+
+    pub(crate) fn with_duration(mut self, duration: Option<Duration>) -> Self {
+        if self.duration.is_none() {
+            self.duration = duration;
+        }
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct FileProgress {
+    file: Arc<str>,
+    sample: EncodeSample,
+}
+
+impl FileProgress {
+    pub fn new(file: Arc<str>, sample: EncodeSample) -> Self {
+        Self { file, sample }
+    }
+
+    pub fn file(&self) -> Arc<str> {
+        self.file.clone()
+    }
+
+    pub fn sample(&self) -> &EncodeSample {
+        &self.sample
+    }
+
+    pub fn percentage(&self) -> Option<f32> {
+        self.sample.percentage()
+    }
+}
+
+/// Bytes of a sidecar file copied so far.
+#[derive(Clone, Debug)]
+pub struct CopyProgress {
+    file: Arc<str>,
+    copied: u64,
+    total: u64,
+}
+
+impl CopyProgress {
+    pub fn new(file: Arc<str>, copied: u64, total: u64) -> Self {
+        Self {
+            file,
+            copied,
+            total,
+        }
+    }
+
+    pub fn file(&self) -> Arc<str> {
+        self.file.clone()
+    }
+
+    pub fn copied(&self) -> u64 {
+        self.copied
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn percentage(&self) -> Option<f32> {
+        if self.total == 0 {
+            return None;
+        }
+
+        Some((self.copied as f32 / self.total as f32 * 100.).clamp(0., 100.))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -58,9 +255,16 @@ pub struct Progress {
     error_count: u8,
     total: u8,
     done: bool,
+    encoding: Arc<[FileProgress]>,
+    conflicts: Arc<[(Arc<str>, ConflictDecision)]>,
+    errors: Arc<[ErrorInfo]>,
+    copying: Arc<[CopyProgress]>,
+    paused: bool,
+    low_space: Option<u64>,
 }
 
 impl Progress {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         folder: Arc<str>,
         file: Arc<str>,
@@ -69,6 +273,8 @@ impl Progress {
         error_count: u8,
         total: u8,
         done: bool,
+        encoding: Arc<[FileProgress]>,
+        conflicts: Arc<[(Arc<str>, ConflictDecision)]>,
     ) -> Self {
         Self {
             folder,
@@ -78,9 +284,35 @@ impl Progress {
             error_count,
             total,
             done,
+            encoding,
+            conflicts,
+            errors: Arc::new([]),
+            copying: Arc::new([]),
+            paused: false,
+            low_space: None,
         }
     }
 
+    pub(crate) fn with_errors(mut self, errors: Arc<[ErrorInfo]>) -> Self {
+        self.errors = errors;
+        self
+    }
+
+    pub(crate) fn with_copying(mut self, copying: Arc<[CopyProgress]>) -> Self {
+        self.copying = copying;
+        self
+    }
+
+    pub(crate) fn with_paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
+    pub(crate) fn with_low_space(mut self, low_space: Option<u64>) -> Self {
+        self.low_space = low_space;
+        self
+    }
+
     pub fn folder(&self) -> Arc<str> {
         self.folder.clone()
     }
@@ -105,10 +337,68 @@ impl Progress {
         match self.stage {
             Stage::Xml => "Xml",
             Stage::Video => "Video",
+            Stage::Thumbnail => "Thumbnail",
         }
     }
 
     pub fn done(&self) -> bool {
         self.done
     }
+
+    /// Files of this bucket that ffmpeg is currently encoding.
+    pub fn encoding(&self) -> Arc<[FileProgress]> {
+        self.encoding.clone()
+    }
+
+    /// Sidecar files of this bucket that are currently being copied.
+    pub fn copying(&self) -> Arc<[CopyProgress]> {
+        self.copying.clone()
+    }
+
+    /// Files that ran into an existing output and how it was handled.
+    pub fn conflicts(&self) -> Arc<[(Arc<str>, ConflictDecision)]> {
+        self.conflicts.clone()
+    }
+
+    /// Files of this bucket that failed and why.
+    pub fn errors(&self) -> Arc<[ErrorInfo]> {
+        self.errors.clone()
+    }
+
+    /// The conversion is paused, running encodes are suspended and queued ones held.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Free bytes left on the output drive when the conversion was paused because it is almost
+    /// full.
+    pub fn low_space(&self) -> Option<u64> {
+        self.low_space
+    }
+
+    /// Bucket completion (0-100) counting the partial progress of the files being encoded.
+    pub fn percentage(&self) -> f32 {
+        if self.total == 0 {
+            return if self.done { 100. } else { 0. };
+        }
+
+        let partial: f32 = match self.stage {
+            Stage::Video => self
+                .encoding
+                .iter()
+                .filter_map(|f| f.percentage())
+                .map(|p| p / 100.)
+                .sum(),
+            Stage::Xml => self
+                .copying
+                .iter()
+                .filter_map(|f| f.percentage())
+                .map(|p| p / 100.)
+                .sum(),
+            // Single frames, nothing worth reporting in between
+            Stage::Thumbnail => 0.,
+        };
+
+        ((self.count as f32 + partial) / self.total as f32 * 100.).clamp(0., 100.)
+    }
 }
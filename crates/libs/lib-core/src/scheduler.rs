@@ -0,0 +1,164 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::oneshot;
+
+use crate::converter::{EncoderFamily, SchedulerOptions};
+
+/// Position of a file in the global queue: files are handed out round by round, the n-th file of
+/// every bucket before the (n + 1)-th of any, so no bucket starves the others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Ticket {
+    pub round: usize,
+    pub bucket: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    running: u32,
+    family_running: HashMap<EncoderFamily, u32>,
+    waiting: BTreeMap<Ticket, (EncoderFamily, oneshot::Sender<()>)>,
+}
+
+/// Hands out encode slots across all buckets, keeping both the total job limit and the limit of
+/// every encoder family.
+#[derive(Debug)]
+pub(crate) struct Scheduler {
+    options: SchedulerOptions,
+    state: Mutex<State>,
+}
+
+/// A running encode, the slot is released on drop.
+#[derive(Debug)]
+pub(crate) struct Slot {
+    scheduler: Arc<Scheduler>,
+    family: EncoderFamily,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut state = self.scheduler.state.lock().unwrap();
+        release(&mut state, self.family);
+        self.scheduler.dispatch(&mut state);
+    }
+}
+
+impl Scheduler {
+    pub fn new(options: SchedulerOptions) -> Self {
+        Self {
+            options,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub fn threads(&self) -> Option<u32> {
+        self.options.threads
+    }
+
+    /// Wait for a slot of `family`, in `ticket` order.
+    pub async fn acquire(self: &Arc<Self>, ticket: Ticket, family: EncoderFamily) -> Slot {
+        let (tx, rx) = oneshot::channel();
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.waiting.insert(ticket, (family, tx));
+            self.dispatch(&mut state);
+        }
+
+        // The sender only goes away with the scheduler, which we hold a reference to
+        rx.await.unwrap();
+
+        Slot {
+            scheduler: self.clone(),
+            family,
+        }
+    }
+
+    /// Start every waiting ticket that fits, in order. A ticket whose family is full doesn't hold
+    /// back the ones behind it.
+    fn dispatch(&self, state: &mut State) {
+        let max_jobs = self.options.max_jobs.max(1);
+
+        let tickets: Vec<Ticket> = state.waiting.keys().copied().collect();
+        for ticket in tickets {
+            if state.running >= max_jobs {
+                break;
+            }
+
+            let family = state.waiting[&ticket].0;
+            let family_running = state.family_running.get(&family).copied().unwrap_or(0);
+            if family_running >= self.options.slots(family).max(1) {
+                continue;
+            }
+
+            let (_, tx) = state.waiting.remove(&ticket).unwrap();
+            state.running += 1;
+            *state.family_running.entry(family).or_default() += 1;
+
+            // The waiter is gone (its task was cancelled), give the slot back
+            if tx.send(()).is_err() {
+                release(state, family);
+            }
+        }
+    }
+}
+
+fn release(state: &mut State, family: EncoderFamily) {
+    state.running -= 1;
+    if let Some(running) = state.family_running.get_mut(&family) {
+        *running -= 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::{sync::mpsc, time::timeout};
+
+    use crate::converter::{EncoderFamily, SchedulerOptions};
+
+    use super::{Scheduler, Ticket};
+
+    fn ticket(round: usize, bucket: usize) -> Ticket {
+        Ticket { round, bucket }
+    }
+
+    #[tokio::test]
+    async fn limits_and_order() {
+        let scheduler = Arc::new(Scheduler::new(SchedulerOptions {
+            max_jobs: 2,
+            nvenc_slots: 1,
+            ..Default::default()
+        }));
+
+        let first = scheduler.acquire(ticket(0, 0), EncoderFamily::Nvenc).await;
+
+        // The NVENC family is full, a software encode still gets the second job
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        for (ticket, family) in [
+            (ticket(1, 0), EncoderFamily::Nvenc),
+            (ticket(0, 1), EncoderFamily::Nvenc),
+            (ticket(1, 1), EncoderFamily::Software),
+        ] {
+            let scheduler = scheduler.clone();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let slot = scheduler.acquire(ticket, family).await;
+                tx.send(ticket).unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                drop(slot);
+            });
+        }
+
+        assert_eq!(rx.recv().await.unwrap(), ticket(1, 1));
+        assert!(timeout(Duration::from_millis(50), rx.recv()).await.is_err());
+
+        // Freeing NVENC lets the earlier round of the second bucket go first
+        drop(first);
+        assert_eq!(rx.recv().await.unwrap(), ticket(0, 1));
+        assert_eq!(rx.recv().await.unwrap(), ticket(1, 0));
+    }
+}
@@ -0,0 +1,487 @@
+use std::{
+    fmt::Display,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{
+    control::ConversionControl,
+    converter::{
+        ArgsType, AudioExport, CineFormQuality, CodecProfile, ConverterOptions, DnxHrProfile,
+        FfmpegOptions, ProresProfile, RateControl, VideoCodec,
+    },
+    probe::MediaInfo,
+    Error, ProgressSystem, Result,
+};
+use lib_sorter::Bucket;
+use tokio::{sync::RwLock, task::JoinHandle};
+
+/// Assumed when ffprobe didn't report a frame rate
+const DEFAULT_FPS: f64 = 30.;
+/// Assumed per audio stream when neither the options nor ffprobe give a bitrate, in kbit/s
+const DEFAULT_AUDIO_KBPS: f64 = 192.;
+/// Assumed when ffprobe didn't report a sample rate, in Hz
+const DEFAULT_SAMPLE_RATE: f64 = 48_000.;
+/// Container overhead and estimation error, in percent of the estimate
+const HEADROOM_PERCENT: u64 = 5;
+
+/// Estimated output size of a conversion against the free space of the output drive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpaceCheck {
+    pub output_dir: PathBuf,
+    /// Estimated size of everything the conversion writes, in bytes
+    pub estimated: u64,
+    /// What has to be free for the conversion to finish without pausing, in bytes
+    pub required: u64,
+    /// Free space on the output drive, in bytes
+    pub available: u64,
+}
+
+impl SpaceCheck {
+    /// Bytes missing on the output drive, `None` when there is enough space.
+    pub fn shortfall(&self) -> Option<u64> {
+        self.required
+            .checked_sub(self.available)
+            .filter(|missing| *missing > 0)
+    }
+}
+
+impl Display for SpaceCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} needed on {:?}, {} free",
+            format_bytes(self.required),
+            self.output_dir,
+            format_bytes(self.available)
+        )
+    }
+}
+
+pub(crate) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024. && unit < UNITS.len() - 1 {
+        value /= 1024.;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// Compare the estimated output of `buckets` with the free space of the output drive.
+pub(crate) fn check_space(
+    options: &ConverterOptions,
+    buckets: &[(Arc<str>, Bucket<Option<MediaInfo>>)],
+) -> Result<SpaceCheck> {
+    // Every video is written once for the main options and once for each profile
+    let outputs: Vec<_> = std::iter::once(&options.ffmpeg_options)
+        .chain(
+            options
+                .profiles
+                .iter()
+                .map(|profile| &profile.ffmpeg_options),
+        )
+        .collect();
+
+    let estimated: u64 = buckets
+        .iter()
+        .map(|(_, bucket)| estimate_bucket(&outputs, bucket))
+        .sum();
+
+    let available = available_space(&options.output_dir).map_err(|err| {
+        Error::SpaceError(format!(
+            "Failed to read free space of {:?}: {err}",
+            options.output_dir
+        ))
+    })?;
+
+    Ok(SpaceCheck {
+        output_dir: options.output_dir.clone(),
+        estimated,
+        required: estimated + estimated * HEADROOM_PERCENT / 100 + options.space.min_free_bytes(),
+        available,
+    })
+}
+
+/// Free space of the drive `dir` is on, `dir` doesn't have to exist yet.
+pub(crate) fn available_space(dir: &Path) -> io::Result<u64> {
+    let existing = dir.ancestors().find(|path| path.exists()).unwrap_or(dir);
+
+    fs2::available_space(existing)
+}
+
+fn estimate_bucket(outputs: &[&FfmpegOptions], bucket: &Bucket<Option<MediaInfo>>) -> u64 {
+    let sidecars: u64 = bucket
+        .xml_files()
+        .iter()
+        .filter_map(|xml| fs::metadata(xml).ok())
+        .map(|metadata| metadata.len())
+        .sum();
+
+    let videos: u64 = bucket
+        .video_files()
+        .iter()
+        .zip(bucket.media().iter())
+        .map(|(file, media)| {
+            let source_size = fs::metadata(file.path_with_extension())
+                .map(|metadata| metadata.len())
+                .unwrap_or_default();
+            outputs
+                .iter()
+                .map(|options| estimate_video(options, media.as_ref(), source_size))
+                .sum::<u64>()
+        })
+        .sum();
+
+    sidecars + videos
+}
+
+/// Bitrate × duration of the output of one video, the source size when its duration is unknown.
+pub(crate) fn estimate_video(
+    options: &FfmpegOptions,
+    media: Option<&MediaInfo>,
+    source_size: u64,
+) -> u64 {
+    let Some((media, duration)) = media.and_then(|m| Some((m, m.duration?))) else {
+        return source_size;
+    };
+
+    if let Some(export) = options.audio_export() {
+        let kbps = audio_export_kbps(options, &export, media);
+        return (kbps * 1000. / 8. * duration.as_secs_f64()) as u64;
+    }
+
+    let rate_control = options
+        .rate_control
+        .filter(|_| matches!(options.video_codec, ArgsType::Custom(_)));
+
+    let video_kbps = match (rate_control, options.video_bitrate, options.video_codec) {
+        (Some(RateControl::Cbr { bitrate } | RateControl::Vbr { bitrate, .. }), _, _) => {
+            bitrate as f64
+        }
+        (None, ArgsType::Custom(bitrate), _) => bitrate as f64,
+        (_, _, ArgsType::Custom(codec)) => {
+            let kbps = options
+                .video_profile()
+                .map_or(codec_kbps(codec), profile_kbps);
+            kbps * pixel_scale(options, media)
+        }
+        (_, _, ArgsType::MatchSource) => match source_video_kbps(media, source_size, duration) {
+            Some(kbps) => kbps,
+            None => return source_size,
+        },
+    };
+
+    let audio_kbps: f64 = match options.audio_bitrate {
+        ArgsType::Custom(bitrate) => bitrate as f64 * media.audio.len() as f64,
+        ArgsType::MatchSource => media
+            .audio
+            .iter()
+            .map(|stream| {
+                stream
+                    .bit_rate
+                    .map_or(DEFAULT_AUDIO_KBPS, |bps| bps as f64 / 1000.)
+            })
+            .sum(),
+    };
+
+    ((video_kbps + audio_kbps) * 1000. / 8. * duration.as_secs_f64()) as u64
+}
+
+/// Bitrate of an audio-only export, lossless codecs are counted as uncompressed 16 bit PCM.
+fn audio_export_kbps(options: &FfmpegOptions, export: &AudioExport, media: &MediaInfo) -> f64 {
+    let stream = media.audio.get(export.stream as usize);
+
+    if export.encoder().is_lossy() {
+        return match options.audio_bitrate {
+            ArgsType::Custom(bitrate) => bitrate as f64,
+            ArgsType::MatchSource => DEFAULT_AUDIO_KBPS,
+        };
+    }
+
+    let sample_rate = export
+        .sample_rate
+        .or(stream.and_then(|s| s.sample_rate))
+        .map_or(DEFAULT_SAMPLE_RATE, |rate| rate as f64);
+    let channels = export
+        .channels
+        .map(u32::from)
+        .or(stream.map(|s| s.channels))
+        .unwrap_or(2);
+
+    sample_rate * channels as f64 * 16. / 1000.
+}
+
+/// Typical bitrate of `codec` for 1080p at 30 fps, in kbit/s.
+fn codec_kbps(codec: VideoCodec) -> f64 {
+    match codec {
+        VideoCodec::H264 | VideoCodec::H264NVENC | VideoCodec::H264AMF | VideoCodec::H264QSV => {
+            10_000.
+        }
+        VideoCodec::H265 | VideoCodec::H265NVENC | VideoCodec::H265AMF | VideoCodec::H265QSV => {
+            6_000.
+        }
+        VideoCodec::CineForm => 120_000.,
+        VideoCodec::Prores => 150_000.,
+        VideoCodec::DnxHr => 220_000.,
+    }
+}
+
+/// Bitrate of `profile` for 1080p at 30 fps, in kbit/s.
+fn profile_kbps(profile: CodecProfile) -> f64 {
+    match profile {
+        CodecProfile::Prores(profile) => match profile {
+            ProresProfile::Proxy => 45_000.,
+            ProresProfile::Lt => 102_000.,
+            ProresProfile::Standard => 147_000.,
+            ProresProfile::Hq => 220_000.,
+            ProresProfile::P4444 => 330_000.,
+        },
+        CodecProfile::CineForm(quality) => match quality {
+            CineFormQuality::Low => 60_000.,
+            CineFormQuality::Medium => 90_000.,
+            CineFormQuality::High => 120_000.,
+            CineFormQuality::Film1 => 160_000.,
+            CineFormQuality::Film2 => 200_000.,
+            CineFormQuality::Film3 => 250_000.,
+        },
+        CodecProfile::DnxHr(profile) => match profile {
+            DnxHrProfile::Lb => 45_000.,
+            DnxHrProfile::Sq => 145_000.,
+            DnxHrProfile::Hq | DnxHrProfile::Hqx => 220_000.,
+            DnxHrProfile::P444 => 440_000.,
+        },
+    }
+}
+
+/// Pixel rate of the output relative to 1080p at 30 fps.
+fn pixel_scale(options: &FfmpegOptions, media: &MediaInfo) -> f64 {
+    let source = media.video.first();
+
+    let (width, height) = match options.resolution {
+        ArgsType::Custom(resolution) => resolution.dimensions(),
+        ArgsType::MatchSource => source.map_or((1920, 1080), |s| (s.width, s.height)),
+    };
+    let fps = source
+        .and_then(|s| s.frame_rate)
+        .filter(|fps| *fps > 0.)
+        .unwrap_or(DEFAULT_FPS);
+
+    (width as f64 * height as f64) / (1920. * 1080.) * fps / DEFAULT_FPS
+}
+
+/// Bitrate of the source's video, in kbit/s.
+fn source_video_kbps(media: &MediaInfo, source_size: u64, duration: Duration) -> Option<f64> {
+    let bps = media
+        .video
+        .first()
+        .and_then(|stream| stream.bit_rate)
+        .or(media.bit_rate)
+        .map(|bps| bps as f64)
+        .or_else(|| {
+            let secs = duration.as_secs_f64();
+            (secs > 0.).then(|| source_size as f64 * 8. / secs)
+        })?;
+
+    Some(bps / 1000.)
+}
+
+/// Runs [`watch_space`] for the length of a conversion.
+pub(crate) struct SpaceWatcher {
+    task: JoinHandle<()>,
+    control: ConversionControl,
+    paused: Arc<AtomicBool>,
+}
+
+impl SpaceWatcher {
+    pub(crate) fn spawn(
+        options: Arc<ConverterOptions>,
+        control: ConversionControl,
+        progress_system: Option<Arc<RwLock<ProgressSystem>>>,
+    ) -> Self {
+        let paused = Arc::new(AtomicBool::new(false));
+        let task = tokio::spawn(watch_space(
+            options,
+            control.clone(),
+            progress_system,
+            paused.clone(),
+        ));
+
+        Self {
+            task,
+            control,
+            paused,
+        }
+    }
+
+    /// Stop watching. A pause of the watcher is undone, the control outlives the conversion.
+    pub(crate) fn stop(self) {
+        self.task.abort();
+        if self.paused.load(Ordering::Relaxed) {
+            self.control.resume();
+        }
+    }
+}
+
+/// Pause the conversion whenever the output drive has less than the configured free space, and
+/// resume it once there is enough again. A pause done by the user is left alone. `paused` tells
+/// whether the current pause is the watcher's.
+async fn watch_space(
+    options: Arc<ConverterOptions>,
+    control: ConversionControl,
+    progress_system: Option<Arc<RwLock<ProgressSystem>>>,
+    paused: Arc<AtomicBool>,
+) {
+    let min_free = options.space.min_free_bytes();
+    let mut interval = tokio::time::interval(Duration::from_secs(
+        options.space.check_interval_secs.max(1) as u64,
+    ));
+    let mut low = false;
+
+    loop {
+        interval.tick().await;
+
+        let available = match available_space(&options.output_dir) {
+            Ok(available) => available,
+            Err(err) => {
+                tracing::warn!("Failed to read free space : {}", err);
+                continue;
+            }
+        };
+
+        if (available < min_free) == low {
+            continue;
+        }
+        low = !low;
+
+        if low {
+            tracing::warn!(
+                "Pausing, only {} left on {:?}",
+                format_bytes(available),
+                options.output_dir
+            );
+            // Only resume later when the conversion was running when the space ran low
+            paused.store(!control.is_paused(), Ordering::Relaxed);
+            control.pause();
+        } else if paused.swap(false, Ordering::Relaxed) {
+            tracing::info!("Resuming, {} free again", format_bytes(available));
+            control.resume();
+        }
+
+        if let Some(progress_system) = &progress_system {
+            if let Err(err) = progress_system
+                .read()
+                .await
+                .set_low_space(low.then_some(available))
+                .await
+            {
+                tracing::warn!("Failed to report low space : {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::{
+        probe::{AudioStream, MediaInfo, VideoStream},
+        types::{
+            ArgsType, AudioExport, AudioFormat, FfmpegOptions, OutputExtension, Resolution,
+            VideoCodec,
+        },
+    };
+
+    use super::estimate_video;
+
+    fn media() -> MediaInfo {
+        MediaInfo {
+            format_name: "mov,mp4".to_string(),
+            duration: Some(Duration::from_secs(100)),
+            size: None,
+            bit_rate: None,
+            creation_time: None,
+            video: vec![VideoStream {
+                index: 0,
+                codec: "h264".to_string(),
+                width: 3840,
+                height: 2160,
+                frame_rate: Some(60.),
+                pixel_format: None,
+                bit_rate: Some(50_000_000),
+            }],
+            audio: vec![AudioStream {
+                index: 1,
+                codec: "pcm_s16be".to_string(),
+                sample_rate: Some(48000),
+                channels: 2,
+                channel_layout: None,
+                bit_rate: Some(1_536_000),
+                language: None,
+            }],
+            subtitles: Vec::new(),
+        }
+    }
+
+    fn options(
+        resolution: ArgsType<Resolution>,
+        video_codec: ArgsType<VideoCodec>,
+        video_bitrate: ArgsType<u32>,
+    ) -> FfmpegOptions {
+        FfmpegOptions::new(
+            resolution,
+            None,
+            ArgsType::MatchSource,
+            video_codec,
+            ArgsType::Custom(320),
+            video_bitrate,
+            ArgsType::MatchSource,
+            OutputExtension::Mkv,
+        )
+    }
+
+    #[test]
+    fn estimates_from_bitrate_and_duration() {
+        let media = media();
+
+        // (8000 + 320) kbit/s for 100s
+        let custom = options(
+            ArgsType::MatchSource,
+            ArgsType::Custom(VideoCodec::H264),
+            ArgsType::Custom(8000),
+        );
+        assert_eq!(estimate_video(&custom, Some(&media), 0), 104_000_000);
+
+        // 6000 kbit/s at 1080p30, scaled to 1080p60
+        let heuristic = options(
+            ArgsType::Custom(Resolution::R1080P),
+            ArgsType::Custom(VideoCodec::H265),
+            ArgsType::MatchSource,
+        );
+        assert_eq!(estimate_video(&heuristic, Some(&media), 0), 154_000_000);
+
+        // Same video bitrate as the source
+        let copy = options(
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+            ArgsType::MatchSource,
+        );
+        assert_eq!(estimate_video(&copy, Some(&media), 0), 629_000_000);
+
+        // Nothing to go on but the source
+        assert_eq!(estimate_video(&copy, None, 42), 42);
+
+        // 48 kHz stereo 16 bit PCM, no video
+        let wav = copy.with_audio_only(AudioExport::new(AudioFormat::Wav));
+        assert_eq!(estimate_video(&wav, Some(&media), 0), 19_200_000);
+    }
+}
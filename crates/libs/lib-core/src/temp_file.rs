@@ -0,0 +1,117 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{Error, Result};
+
+/// Prefix of the hidden files outputs are written to before being renamed into place.
+const TEMP_PREFIX: &str = ".reconv_tmp.";
+
+/// Hidden sibling of `target` to write to. The original file name is kept as a suffix so ffmpeg
+/// still picks the muxer from the extension.
+pub(crate) fn temp_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    target.with_file_name(format!("{TEMP_PREFIX}{name}"))
+}
+
+/// Move a finished temporary file onto `target`.
+pub(crate) fn persist(temp: &Path, target: &Path) -> Result<()> {
+    fs::rename(temp, target).map_err(|err| {
+        Error::CopyError(format!(
+            "Failed to move {:?} into place at {:?}: {}",
+            temp, target, err
+        ))
+    })
+}
+
+/// Remove a temporary file, ignoring the case where it was never created.
+pub(crate) fn discard(temp: &Path) {
+    if temp.exists() {
+        if let Err(err) = fs::remove_file(temp) {
+            tracing::warn!("Failed to remove temporary file {:?} : {}", temp, err);
+        }
+    }
+}
+
+/// Remove every leftover temporary file under `dir`, returning how many were removed. Symbolic
+/// links are not followed, they may lead out of the output or back into it.
+pub(crate) fn sweep(dir: &Path) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+
+    let mut removed = 0;
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            removed += sweep(&path);
+        } else if file_type.is_file()
+            && entry.file_name().to_string_lossy().starts_with(TEMP_PREFIX)
+        {
+            tracing::info!("Removing leftover temporary file : {:?}", path);
+            match fs::remove_file(&path) {
+                Ok(_) => removed += 1,
+                Err(err) => tracing::warn!("Failed to remove {:?} : {}", path, err),
+            }
+        }
+    }
+
+    removed
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{persist, sweep, temp_path};
+
+    #[test]
+    fn temp_files_lifecycle() {
+        let dir = std::env::temp_dir().join("reconv_temp_files_lifecycle");
+        let bucket = dir.join("bucket");
+        fs::create_dir_all(&bucket).unwrap();
+
+        let target = bucket.join("clip.mkv");
+        let temp = temp_path(&target);
+        assert_eq!(temp, bucket.join(".reconv_tmp.clip.mkv"));
+        assert_eq!(temp.extension().unwrap(), "mkv");
+
+        fs::write(&temp, b"done").unwrap();
+        persist(&temp, &target).unwrap();
+        assert!(!temp.exists());
+        assert_eq!(fs::read(&target).unwrap(), b"done");
+
+        fs::write(temp_path(&bucket.join("other.mkv")), b"half").unwrap();
+        fs::write(temp_path(&dir.join("note.xml")), b"half").unwrap();
+
+        // A loop back into the folder and a link to somewhere else, neither is walked
+        let library = std::env::temp_dir().join("reconv_temp_files_library");
+        #[cfg(unix)]
+        {
+            fs::create_dir_all(&library).unwrap();
+            fs::write(temp_path(&library.join("kept.mkv")), b"other").unwrap();
+            std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+            std::os::unix::fs::symlink(&library, dir.join("library")).unwrap();
+        }
+
+        assert_eq!(sweep(&dir), 2);
+        #[cfg(unix)]
+        assert!(temp_path(&library.join("kept.mkv")).exists());
+
+        assert_eq!(fs::read_dir(&bucket).unwrap().count(), 1);
+        assert!(target.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+        let _ = fs::remove_dir_all(&library);
+    }
+}
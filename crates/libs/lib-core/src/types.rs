@@ -1,4 +1,15 @@
+pub use crate::capabilities::Capabilities;
 pub use crate::converter::{
-    ArgsType, AudioCodec, Converter, ConverterOptions, FfmpegOptions, HwAccel, OutputExtension,
-    PictureFormat, Resolution, VideoCodec,
+    ArgsType, AudioCodec, AudioExport, AudioFormat, AudioSelection, AudioTrackCodec, BucketPlan,
+    BucketReport, CineFormQuality, CodecProfile, ConflictPolicy, ConversionPlan, ConversionReport,
+    Converter, ConverterOptions, CopyPlan, Deinterlacer, DnxHrProfile, EncodePlan, EncodedFile,
+    EncoderFamily, EncoderPreset, EncoderTune, FfmpegOptions, FileFailure, Filter, FilterGraph,
+    FrameRate, FrameRateMode, HwAccel, ImageFormat, Keyframes, Loudness, LoudnessReport,
+    LoudnessTarget, OutputExtension, OutputProfile, PictureFormat, ProresProfile, RateControl,
+    Resolution, RetryPolicy, Rotation, ScaleMode, SchedulerOptions, SpaceOptions, StreamMapping,
+    ThumbnailOptions, ThumbnailPlacement, Thumbnails, VideoCodec, THUMBS_DIR,
 };
+pub use crate::journal::{EncodeAttempt, FileState};
+pub use crate::manifest::{FileInfo, Manifest, ManifestEntry};
+pub use crate::probe::{AudioStream, MediaInfo, SubtitleStream, VideoStream};
+pub use crate::space::SpaceCheck;
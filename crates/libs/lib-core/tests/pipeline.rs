@@ -0,0 +1,788 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use lib_core::{
+    fake::{Script, ScriptedExecutor, FAKE_VERSION},
+    types::{
+        ArgsType, AudioCodec, AudioExport, AudioFormat, Converter, ConverterOptions, FfmpegOptions,
+        FileState, LoudnessTarget, Manifest, OutputExtension, OutputProfile, RetryPolicy,
+        ThumbnailOptions, ThumbnailPlacement, VideoCodec,
+    },
+    Error, ProgressSystem,
+};
+use tokio::sync::{watch, RwLock};
+
+/// Input folder with two clips and a sidecar, the output folder next to it.
+fn setup(name: &str) -> (PathBuf, PathBuf) {
+    let root = std::env::temp_dir().join(format!("reconv_pipeline_{name}"));
+    let _ = fs::remove_dir_all(&root);
+
+    let input = root.join("input");
+    fs::create_dir_all(&input).unwrap();
+    fs::write(input.join("C0001.MP4"), b"video").unwrap();
+    fs::write(input.join("C0002.MP4"), b"video").unwrap();
+    fs::write(input.join("C0001M01.XML"), b"<xml/>").unwrap();
+
+    (input, root.join("output"))
+}
+
+fn options(input: PathBuf, output: PathBuf) -> Arc<ConverterOptions> {
+    let ffmpeg_options = FfmpegOptions::new(
+        ArgsType::MatchSource,
+        None,
+        ArgsType::MatchSource,
+        ArgsType::MatchSource,
+        ArgsType::MatchSource,
+        ArgsType::MatchSource,
+        ArgsType::MatchSource,
+        OutputExtension::Mkv,
+    );
+
+    let mut options = ConverterOptions::new(input, output, false, ffmpeg_options);
+    // Don't depend on the free space of the machine running the tests
+    options.space.min_free_mb = 0;
+    Arc::new(options)
+}
+
+/// Every file under `dir`, relative to it.
+fn files(dir: &Path) -> Vec<String> {
+    fn walk(dir: &Path, root: &Path, out: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, root, out);
+            } else {
+                let relative = path.strip_prefix(root).unwrap();
+                out.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(dir, dir, &mut out);
+    out.retain(|f| !f.ends_with(".reconv_journal.json") && !f.ends_with("reconv_manifest.json"));
+    out.sort();
+    out
+}
+
+/// Strip the bucket folder, its name depends on the time the test runs at.
+fn bucket_files(output: &Path) -> Vec<String> {
+    files(output)
+        .into_iter()
+        .map(|f| f.split_once('/').unwrap().1.to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn converts_and_copies_a_bucket() {
+    let (input, output) = setup("converts");
+    let executor = Arc::new(ScriptedExecutor::new(Script::success()));
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(options(input, output.clone()), None)
+        .await
+        .unwrap();
+    converter.start_conversion(None).await.unwrap();
+
+    assert_eq!(
+        bucket_files(&output),
+        ["C0001.mkv", "C0002.mkv", "xml/C0001M01.XML"]
+    );
+
+    let bucket = fs::read_dir(&output)
+        .unwrap()
+        .flatten()
+        .find(|e| e.path().is_dir())
+        .unwrap()
+        .path();
+    assert_eq!(fs::read(bucket.join("C0001.mkv")).unwrap(), b"converted");
+
+    let calls = executor.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(calls.iter().all(|args| args.contains(&"-y".to_string())));
+}
+
+#[tokio::test]
+async fn failed_encode_is_reported_and_the_bucket_goes_on() {
+    let (input, output) = setup("failure");
+
+    let executor = ScriptedExecutor::new(Script::success())
+        .with_script("C0001.MP4", Script::failure("Invalid data found"));
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(Arc::new(executor));
+
+    converter
+        .prepare_task(options(input.clone(), output.clone()), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    assert_eq!(bucket_files(&output), ["C0002.mkv", "xml/C0001M01.XML"]);
+
+    assert!(!report.is_success());
+    let bucket = &report.buckets[0];
+    assert_eq!(bucket.copied.len(), 1);
+    assert_eq!(bucket.encoded.len(), 1);
+    assert_eq!(bucket.encoded[0].source, input.join("C0002.MP4"));
+    assert_eq!(bucket.encoded[0].encoder, "copy");
+
+    let failures: Vec<_> = report.failures().collect();
+    assert_eq!(failures.len(), 1);
+    let (_, failure) = failures[0];
+    assert_eq!(failure.source, input.join("C0001.MP4"));
+    assert_eq!(failure.exit_code, Some(1));
+    assert_eq!(failure.summary(), "Invalid data found");
+    assert_eq!(failure.attempts.len(), 1);
+}
+
+#[tokio::test]
+async fn falls_back_to_the_next_encoder() {
+    let (input, output) = setup("fallback");
+    fs::remove_file(input.join("C0002.MP4")).unwrap();
+
+    let executor = Arc::new(
+        ScriptedExecutor::new(Script::success())
+            .with_encoder_script(
+                "h264_nvenc",
+                Script::failure("No NVENC capable devices found"),
+            )
+            .with_encoder_script("h264_qsv", Script::failure("Error creating a MFX session")),
+    );
+
+    let mut options = (*options(input, output.clone())).clone();
+    options.ffmpeg_options.video_codec = ArgsType::Custom(VideoCodec::H264NVENC);
+    options.ffmpeg_options.retry = RetryPolicy {
+        max_attempts: 2,
+        backoff_ms: 1,
+        fallback: vec![VideoCodec::H264QSV, VideoCodec::H264],
+    };
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+    converter.start_conversion(None).await.unwrap();
+
+    assert_eq!(bucket_files(&output), ["C0001.mkv", "xml/C0001M01.XML"]);
+
+    let encoders: Vec<String> = executor
+        .calls()
+        .iter()
+        .map(|args| {
+            let i = args.iter().position(|arg| arg == "-c:v").unwrap();
+            args[i + 1].clone()
+        })
+        .collect();
+    assert_eq!(
+        encoders,
+        [
+            "h264_nvenc",
+            "h264_nvenc",
+            "h264_qsv",
+            "h264_qsv",
+            "libx264"
+        ]
+    );
+
+    let journal = fs::read_to_string(output.join(".reconv_journal.json")).unwrap();
+    assert!(journal.contains("No NVENC capable devices found"));
+}
+
+#[tokio::test]
+async fn normalizes_loudness_in_two_passes() {
+    let (input, output) = setup("loudness");
+    fs::remove_file(input.join("C0002.MP4")).unwrap();
+
+    let executor = Arc::new(ScriptedExecutor::new(Script::success()));
+
+    let mut options = (*options(input, output.clone())).clone();
+    options.ffmpeg_options.audio_codec = ArgsType::Custom(AudioCodec::Aac);
+    options.ffmpeg_options.loudness = Some(LoudnessTarget::default());
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    let calls = executor.calls();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0].last().unwrap(), "-");
+    assert!(calls[1]
+        .iter()
+        .any(|arg| arg.contains("measured_I=-27.61") && arg.contains("linear=true")));
+
+    let loudness = report.buckets[0].encoded[0].loudness.unwrap();
+    assert_eq!(loudness.measured.unwrap().integrated, -27.61);
+    assert_eq!(loudness.result.unwrap().integrated, -23.02);
+}
+
+#[tokio::test]
+async fn copied_audio_is_not_normalized() {
+    let (input, output) = setup("loudness_copy");
+    fs::remove_file(input.join("C0002.MP4")).unwrap();
+
+    let executor = Arc::new(ScriptedExecutor::new(Script::success()));
+
+    let mut options = (*options(input, output.clone())).clone();
+    options.ffmpeg_options.loudness = Some(LoudnessTarget::default());
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    let calls = executor.calls();
+    assert_eq!(calls.len(), 1);
+    assert!(!calls[0].iter().any(|arg| arg.starts_with("loudnorm")));
+    assert_eq!(report.buckets[0].encoded[0].loudness, None);
+}
+
+#[tokio::test]
+async fn exports_audio_only() {
+    let (input, output) = setup("audio_only");
+
+    let executor = Arc::new(ScriptedExecutor::new(Script::success()));
+
+    let mut options = (*options(input, output.clone())).clone();
+    options.ffmpeg_options.audio_only = Some(AudioExport::new(AudioFormat::M4a));
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    assert_eq!(
+        bucket_files(&output),
+        ["C0001.m4a", "C0002.m4a", "xml/C0001M01.XML"]
+    );
+    assert!(report.buckets[0]
+        .encoded
+        .iter()
+        .all(|file| file.encoder == "aac"));
+
+    for args in executor.calls() {
+        assert!(args.contains(&"-vn".to_string()));
+        assert!(!args.contains(&"-c:v".to_string()));
+        assert!(args.iter().any(|arg| arg.starts_with("title=C000")));
+    }
+}
+
+#[tokio::test]
+async fn writes_thumbnails_of_the_converted_clips() {
+    let (input, output) = setup("thumbnails");
+
+    let executor = Arc::new(
+        ScriptedExecutor::new(Script::success())
+            .with_script("C0001.MP4", Script::failure("Invalid data found")),
+    );
+
+    let options = (*options(input.clone(), output.clone()))
+        .clone()
+        .with_thumbnails(ThumbnailOptions::default().with_contact_sheet(9));
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(Arc::new(options.clone()), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    // Only the clip that was converted, and no sheet without a duration to spread it over
+    assert_eq!(
+        bucket_files(&output),
+        ["C0002.mkv", "thumbs/C0002.jpg", "xml/C0001M01.XML"]
+    );
+
+    let encoded = &report.buckets[0].encoded[0];
+    let thumbnails = encoded.thumbnails.as_ref().unwrap();
+    assert_eq!(
+        thumbnails.poster.as_deref(),
+        Some(
+            report.buckets[0]
+                .output_dir
+                .join("thumbs/C0002.jpg")
+                .as_path()
+        )
+    );
+    assert_eq!(thumbnails.contact_sheet, None);
+
+    // Taken from the output, not the source
+    let poster = executor.calls().pop().unwrap();
+    assert!(poster.contains(&encoded.output.to_str().unwrap().to_string()));
+    assert!(poster.contains(&"-frames:v".to_string()));
+
+    let (_, output) = setup("thumbnails_next_to");
+    let options = ConverterOptions {
+        output_dir: output.clone(),
+        thumbnails: options
+            .thumbnails
+            .map(|t| t.with_placement(ThumbnailPlacement::NextToOutput)),
+        ..options
+    };
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter =
+        Converter::new(stop_rx).with_executor(Arc::new(ScriptedExecutor::new(Script::success())));
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+    converter.start_conversion(None).await.unwrap();
+
+    assert_eq!(
+        bucket_files(&output),
+        [
+            "C0001.jpg",
+            "C0001.mkv",
+            "C0002.jpg",
+            "C0002.mkv",
+            "xml/C0001M01.XML"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn writes_a_proxy_in_the_same_run() {
+    let (input, output) = setup("profiles");
+
+    let executor = Arc::new(ScriptedExecutor::new(Script::success()));
+
+    let base = options(input.clone(), output.clone());
+    let proxy = FfmpegOptions {
+        video_codec: ArgsType::Custom(VideoCodec::H264),
+        output_extension: OutputExtension::Mov,
+        ..base.ffmpeg_options.clone()
+    };
+    let options = (*base).clone().with_profile(
+        OutputProfile::new("proxy", proxy)
+            .with_subfolder("Proxy")
+            .with_suffix("_proxy"),
+    );
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    assert_eq!(
+        bucket_files(&output),
+        [
+            "C0001.mkv",
+            "C0002.mkv",
+            "Proxy/C0001_proxy.mov",
+            "Proxy/C0002_proxy.mov",
+            "xml/C0001M01.XML"
+        ]
+    );
+
+    // The source is read once for both outputs
+    let calls = executor.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(calls
+        .iter()
+        .all(|args| args.iter().filter(|arg| *arg == "-i").count() == 1));
+
+    let bucket = &report.buckets[0];
+    assert_eq!(bucket.encoded.len(), 4);
+    let proxies: Vec<_> = bucket
+        .encoded
+        .iter()
+        .filter(|e| e.profile.as_deref() == Some("proxy"))
+        .collect();
+    assert_eq!(proxies.len(), 2);
+    assert!(proxies.iter().all(|e| e.encoder == "libx264"));
+
+    // Both outputs are journaled on their own
+    let manifest: Manifest =
+        serde_json::from_slice(&fs::read(bucket.manifest.as_ref().unwrap()).unwrap()).unwrap();
+    let outputs: Vec<_> = manifest
+        .files
+        .iter()
+        .filter(|f| f.source_path == input.join("C0001.MP4"))
+        .map(|f| (f.profile.clone(), f.outcome.clone()))
+        .collect();
+    assert_eq!(
+        outputs,
+        [
+            (None, FileState::Done),
+            (Some("proxy".to_string()), FileState::Done)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn outputs_keep_the_date_of_their_source() {
+    let (input, output) = setup("dates");
+    let captured = SystemTime::UNIX_EPOCH + Duration::from_secs(1_730_894_400);
+    for clip in ["C0001.MP4", "C0002.MP4"] {
+        fs::File::options()
+            .write(true)
+            .open(input.join(clip))
+            .unwrap()
+            .set_modified(captured)
+            .unwrap();
+    }
+
+    let executor = Arc::new(ScriptedExecutor::new(Script::success()));
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+
+    converter
+        .prepare_task(options(input, output.clone()), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    let bucket = &report.buckets[0];
+    assert_eq!(bucket.encoded.len(), 2);
+    for encoded in bucket.encoded.iter() {
+        let modified = fs::metadata(&encoded.output).unwrap().modified().unwrap();
+        assert_eq!(modified, captured);
+    }
+
+    let title = format!("title={}", bucket.name);
+    for args in executor.calls() {
+        assert!(args.windows(2).any(|w| w == ["-metadata", title.as_str()]));
+        assert!(args.iter().any(|arg| arg.starts_with("creation_time=")));
+    }
+}
+
+#[tokio::test]
+async fn writes_a_manifest_into_the_bucket() {
+    let (input, output) = setup("manifest");
+    let executor = ScriptedExecutor::new(Script::success())
+        .with_script("C0002.MP4", Script::failure("Invalid data found"));
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(Arc::new(executor));
+
+    converter
+        .prepare_task(options(input.clone(), output.clone()), None)
+        .await
+        .unwrap();
+    let report = converter.start_conversion(None).await.unwrap();
+
+    let path = report.buckets[0].manifest.clone().unwrap();
+    assert_eq!(path, Manifest::path_for(&report.buckets[0].output_dir));
+
+    let manifest: Manifest = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+    assert_eq!(manifest.encoder_version.as_deref(), Some(FAKE_VERSION));
+    assert_eq!(manifest.options.input_dir, input);
+    assert_eq!(manifest.files.len(), 3);
+
+    let entry = |name: &str| {
+        manifest
+            .files
+            .iter()
+            .find(|e| e.source_path == input.join(name))
+            .unwrap()
+    };
+
+    let xml = entry("C0001M01.XML");
+    assert_eq!(xml.outcome, FileState::Done);
+    assert_eq!(xml.output.as_ref().unwrap().size, 6);
+    assert!(xml.attempts.is_empty());
+
+    let converted = entry("C0001.MP4");
+    let source = converted.source.as_ref().unwrap();
+    assert_eq!(source.size, 5);
+    assert_eq!(source.sha256.len(), 64);
+    let output = converted.output.as_ref().unwrap();
+    assert!(output.path.ends_with("C0001.mkv"));
+    assert_eq!(output.size, b"converted".len() as u64);
+    assert_eq!(converted.attempts.len(), 1);
+    assert!(converted.attempts[0]
+        .args
+        .contains(&"-progress".to_string()));
+
+    let failed = entry("C0002.MP4");
+    assert!(matches!(failed.outcome, FileState::Failed(_)));
+    assert!(failed.output.is_none());
+    assert_eq!(failed.attempts[0].exit_code, Some(1));
+}
+
+#[tokio::test]
+async fn cancellation_removes_temporary_outputs() {
+    let (input, output) = setup("cancel");
+    let slow = Script::success().with_steps(100, Duration::from_millis(20));
+
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let mut converter =
+        Converter::new(stop_rx).with_executor(Arc::new(ScriptedExecutor::new(slow)));
+
+    converter
+        .prepare_task(options(input, output.clone()), None)
+        .await
+        .unwrap();
+
+    let conversion = tokio::spawn(async move { converter.start_conversion(None).await });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    stop_tx.send(true).unwrap();
+
+    let report = conversion.await.unwrap().unwrap();
+    assert!(report.cancelled);
+    // Give the detached encodes a moment to be killed
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(bucket_files(&output), ["xml/C0001M01.XML"]);
+}
+
+#[tokio::test]
+async fn resume_redoes_only_unfinished_files() {
+    let (input, output) = setup("resume");
+    fs::write(input.join("C0003.MP4"), b"video").unwrap();
+    let options = options(input, output.clone());
+
+    // C0001 finishes, C0002 is still encoding when the job stops and C0003 fails
+    let interrupted = ScriptedExecutor::new(Script::success())
+        .with_script(
+            "C0002.MP4",
+            Script::success().with_steps(100, Duration::from_millis(20)),
+        )
+        .with_script("C0003.MP4", Script::failure("Invalid data found"));
+
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(Arc::new(interrupted));
+    converter.prepare_task(options.clone(), None).await.unwrap();
+
+    let conversion = tokio::spawn(async move { converter.start_conversion(None).await });
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    stop_tx.send(true).unwrap();
+
+    let report = conversion.await.unwrap().unwrap();
+    assert!(report.cancelled);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(bucket_files(&output), ["C0001.mkv", "xml/C0001M01.XML"]);
+
+    let executor = Arc::new(ScriptedExecutor::new(Script::success()));
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx).with_executor(executor.clone());
+    converter.prepare_task(options, None).await.unwrap();
+
+    let report = converter.resume(None).await.unwrap();
+    assert!(report.is_success());
+
+    let mut encoded: Vec<_> = executor
+        .calls()
+        .iter()
+        .map(|args| {
+            ["C0001.MP4", "C0002.MP4", "C0003.MP4"]
+                .into_iter()
+                .find(|clip| args.iter().any(|arg| arg.ends_with(clip)))
+                .unwrap()
+        })
+        .collect();
+    encoded.sort();
+    assert_eq!(encoded, ["C0002.MP4", "C0003.MP4"]);
+
+    assert_eq!(
+        bucket_files(&output),
+        ["C0001.mkv", "C0002.mkv", "C0003.mkv", "xml/C0001M01.XML"]
+    );
+}
+
+#[tokio::test]
+async fn reports_encode_progress() {
+    let (input, output) = setup("progress");
+    let script = Script::success().with_steps(5, Duration::from_millis(30));
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let progress_system = Arc::new(RwLock::new(ProgressSystem::new(10)));
+    let mut converter = Converter::new_with_progress_tracker(stop_rx, progress_system.clone())
+        .with_executor(Arc::new(ScriptedExecutor::new(script)));
+
+    converter
+        .prepare_task(options(input, output), None)
+        .await
+        .unwrap();
+
+    let conversion = tokio::spawn(async move { converter.start_conversion(None).await });
+
+    let mut saw_encoding = false;
+    loop {
+        let progress = progress_system.write().await.get_progress().await.unwrap();
+
+        saw_encoding |= progress
+            .iter()
+            .flat_map(|p| p.encoding().to_vec())
+            .any(|f| f.percentage().is_some_and(|p| p > 0.));
+
+        if !progress.is_empty() && progress.iter().all(|p| p.done()) {
+            assert!(progress.iter().all(|p| p.count() == p.total()));
+            break;
+        }
+    }
+
+    conversion.await.unwrap().unwrap();
+    assert!(saw_encoding);
+}
+
+#[tokio::test]
+async fn pause_holds_encodes_until_resumed() {
+    let (input, output) = setup("pause");
+    let script = Script::success().with_steps(10, Duration::from_millis(20));
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let progress_system = Arc::new(RwLock::new(ProgressSystem::new(10)));
+    let mut converter = Converter::new_with_progress_tracker(stop_rx, progress_system.clone())
+        .with_executor(Arc::new(ScriptedExecutor::new(script)));
+    let control = converter.control();
+
+    converter
+        .prepare_task(options(input, output.clone()), None)
+        .await
+        .unwrap();
+
+    // Keep only the latest snapshot, the channel buffers older ones
+    let latest = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let collector = {
+        let latest = latest.clone();
+        tokio::spawn(async move {
+            while let Some(progress) = { progress_system.write().await }.get_progress().await {
+                *latest.lock().unwrap() = progress.to_vec();
+            }
+        })
+    };
+    let snapshot = || -> (bool, Vec<u64>) {
+        let progress = latest.lock().unwrap();
+        let frames = progress
+            .iter()
+            .flat_map(|p| p.encoding().to_vec())
+            .map(|f| f.sample().frame())
+            .collect();
+        (
+            !progress.is_empty() && progress.iter().all(|p| p.paused()),
+            frames,
+        )
+    };
+
+    let conversion = tokio::spawn(async move { converter.start_conversion(None).await });
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    control.pause();
+
+    // Let the suspension settle, then nothing may move while paused
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let (_, before) = snapshot();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let (paused, after) = snapshot();
+
+    assert!(paused);
+    assert!(!after.is_empty());
+    assert_eq!(before, after);
+    assert!(!conversion.is_finished());
+
+    control.resume();
+    conversion.await.unwrap().unwrap();
+    collector.abort();
+
+    assert_eq!(
+        bucket_files(&output),
+        ["C0001.mkv", "C0002.mkv", "xml/C0001M01.XML"]
+    );
+}
+
+#[tokio::test]
+async fn refuses_to_start_without_enough_space() {
+    let (input, output) = setup("space");
+
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter =
+        Converter::new(stop_rx).with_executor(Arc::new(ScriptedExecutor::new(Script::success())));
+
+    let mut options = Arc::unwrap_or_clone(options(input, output.clone()));
+    options.space.min_free_mb = u32::MAX;
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+
+    let check = converter.check_space().unwrap();
+    assert_eq!(
+        check.estimated,
+        2 * b"video".len() as u64 + b"<xml/>".len() as u64
+    );
+    assert!(check.shortfall().is_some());
+
+    let Err(Error::NotEnoughSpace(refused)) = converter.start_conversion(None).await else {
+        panic!("the conversion should not start");
+    };
+    assert_eq!(refused, check);
+    assert!(!output.exists());
+}
+
+#[tokio::test]
+async fn pauses_while_the_output_drive_is_almost_full() {
+    let (input, output) = setup("low_space");
+
+    let (stop_tx, stop_rx) = watch::channel(false);
+    let progress_system = Arc::new(RwLock::new(ProgressSystem::new(10)));
+    let mut converter = Converter::new_with_progress_tracker(stop_rx, progress_system.clone())
+        .with_executor(Arc::new(ScriptedExecutor::new(Script::success())))
+        .ignore_space_check(true);
+    let control = converter.control();
+
+    let mut options = Arc::unwrap_or_clone(options(input, output));
+    options.space.min_free_mb = u32::MAX;
+    converter
+        .prepare_task(Arc::new(options), None)
+        .await
+        .unwrap();
+
+    let conversion = tokio::spawn(async move { converter.start_conversion(None).await });
+
+    let low_space = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let progress = { progress_system.write().await }
+                .get_progress()
+                .await
+                .unwrap();
+            if let Some(available) = progress.iter().find_map(|p| p.low_space()) {
+                break available;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(low_space < u32::MAX as u64 * 1024 * 1024);
+    assert!(control.is_paused());
+
+    stop_tx.send(true).unwrap();
+    let report = conversion.await.unwrap().unwrap();
+    assert!(report.cancelled);
+    // The next conversion with the same control mustn't start paused
+    assert!(!control.is_paused());
+}
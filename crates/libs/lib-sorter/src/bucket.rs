@@ -24,14 +24,17 @@ impl _Bucket {
         self.video_files.push(file);
     }
 }
+/// Files sharing a folder title. `M` is per video file information attached after sinking,
+/// stored in the same order as [`Bucket::video_files`].
 #[derive(Debug, Default, Clone)]
-pub struct Bucket {
+pub struct Bucket<M = ()> {
     folder_title: Arc<str>,
     xml_files: Arc<[PathBuf]>,
     video_files: Arc<[FileExt]>,
+    media: Arc<[M]>,
 }
 
-impl Bucket {
+impl<M> Bucket<M> {
     pub fn title(&self) -> Arc<str> {
         self.folder_title.clone()
     }
@@ -44,6 +47,23 @@ impl Bucket {
         self.video_files.clone()
     }
 
+    pub fn media(&self) -> Arc<[M]> {
+        self.media.clone()
+    }
+
+    /// Attach information to every video file, `media` must follow the order of `video_files`
+    pub fn with_media<N>(self, media: impl Into<Arc<[N]>>) -> Bucket<N> {
+        let media = media.into();
+        assert_eq!(media.len(), self.video_files.len());
+
+        Bucket {
+            folder_title: self.folder_title,
+            xml_files: self.xml_files,
+            video_files: self.video_files,
+            media,
+        }
+    }
+
     pub fn into_parts(self) -> (Arc<str>, Arc<[PathBuf]>, Arc<[FileExt]>) {
         (self.folder_title, self.xml_files, self.video_files)
     }
@@ -51,10 +71,12 @@ impl Bucket {
 
 impl From<_Bucket> for Bucket {
     fn from(value: _Bucket) -> Self {
+        let media = vec![(); value.video_files.len()];
         Self {
             folder_title: Arc::from(value.folder_title),
             xml_files: Arc::from(value.xml_files),
             video_files: Arc::from(value.video_files),
+            media: Arc::from(media),
         }
     }
 }
@@ -0,0 +1,102 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+/// Single key presses of the terminal, read on their own thread until [`KeyReader::stop`].
+pub(crate) struct KeyReader {
+    shutdown: Arc<AtomicBool>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    thread: JoinHandle<()>,
+}
+
+impl KeyReader {
+    pub(crate) fn spawn(on_key: impl FnMut(char) + Send + 'static) -> Self {
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let flag = shutdown.clone();
+        let thread = std::thread::spawn(move || read_keys(&flag, on_key));
+
+        Self { shutdown, thread }
+    }
+
+    /// Stops reading and gives the terminal back the way it was found.
+    pub(crate) fn stop(self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        // The Windows read can't be interrupted, it doesn't change the console mode either
+        #[cfg(unix)]
+        let _ = self.thread.join();
+    }
+}
+
+/// Reads `/dev/tty` without line buffering nor echo, waking up regularly to notice `shutdown`.
+/// ctrl-c still raises SIGINT.
+#[cfg(unix)]
+fn read_keys(shutdown: &AtomicBool, mut on_key: impl FnMut(char)) {
+    use std::{fs::File, io::Read, mem::MaybeUninit, os::fd::AsRawFd};
+
+    let Ok(mut tty) = File::open("/dev/tty") else {
+        return;
+    };
+    let fd = tty.as_raw_fd();
+
+    let mut saved = MaybeUninit::<libc::termios>::uninit();
+    // SAFETY: `fd` is open and `saved` is only read once `tcgetattr` filled it
+    if unsafe { libc::tcgetattr(fd, saved.as_mut_ptr()) } != 0 {
+        return;
+    }
+    let saved = unsafe { saved.assume_init() };
+
+    let mut keys = saved;
+    keys.c_lflag &= !(libc::ICANON | libc::ECHO);
+    keys.c_cc[libc::VMIN] = 1;
+    keys.c_cc[libc::VTIME] = 0;
+    // SAFETY: `fd` is open and `keys` a valid termios
+    if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &keys) } != 0 {
+        return;
+    }
+
+    while !shutdown.load(Ordering::Relaxed) {
+        let mut poll = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: a single valid pollfd
+        let ready = unsafe { libc::poll(&mut poll, 1, 100) };
+
+        if ready <= 0 {
+            // Timed out, or interrupted by a signal
+            continue;
+        }
+        if poll.revents & (libc::POLLHUP | libc::POLLERR | libc::POLLNVAL) != 0 {
+            break;
+        }
+
+        let mut byte = [0u8];
+        match tty.read(&mut byte) {
+            Ok(1) => on_key(byte[0] as char),
+            Ok(_) => break,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) => break,
+        }
+    }
+
+    // SAFETY: `fd` is still open, `saved` is what `tcgetattr` gave
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &saved) };
+}
+
+#[cfg(not(unix))]
+fn read_keys(shutdown: &AtomicBool, mut on_key: impl FnMut(char)) {
+    let term = console::Term::stdout();
+
+    while !shutdown.load(Ordering::Relaxed) {
+        match term.read_key() {
+            Ok(console::Key::Char(key)) => on_key(key),
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+}
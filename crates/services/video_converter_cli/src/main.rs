@@ -1,11 +1,17 @@
 use clap::Parser;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use console::Term;
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
 use lib_core::{
     types::{
-        ArgsType, AudioCodec, Converter, ConverterOptions, FfmpegOptions, HwAccel, OutputExtension,
-        VideoCodec,
+        ArgsType, AudioCodec, AudioExport, AudioFormat, AudioSelection, AudioTrackCodec,
+        BucketReport, Capabilities, CineFormQuality, CodecProfile, ConflictPolicy, ConversionPlan,
+        ConversionReport, Converter, ConverterOptions, DnxHrProfile, EncoderPreset, EncoderTune,
+        FfmpegOptions, FrameRate, FrameRateMode, HwAccel, ImageFormat, Keyframes, Loudness,
+        LoudnessTarget, OutputExtension, OutputProfile, ProresProfile, RateControl, Resolution,
+        RetryPolicy, SchedulerOptions, SpaceOptions, StreamMapping, ThumbnailOptions,
+        ThumbnailPlacement, VideoCodec,
     },
-    ProgressSystem,
+    ConflictDecision, ProgressSystem,
 };
 #[cfg(feature = "embedded")]
 use once_cell::sync::OnceCell;
@@ -15,6 +21,10 @@ use rust_embed::Embed;
 use std::{collections::HashMap, error::Error, path::PathBuf, sync::Arc, time::Duration};
 use tokio::{sync::RwLock, task::JoinSet, time::sleep};
 
+use keys::KeyReader;
+
+mod keys;
+
 #[cfg(feature = "embedded")]
 #[derive(Embed)]
 #[folder = "$CARGO_MANIFEST_DIR/../../../binaries"]
@@ -23,29 +33,38 @@ struct Binaries;
 #[cfg(feature = "embedded")]
 fn ffmpeg_instance() -> &'static PathBuf {
     static INSTANCE: OnceCell<PathBuf> = OnceCell::new();
-    INSTANCE.get_or_init(|| {
-        use std::fs::File;
-        use std::io::Write;
+    INSTANCE.get_or_init(|| extract_binary("ffmpeg"))
+}
 
-        let ffmpeg_name = if cfg!(target_os = "windows") {
-            "ffmpeg.exe"
-        } else {
-            "ffmpeg"
-        };
+#[cfg(feature = "embedded")]
+fn ffprobe_instance() -> &'static PathBuf {
+    static INSTANCE: OnceCell<PathBuf> = OnceCell::new();
+    INSTANCE.get_or_init(|| extract_binary("ffprobe"))
+}
+
+#[cfg(feature = "embedded")]
+fn extract_binary(name: &str) -> PathBuf {
+    use std::fs::File;
+    use std::io::Write;
 
-        let temp_dir = std::env::temp_dir();
+    let binary_name = if cfg!(target_os = "windows") {
+        format!("{name}.exe")
+    } else {
+        name.to_string()
+    };
 
-        let ffmpeg_path = temp_dir.join(ffmpeg_name);
+    let temp_dir = std::env::temp_dir();
 
-        if !ffmpeg_path.exists() {
-            {
-                let mut file = File::create(&ffmpeg_path).unwrap();
-                file.write_all(&Binaries::get(ffmpeg_name).unwrap().data)
-                    .unwrap();
-            }
+    let binary_path = temp_dir.join(&binary_name);
+
+    if !binary_path.exists() {
+        {
+            let mut file = File::create(&binary_path).unwrap();
+            file.write_all(&Binaries::get(&binary_name).unwrap().data)
+                .unwrap();
         }
-        ffmpeg_path
-    })
+    }
+    binary_path
 }
 
 #[derive(Parser, Debug)]
@@ -55,12 +74,662 @@ struct Args {
 
     #[arg(short)]
     output: PathBuf,
+
+    /// Continue an interrupted conversion in the output folder
+    #[arg(short, long)]
+    resume: bool,
+
+    /// What to do with outputs that already exist : skip, overwrite, rename or fail
+    #[arg(long, value_parser = parse_conflict_policy, default_value = "skip")]
+    conflict: ConflictPolicy,
+
+    /// Print what the conversion would do without writing anything. The sources aren't probed,
+    /// the ffmpeg commands leave out the stream mapping and loudness normalization
+    #[arg(long)]
+    plan: bool,
+
+    /// Print the plan as JSON instead of a table
+    #[arg(long, requires = "plan")]
+    json: bool,
+
+    /// Maximum number of encodes running at once, across all buckets
+    #[arg(short, long)]
+    jobs: Option<u32>,
+
+    /// Maximum number of software (CPU) encodes at once
+    #[arg(long)]
+    software_slots: Option<u32>,
+
+    /// Maximum number of NVENC sessions at once
+    #[arg(long)]
+    nvenc_slots: Option<u32>,
+
+    /// Maximum number of Quick Sync sessions at once
+    #[arg(long)]
+    qsv_slots: Option<u32>,
+
+    /// Maximum number of AMF sessions at once
+    #[arg(long)]
+    amf_slots: Option<u32>,
+
+    /// Passed to ffmpeg as -threads
+    #[arg(long)]
+    threads: Option<u32>,
+
+    /// How often every encoder is tried on a file before falling back
+    #[arg(long, default_value_t = 1)]
+    attempts: u32,
+
+    /// Wait before the first retry in milliseconds, doubled on every further one
+    #[arg(long, default_value_t = 1000)]
+    retry_backoff: u32,
+
+    /// Encoders to fall back to when encoding fails, in order, e.g. h264_qsv,libx264
+    #[arg(long, value_parser = parse_video_codec, value_delimiter = ',')]
+    fallback: Vec<VideoCodec>,
+
+    /// Start even when the output drive looks too small for the conversion
+    #[arg(long)]
+    ignore_space: bool,
+
+    /// Pause the conversion while the output drive has less free space, in MB
+    #[arg(long)]
+    min_free_space: Option<u32>,
+
+    /// Encode at constant quality instead of a bitrate, lower is better (CRF, CQ, global_quality
+    /// or QP depending on the encoder)
+    #[arg(long, conflicts_with_all = ["cbr", "vbr"])]
+    quality: Option<u8>,
+
+    /// Encode at a constant bitrate, in kbit/s
+    #[arg(long, conflicts_with = "vbr")]
+    cbr: Option<u32>,
+
+    /// Encode at a variable bitrate averaging this many kbit/s, capped by --maxrate
+    #[arg(long, requires = "maxrate")]
+    vbr: Option<u32>,
+
+    /// Highest bitrate of --vbr, in kbit/s
+    #[arg(long, requires = "vbr")]
+    maxrate: Option<u32>,
+
+    /// Rate control buffer of --vbr in kbit, twice --maxrate by default
+    #[arg(long, requires = "vbr")]
+    bufsize: Option<u32>,
+
+    /// Encoder preset : fastest, fast, medium, slow or slowest
+    #[arg(long, value_parser = parse_preset)]
+    preset: Option<EncoderPreset>,
+
+    /// Encoder tune : film, animation, grain, stillimage, fastdecode or zerolatency
+    #[arg(long, value_parser = parse_tune)]
+    tune: Option<EncoderTune>,
+
+    /// Frame rate of the outputs, e.g. 25, 29.97 or 30000/1001
+    #[arg(long, value_parser = parse_frame_rate)]
+    frame_rate: Option<FrameRate>,
+
+    /// Convert variable frame rate sources to a constant rate, --frame-rate or the one of the
+    /// source
+    #[arg(long)]
+    cfr: bool,
+
+    /// A keyframe every this many frames, ignored by ProRes, CineForm and DNxHR
+    #[arg(long, conflicts_with = "all_intra")]
+    gop: Option<u32>,
+
+    /// Make every frame a keyframe
+    #[arg(long)]
+    all_intra: bool,
+
+    /// Move the index of MOV and MP4 outputs to the front of the file
+    #[arg(long)]
+    faststart: bool,
+
+    /// Profile of the ProRes, CineForm or DNxHR encodes as CODEC:PROFILE, e.g. prores:proxy,
+    /// cineform:film2 or dnxhr:hqx
+    #[arg(long, value_parser = parse_codec_profile)]
+    codec_profile: Option<CodecProfile>,
+
+    /// Normalize the audio loudness in two passes, EBU R128 (-23 LUFS) unless overridden
+    #[arg(long)]
+    loudnorm: bool,
+
+    /// Integrated loudness target of --loudnorm, in LUFS
+    #[arg(long, requires = "loudnorm", allow_hyphen_values = true)]
+    target_lufs: Option<f64>,
+
+    /// Maximum true peak of --loudnorm, in dBTP
+    #[arg(long, requires = "loudnorm", allow_hyphen_values = true)]
+    true_peak: Option<f64>,
+
+    /// Loudness range target of --loudnorm, in LU
+    #[arg(long, requires = "loudnorm")]
+    lra: Option<f64>,
+
+    /// Export only the audio : mp3, m4a, opus, flac or wav
+    #[arg(long, value_parser = parse_audio_format)]
+    audio_only: Option<AudioFormat>,
+
+    /// Store ALAC instead of AAC in --audio-only m4a
+    #[arg(long, requires = "audio_only")]
+    alac: bool,
+
+    /// Sample rate of --audio-only, in Hz
+    #[arg(long, requires = "audio_only")]
+    sample_rate: Option<u32>,
+
+    /// Channels of --audio-only, 1 for mono and 2 for stereo
+    #[arg(long, requires = "audio_only")]
+    channels: Option<u8>,
+
+    /// Audio stream exported by --audio-only, counted from 0
+    #[arg(long, requires = "audio_only", default_value_t = 0)]
+    audio_stream: u32,
+
+    /// Don't tag --audio-only exports with a title and a date
+    #[arg(long, requires = "audio_only")]
+    no_tags: bool,
+
+    /// Write a poster frame of every converted clip into a `thumbs` folder of its bucket
+    #[arg(long, conflicts_with = "audio_only")]
+    thumbnails: bool,
+
+    /// Write the --thumbnails next to the converted clips instead
+    #[arg(long, requires = "thumbnails")]
+    thumbnails_next_to_output: bool,
+
+    /// Write the --thumbnails as PNG instead of JPEG
+    #[arg(long, requires = "thumbnails")]
+    png: bool,
+
+    /// Width of the --thumbnails in pixels
+    #[arg(long, requires = "thumbnails")]
+    thumbnail_width: Option<u32>,
+
+    /// Also write a contact sheet of this many evenly spaced frames
+    #[arg(long, requires = "thumbnails")]
+    contact_sheet: Option<u32>,
+
+    /// Also write a 720p editing proxy of every clip into a `Proxy` folder of its bucket
+    #[arg(long, conflicts_with = "audio_only")]
+    proxy: bool,
+
+    /// Encoder of the --proxy, the one of the main output by default
+    #[arg(long, requires = "proxy", value_parser = parse_video_codec)]
+    proxy_codec: Option<VideoCodec>,
+
+    /// Video bitrate of the --proxy, in kbit/s
+    #[arg(long, requires = "proxy", default_value_t = 5000)]
+    proxy_bitrate: u32,
+
+    /// Keep every audio track, the subtitles the container can hold, the timecode track, the
+    /// chapters and the tags of the source instead of a single video and audio stream
+    #[arg(long, conflicts_with = "audio_only")]
+    all_streams: bool,
+
+    /// Audio tracks to keep, counted from 0, e.g. 0,2
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["audio_only", "audio_languages"])]
+    audio_tracks: Vec<u32>,
+
+    /// Audio tracks to keep by language, e.g. eng,jpn
+    #[arg(long, value_delimiter = ',', conflicts_with = "audio_only")]
+    audio_languages: Vec<String>,
+
+    /// Codec of a single kept audio track as TRACK=CODEC[:KBPS], e.g. 1=aac:192
+    #[arg(long, value_parser = parse_audio_track_codec, conflicts_with = "audio_only")]
+    audio_track_codec: Vec<AudioTrackCodec>,
+}
+
+impl Args {
+    fn scheduler(&self) -> SchedulerOptions {
+        let default = SchedulerOptions::default();
+
+        SchedulerOptions {
+            max_jobs: self.jobs.unwrap_or(default.max_jobs),
+            software_slots: self.software_slots.unwrap_or(default.software_slots),
+            nvenc_slots: self.nvenc_slots.unwrap_or(default.nvenc_slots),
+            qsv_slots: self.qsv_slots.unwrap_or(default.qsv_slots),
+            amf_slots: self.amf_slots.unwrap_or(default.amf_slots),
+            threads: self.threads,
+        }
+    }
+
+    fn retry(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.attempts,
+            backoff_ms: self.retry_backoff,
+            fallback: self.fallback.clone(),
+        }
+    }
+
+    fn rate_control(&self) -> Option<RateControl> {
+        if let Some(value) = self.quality {
+            return Some(RateControl::Quality { value });
+        }
+        if let Some(bitrate) = self.cbr {
+            return Some(RateControl::Cbr { bitrate });
+        }
+
+        let (bitrate, max_bitrate) = (self.vbr?, self.maxrate?);
+        Some(RateControl::Vbr {
+            bitrate,
+            max_bitrate,
+            buffer_size: self.bufsize.unwrap_or(max_bitrate * 2),
+        })
+    }
+
+    fn loudness(&self) -> Option<LoudnessTarget> {
+        if !self.loudnorm {
+            return None;
+        }
+
+        let default = LoudnessTarget::default();
+        Some(LoudnessTarget {
+            integrated: self.target_lufs.unwrap_or(default.integrated),
+            true_peak: self.true_peak.unwrap_or(default.true_peak),
+            lra: self.lra.unwrap_or(default.lra),
+        })
+    }
+
+    fn audio_only(&self) -> Option<AudioExport> {
+        let export = AudioExport {
+            codec: self.alac.then_some(AudioCodec::Alac),
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            stream: self.audio_stream,
+            tags: !self.no_tags,
+            ..AudioExport::new(self.audio_only?)
+        };
+
+        Some(export)
+    }
+
+    fn thumbnails(&self) -> Option<ThumbnailOptions> {
+        if !self.thumbnails {
+            return None;
+        }
+
+        let default = ThumbnailOptions::default();
+        Some(ThumbnailOptions {
+            format: if self.png {
+                ImageFormat::Png
+            } else {
+                ImageFormat::Jpeg
+            },
+            placement: if self.thumbnails_next_to_output {
+                ThumbnailPlacement::NextToOutput
+            } else {
+                ThumbnailPlacement::ThumbsFolder
+            },
+            width: self.thumbnail_width.unwrap_or(default.width),
+            contact_sheet: self.contact_sheet,
+        })
+    }
+
+    /// Same decoding as `main`, so the proxy is written by the run of the main output.
+    fn proxy(&self, main: &FfmpegOptions) -> Option<OutputProfile> {
+        if !self.proxy {
+            return None;
+        }
+
+        let options = FfmpegOptions {
+            resolution: ArgsType::Custom(Resolution::R720P),
+            audio_codec: ArgsType::Custom(AudioCodec::Aac),
+            video_codec: self.proxy_codec.map_or(main.video_codec, ArgsType::Custom),
+            audio_bitrate: ArgsType::MatchSource,
+            video_bitrate: ArgsType::Custom(self.proxy_bitrate),
+            output_extension: OutputExtension::Mov,
+            rate_control: None,
+            ..main.clone()
+        };
+
+        Some(
+            OutputProfile::new("proxy", options)
+                .with_subfolder("Proxy")
+                .with_suffix("_proxy"),
+        )
+    }
+
+    fn streams(&self) -> Option<StreamMapping> {
+        let audio = if !self.audio_tracks.is_empty() {
+            AudioSelection::Indices(self.audio_tracks.clone())
+        } else if !self.audio_languages.is_empty() {
+            AudioSelection::Languages(self.audio_languages.clone())
+        } else if self.all_streams || !self.audio_track_codec.is_empty() {
+            AudioSelection::All
+        } else {
+            return None;
+        };
+
+        let streams = StreamMapping {
+            audio,
+            audio_codecs: self.audio_track_codec.clone(),
+            // Only the selected audio tracks otherwise
+            subtitles: self.all_streams,
+            data: self.all_streams,
+            chapters: self.all_streams,
+            metadata: self.all_streams,
+        };
+
+        Some(streams)
+    }
+
+    fn keyframes(&self) -> Option<Keyframes> {
+        match (self.gop, self.all_intra) {
+            (_, true) => Some(Keyframes::AllIntra),
+            (Some(frames), false) => Some(Keyframes::Interval(frames)),
+            (None, false) => None,
+        }
+    }
+
+    fn space(&self) -> SpaceOptions {
+        let default = SpaceOptions::default();
+
+        SpaceOptions {
+            min_free_mb: self.min_free_space.unwrap_or(default.min_free_mb),
+            ..default
+        }
+    }
+}
+
+fn parse_conflict_policy(value: &str) -> Result<ConflictPolicy, String> {
+    match value {
+        "skip" => Ok(ConflictPolicy::Skip),
+        "overwrite" => Ok(ConflictPolicy::Overwrite),
+        "rename" => Ok(ConflictPolicy::Rename),
+        "fail" => Ok(ConflictPolicy::Fail),
+        _ => Err(format!("unknown conflict policy : {value}")),
+    }
+}
+
+fn parse_preset(value: &str) -> Result<EncoderPreset, String> {
+    match value {
+        "fastest" => Ok(EncoderPreset::Fastest),
+        "fast" => Ok(EncoderPreset::Fast),
+        "medium" => Ok(EncoderPreset::Medium),
+        "slow" => Ok(EncoderPreset::Slow),
+        "slowest" => Ok(EncoderPreset::Slowest),
+        _ => Err(format!("unknown preset : {value}")),
+    }
+}
+
+fn parse_tune(value: &str) -> Result<EncoderTune, String> {
+    match value {
+        "film" => Ok(EncoderTune::Film),
+        "animation" => Ok(EncoderTune::Animation),
+        "grain" => Ok(EncoderTune::Grain),
+        "stillimage" => Ok(EncoderTune::StillImage),
+        "fastdecode" => Ok(EncoderTune::FastDecode),
+        "zerolatency" => Ok(EncoderTune::ZeroLatency),
+        _ => Err(format!("unknown tune : {value}")),
+    }
+}
+
+fn parse_frame_rate(value: &str) -> Result<FrameRate, String> {
+    let invalid = || format!("expected FPS or NUM/DEN : {value}");
+
+    if let Some((numerator, denominator)) = value.split_once('/') {
+        let numerator = numerator.parse().map_err(|_| invalid())?;
+        let denominator = denominator.parse().map_err(|_| invalid())?;
+        return match denominator {
+            0 => Err(invalid()),
+            denominator => Ok(FrameRate::new(numerator, denominator)),
+        };
+    }
+    if let Ok(fps) = value.parse() {
+        return Ok(FrameRate::fps(fps));
+    }
+
+    // Decimal rates are the NTSC ones, 23.976 is 24000/1001
+    let fps: f64 = value.parse().map_err(|_| invalid())?;
+    let ntsc = fps.ceil() as u32;
+    if (ntsc as f64 * 1000.0 / 1001.0 - fps).abs() < 0.01 {
+        Ok(FrameRate::ntsc(ntsc))
+    } else {
+        Err(invalid())
+    }
+}
+
+fn parse_codec_profile(value: &str) -> Result<CodecProfile, String> {
+    let (codec, profile) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected CODEC:PROFILE : {value}"))?;
+
+    let profile = match (codec, profile) {
+        ("prores", "proxy") => CodecProfile::Prores(ProresProfile::Proxy),
+        ("prores", "lt") => CodecProfile::Prores(ProresProfile::Lt),
+        ("prores", "standard") => CodecProfile::Prores(ProresProfile::Standard),
+        ("prores", "hq") => CodecProfile::Prores(ProresProfile::Hq),
+        ("prores", "4444") => CodecProfile::Prores(ProresProfile::P4444),
+        ("cineform", "low") => CodecProfile::CineForm(CineFormQuality::Low),
+        ("cineform", "medium") => CodecProfile::CineForm(CineFormQuality::Medium),
+        ("cineform", "high") => CodecProfile::CineForm(CineFormQuality::High),
+        ("cineform", "film1") => CodecProfile::CineForm(CineFormQuality::Film1),
+        ("cineform", "film2") => CodecProfile::CineForm(CineFormQuality::Film2),
+        ("cineform", "film3") => CodecProfile::CineForm(CineFormQuality::Film3),
+        ("dnxhr", "lb") => CodecProfile::DnxHr(DnxHrProfile::Lb),
+        ("dnxhr", "sq") => CodecProfile::DnxHr(DnxHrProfile::Sq),
+        ("dnxhr", "hq") => CodecProfile::DnxHr(DnxHrProfile::Hq),
+        ("dnxhr", "hqx") => CodecProfile::DnxHr(DnxHrProfile::Hqx),
+        ("dnxhr", "444") => CodecProfile::DnxHr(DnxHrProfile::P444),
+        _ => return Err(format!("unknown codec profile : {value}")),
+    };
+
+    Ok(profile)
+}
+
+fn parse_audio_format(value: &str) -> Result<AudioFormat, String> {
+    match value {
+        "mp3" => Ok(AudioFormat::Mp3),
+        "m4a" => Ok(AudioFormat::M4a),
+        "opus" => Ok(AudioFormat::Opus),
+        "flac" => Ok(AudioFormat::Flac),
+        "wav" => Ok(AudioFormat::Wav),
+        _ => Err(format!("unknown audio format : {value}")),
+    }
+}
+
+fn parse_audio_track_codec(value: &str) -> Result<AudioTrackCodec, String> {
+    let invalid = || format!("expected TRACK=CODEC[:KBPS] : {value}");
+
+    let (track, codec) = value.split_once('=').ok_or_else(invalid)?;
+    let (codec, bitrate) = match codec.split_once(':') {
+        Some((codec, bitrate)) => (codec, Some(bitrate.parse().map_err(|_| invalid())?)),
+        None => (codec, None),
+    };
+
+    let codec = AudioCodec::ALL
+        .into_iter()
+        .find(|c| c.to_string() == codec)
+        .ok_or_else(|| format!("unknown audio encoder : {codec}"))?;
+
+    Ok(AudioTrackCodec {
+        track: track.parse().map_err(|_| invalid())?,
+        codec,
+        bitrate,
+    })
+}
+
+fn parse_video_codec(value: &str) -> Result<VideoCodec, String> {
+    VideoCodec::ALL
+        .into_iter()
+        .find(|codec| codec.to_string() == value)
+        .ok_or_else(|| format!("unknown video encoder : {value}"))
+}
+
+fn print_plan(plan: &ConversionPlan) {
+    println!("{:?} -> {:?}", plan.input_dir, plan.output_dir);
+
+    for bucket in plan.buckets.iter() {
+        println!();
+        println!("[{}] {:?}", bucket.name, bucket.output_dir);
+
+        for copy in bucket.copies.iter() {
+            println!(
+                "  {:8} {:?} -> {:?}{}",
+                "copy",
+                copy.source,
+                copy.target,
+                if copy.exists { " (exists)" } else { "" }
+            );
+        }
+
+        for encode in bucket.encodes.iter() {
+            println!(
+                "  {:8} {:?} -> {:?}{}",
+                encode.profile.as_deref().unwrap_or("encode"),
+                encode.source,
+                encode.output,
+                if encode.exists { " (exists)" } else { "" }
+            );
+            println!("  {:8} ffmpeg {}", "approx.", encode.args.join(" "));
+        }
+    }
+}
+
+fn print_report(report: &ConversionReport) {
+    let count = |f: fn(&BucketReport) -> usize| report.buckets.iter().map(f).sum::<usize>();
+
+    println!(
+        "{} encoded, {} copied, {} skipped, {} failed{}",
+        count(|b| b.encoded.len()),
+        count(|b| b.copied.len()),
+        count(|b| b.skipped.len()),
+        count(|b| b.failures.len()),
+        if report.cancelled { " (cancelled)" } else { "" }
+    );
+
+    for bucket in report.buckets.iter() {
+        if let Some(error) = &bucket.error {
+            println!("[{}] {}", bucket.name, error);
+        }
+    }
+
+    for bucket in report.buckets.iter() {
+        for file in bucket.encoded.iter() {
+            let Some(loudness) = file.loudness else {
+                continue;
+            };
+            let lufs = |loudness: Option<Loudness>| {
+                loudness.map_or("?".to_string(), |l| format!("{:.1}", l.integrated))
+            };
+            println!(
+                "[{}] {:?} : {} LUFS -> {} LUFS",
+                bucket.name,
+                file.source,
+                lufs(loudness.measured),
+                lufs(loudness.result)
+            );
+        }
+    }
+
+    for (bucket, failure) in report.failures() {
+        let exit_code = failure
+            .exit_code
+            .map(|code| format!(" (exit code {code})"))
+            .unwrap_or_default();
+        let profile = failure
+            .profile
+            .as_ref()
+            .map(|profile| format!(" [{profile}]"))
+            .unwrap_or_default();
+        println!(
+            "[{}] {:?}{} : {}{}",
+            bucket,
+            failure.source,
+            profile,
+            failure.summary(),
+            exit_code
+        );
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
+    let ffmpeg_option = FfmpegOptions {
+        rate_control: args.rate_control(),
+        preset: args.preset,
+        tune: args.tune,
+        loudness: args.loudness(),
+        audio_only: args.audio_only(),
+        streams: args.streams(),
+        frame_rate: args.frame_rate,
+        frame_rate_mode: if args.cfr {
+            FrameRateMode::Constant
+        } else {
+            FrameRateMode::Auto
+        },
+        keyframes: args.keyframes(),
+        faststart: args.faststart,
+        codec_profile: args.codec_profile,
+        ..FfmpegOptions::new(
+            ArgsType::MatchSource,
+            Some(HwAccel::Cuda),
+            // None,
+            ArgsType::Custom(AudioCodec::Flac),
+            ArgsType::Custom(VideoCodec::H264NVENC),
+            // ArgsType::Custom(VideoCodec::H264QSV),
+            ArgsType::MatchSource,
+            ArgsType::Custom(10000),
+            ArgsType::MatchSource,
+            OutputExtension::Mkv,
+        )
+        .with_retry(args.retry())
+    };
+
+    let scheduler = args.scheduler();
+    let space = args.space();
+    let thumbnails = args.thumbnails();
+    let profiles = args.proxy(&ffmpeg_option).into_iter().collect();
+    let options = Arc::new(ConverterOptions {
+        thumbnails,
+        profiles,
+        ..ConverterOptions::new(args.input, args.output, true, ffmpeg_option)
+            .with_conflict_policy(args.conflict)
+            .with_scheduler(scheduler)
+            .with_space(space)
+    });
+
+    if args.plan {
+        let plan = Converter::plan(&options)?;
+
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&plan)?);
+        } else {
+            print_plan(&plan);
+        }
+
+        return Ok(());
+    }
+
+    #[cfg(feature = "embedded")]
+    let ffmpeg_executable = Some(ffmpeg_instance());
+
+    #[cfg(not(feature = "embedded"))]
+    let ffmpeg_executable = None;
+
+    // Refuse what the ffmpeg build or the machine can't do instead of failing every file
+    let capabilities = Capabilities::detect(ffmpeg_executable).await?;
+    let mut unsupported = capabilities.unsupported(&options.ffmpeg_options);
+    for profile in options.profiles.iter() {
+        unsupported.extend(
+            capabilities
+                .unsupported(&profile.ffmpeg_options)
+                .into_iter()
+                .map(|reason| format!("{} : {reason}", profile.name)),
+        );
+    }
+    if !unsupported.is_empty() {
+        return Err(format!(
+            "ffmpeg can't convert with these options :\n  {}",
+            unsupported.join("\n  ")
+        )
+        .into());
+    }
+
     let mut join_set = JoinSet::new();
 
     let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
@@ -68,48 +737,51 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let progress_system = Arc::new(RwLock::new(ProgressSystem::new(200)));
 
     let mut converter =
-        Converter::new_with_progress_tracker(stop_rx.clone(), progress_system.clone());
-
-    let ffmpeg_option = FfmpegOptions::new(
-        ArgsType::MatchSource,
-        Some(HwAccel::Cuda),
-        // None,
-        ArgsType::Custom(AudioCodec::Flac),
-        ArgsType::Custom(VideoCodec::H264NVENC),
-        // ArgsType::Custom(VideoCodec::H264QSV),
-        ArgsType::MatchSource,
-        ArgsType::Custom(10000),
-        ArgsType::MatchSource,
-        OutputExtension::Mkv,
-    );
+        Converter::new_with_progress_tracker(stop_rx.clone(), progress_system.clone())
+            .ignore_space_check(args.ignore_space);
 
-    join_set.spawn(async move {
-        converter
-            .prepare_task(Arc::new(ConverterOptions::new(
-                args.input,
-                args.output,
-                true,
-                ffmpeg_option,
-            )))
-            .await
-            .unwrap();
+    let control = converter.control();
 
+    let stop_tx_clone = stop_tx.clone();
+    join_set.spawn(async move {
         #[cfg(feature = "embedded")]
         converter
-            .start_conversion(Some(ffmpeg_instance()))
+            .prepare_task(options, Some(ffprobe_instance()))
             .await
             .unwrap();
 
         #[cfg(not(feature = "embedded"))]
-        converter.start_conversion(None).await.unwrap();
+        converter.prepare_task(options, None).await.unwrap();
+
+        let report = if args.resume {
+            converter.resume(ffmpeg_executable).await
+        } else {
+            converter.start_conversion(ffmpeg_executable).await
+        };
+
+        match report {
+            Ok(report) => print_report(&report),
+            Err(err) => {
+                eprintln!("{err}");
+                if matches!(err, lib_core::Error::NotEnoughSpace(_)) {
+                    eprintln!("Free some space or run again with --ignore-space");
+                }
+                stop_tx_clone.send(true).unwrap();
+            }
+        }
     });
 
     let progress_system_clone = progress_system.clone();
     let stop_rx_clone = stop_rx.clone();
-    let stop_tx_clone = stop_tx.clone();
     join_set.spawn(async move {
         let mut bar_map = HashMap::new();
+        let mut file_bar_map: HashMap<(Arc<str>, Arc<str>), ProgressBar> = HashMap::new();
+        let mut reported_conflicts: HashMap<Arc<str>, usize> = HashMap::new();
+        let mut reported_errors: HashMap<Arc<str>, usize> = HashMap::new();
         let multi_prog = MultiProgress::new();
+        if Term::stdout().is_term() {
+            multi_prog.println("Press p to pause or resume").unwrap();
+        }
         while let Some(progress_list) = { progress_system_clone.write().await }.get_progress().await
         {
             let all_done = if !progress_list.is_empty() {
@@ -119,14 +791,13 @@ async fn main() -> Result<(), Box<dyn Error>> {
             };
 
             if all_done.is_some_and(|val| val) {
-                stop_tx_clone.send(true).unwrap();
                 break;
             }
 
             if *stop_rx_clone.borrow() {
                 break;
             }
-            progress_list.into_iter().for_each(|progress| {
+            progress_list.iter().for_each(|progress| {
                 bar_map
                     .entry(progress.folder().to_owned())
                     .and_modify(|pb: &mut ProgressBar| {
@@ -137,7 +808,19 @@ async fn main() -> Result<(), Box<dyn Error>> {
                             }
                             pb.set_position(progress.count() as u64);
                         }
-                        pb.set_message(format!("[{}] {}", progress.folder(), progress.file()));
+                        let paused = match progress.low_space() {
+                            Some(available) => {
+                                format!(" (paused, {} left on drive)", HumanBytes(available))
+                            }
+                            None if progress.paused() => " (paused)".to_string(),
+                            None => String::new(),
+                        };
+                        pb.set_message(format!(
+                            "[{}] {}{}",
+                            progress.folder(),
+                            progress.file(),
+                            paused
+                        ));
                     })
                     .or_insert_with(|| {
                         let mut pb = ProgressBar::new(progress.total() as u64);
@@ -151,6 +834,87 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         pb.inc(progress.count() as u64);
                         pb
                     });
+
+                let file_bar = || {
+                    let pb = multi_prog.add(ProgressBar::new(100));
+                    pb.set_style(
+                        ProgressStyle::with_template("  {msg:38} {bar:30.green/white} {pos:>6}%")
+                            .unwrap(),
+                    );
+                    pb
+                };
+
+                // One sub bar per sidecar being copied
+                let copying = progress.copying();
+                for copy_progress in copying.iter() {
+                    let pb = file_bar_map
+                        .entry((progress.folder(), copy_progress.file()))
+                        .or_insert_with(file_bar);
+                    pb.set_position(copy_progress.percentage().unwrap_or(0.) as u64);
+                    pb.set_message(format!(
+                        "{} {}/{}",
+                        copy_progress.file(),
+                        HumanBytes(copy_progress.copied()),
+                        HumanBytes(copy_progress.total())
+                    ));
+                }
+
+                // One sub bar per file that ffmpeg is currently encoding
+                let encoding = progress.encoding();
+                for file_progress in encoding.iter() {
+                    let pb = file_bar_map
+                        .entry((progress.folder(), file_progress.file()))
+                        .or_insert_with(file_bar);
+                    let sample = file_progress.sample();
+                    pb.set_position(file_progress.percentage().unwrap_or(0.) as u64);
+                    pb.set_message(format!(
+                        "{} {:.0}fps {:.2}x",
+                        file_progress.file(),
+                        sample.fps(),
+                        sample.speed()
+                    ));
+                }
+
+                // Print each conflict decision once, above the bars
+                let conflicts = progress.conflicts();
+                let reported = reported_conflicts.entry(progress.folder()).or_default();
+                for (file, decision) in conflicts.iter().skip(*reported) {
+                    let message = match decision {
+                        ConflictDecision::Skipped(path) => format!("kept existing {path:?}"),
+                        ConflictDecision::Overwritten(path) => format!("overwrote {path:?}"),
+                        ConflictDecision::Renamed { written, .. } => {
+                            format!("renamed to {written:?}")
+                        }
+                    };
+                    multi_prog
+                        .println(format!("[{}] {} : {}", progress.folder(), file, message))
+                        .unwrap();
+                }
+                *reported = conflicts.len();
+
+                let errors = progress.errors();
+                let reported = reported_errors.entry(progress.folder()).or_default();
+                for error in errors.iter().skip(*reported) {
+                    multi_prog
+                        .println(format!(
+                            "[{}] {} : failed, {}",
+                            progress.folder(),
+                            error.file(),
+                            error.cause()
+                        ))
+                        .unwrap();
+                }
+                *reported = errors.len();
+
+                file_bar_map.retain(|(folder, file), pb| {
+                    let still_running = !folder.eq(&progress.folder())
+                        || encoding.iter().any(|f| f.file().eq(file))
+                        || copying.iter().any(|f| f.file().eq(file));
+                    if !still_running {
+                        pb.finish_and_clear();
+                    }
+                    still_running
+                });
             });
         }
 
@@ -165,8 +929,21 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .unwrap();
     });
 
+    let keys = Term::stdout().is_term().then(|| {
+        KeyReader::spawn(move |key| {
+            if matches!(key, 'p' | 'P') {
+                control.toggle_pause();
+            }
+        })
+    });
+
     join_set.join_all().await;
 
+    // The terminal has to be usable again before exiting
+    if let Some(keys) = keys {
+        keys.stop();
+    }
+
     stop_tx.send(true).unwrap();
 
     drop(progress_system);
@@ -174,7 +951,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
     sleep(Duration::from_secs(5)).await;
 
     #[cfg(feature = "embedded")]
-    std::fs::remove_file(ffmpeg_instance())?;
+    {
+        std::fs::remove_file(ffmpeg_instance())?;
+        std::fs::remove_file(ffprobe_instance())?;
+    }
 
     Ok(())
 }
@@ -18,27 +18,36 @@ struct Binaries;
 #[cfg(feature = "embedded")]
 pub fn ffmpeg_instance() -> &'static PathBuf {
     static INSTANCE: OnceCell<PathBuf> = OnceCell::new();
-    INSTANCE.get_or_init(|| {
-        use std::fs::File;
-        use std::io::Write;
-
-        let ffmpeg_name = if cfg!(target_os = "windows") {
-            "ffmpeg.exe"
-        } else {
-            "ffmpeg"
-        };
-
-        let temp_dir = std::env::temp_dir();
-
-        let ffmpeg_path = temp_dir.join(ffmpeg_name);
-
-        if !ffmpeg_path.exists() {
-            {
-                let mut file = File::create(&ffmpeg_path).unwrap();
-                file.write_all(&Binaries::get(ffmpeg_name).unwrap().data)
-                    .unwrap();
-            }
+    INSTANCE.get_or_init(|| extract_binary("ffmpeg"))
+}
+
+#[cfg(feature = "embedded")]
+pub fn ffprobe_instance() -> &'static PathBuf {
+    static INSTANCE: OnceCell<PathBuf> = OnceCell::new();
+    INSTANCE.get_or_init(|| extract_binary("ffprobe"))
+}
+
+#[cfg(feature = "embedded")]
+fn extract_binary(name: &str) -> PathBuf {
+    use std::fs::File;
+    use std::io::Write;
+
+    let binary_name = if cfg!(target_os = "windows") {
+        format!("{name}.exe")
+    } else {
+        name.to_string()
+    };
+
+    let temp_dir = std::env::temp_dir();
+
+    let binary_path = temp_dir.join(&binary_name);
+
+    if !binary_path.exists() {
+        {
+            let mut file = File::create(&binary_path).unwrap();
+            file.write_all(&Binaries::get(&binary_name).unwrap().data)
+                .unwrap();
         }
-        ffmpeg_path
-    })
+    }
+    binary_path
 }
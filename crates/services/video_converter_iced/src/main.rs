@@ -7,7 +7,7 @@ use iced::{
     window,
 };
 use image::ImageFormat;
-use tracing::Level;
+use lib_core::types::Capabilities;
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::filter::EnvFilter;
 use tracing_subscriber::fmt;
@@ -23,12 +23,12 @@ mod pages;
 use crate::config::Config;
 use crate::pages::SetupPageMessage;
 use crate::{
-    assets::{Assets},
+    assets::Assets,
     pages::{Message, Page, Pages, ProgressPageMessage},
 };
 
 #[cfg(feature = "embedded")]
-use crate::assets::ffmpeg_instance;
+use crate::assets::{ffmpeg_instance, ffprobe_instance};
 
 fn main() -> iced::Result {
     // console_subscriber::init();
@@ -91,11 +91,27 @@ impl ReConverter {
         tracing::info!("Starting application");
         (
             Self::default(),
-            Task::future(async move {
-                let op = { Config::get_instance().read().await }.last_saved.clone();
+            Task::batch([
+                Task::future(async move {
+                    let op = { Config::get_instance().read().await }.last_saved.clone();
 
-                Message::SetupPage(SetupPageMessage::DefaultValue(op))
-            }),
+                    Message::SetupPage(SetupPageMessage::DefaultValue(op.map(Box::new)))
+                }),
+                Task::future(async move {
+                    #[cfg(feature = "embedded")]
+                    let ffmpeg_executable = Some(ffmpeg_instance());
+
+                    #[cfg(not(feature = "embedded"))]
+                    let ffmpeg_executable = None;
+
+                    let capabilities = Capabilities::detect(ffmpeg_executable)
+                        .await
+                        .inspect_err(|err| tracing::warn!("Failed to query ffmpeg : {}", err))
+                        .ok();
+
+                    Message::SetupPage(SetupPageMessage::CapabilitiesDetected(capabilities))
+                }),
+            ]),
         )
     }
     fn title(&self) -> String {
@@ -112,7 +128,10 @@ impl ReConverter {
                 }
                 Event::Window(window::Event::CloseRequested) => {
                     #[cfg(feature = "embedded")]
-                    let _ = std::fs::remove_file(ffmpeg_instance());
+                    {
+                        let _ = std::fs::remove_file(ffmpeg_instance());
+                        let _ = std::fs::remove_file(ffprobe_instance());
+                    }
                     return exit();
                 }
                 _ => {}
@@ -126,7 +145,7 @@ impl ReConverter {
         task
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         self.page.view()
     }
 
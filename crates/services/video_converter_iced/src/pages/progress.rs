@@ -9,7 +9,7 @@ use iced::widget::{
     text,
 };
 use iced::{Alignment, Color, Element, Font, Length, Subscription, Task, font};
-use lib_core::{Progress, ProgressSystem};
+use lib_core::{ConversionControl, Progress, ProgressSystem, types::ConversionReport};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{RwLock, watch};
@@ -20,7 +20,8 @@ pub enum ProgressPageMessage {
     ChangeToSetupPage,
     UpdateProgress(Option<Arc<[Progress]>>),
     StopConvert,
-    DoneConvert,
+    TogglePause,
+    DoneConvert(Arc<ConversionReport>),
     Tick,
     Debug,
 }
@@ -29,7 +30,9 @@ pub enum ProgressPageMessage {
 pub struct ProgressPage {
     progress: Arc<[Progress]>,
     stop_signal: watch::Sender<bool>,
+    control: ConversionControl,
     progress_system: Option<Arc<RwLock<ProgressSystem>>>,
+    report: Option<Arc<ConversionReport>>,
     debug: bool,
     converting: bool,
 }
@@ -65,18 +68,26 @@ impl Page for ProgressPage {
                     }
                 }
 
-                ProgressPageMessage::DoneConvert => {
+                ProgressPageMessage::TogglePause => {
+                    if self.converting {
+                        self.control.toggle_pause();
+                    }
+                }
+
+                ProgressPageMessage::DoneConvert(report) => {
                     if !self.progress.iter().all(|p| p.done()) {
                         return (
                             Task::future(async {
                                 sleep(Duration::from_millis(300)).await;
-                                Message::ProgressPage(ProgressPageMessage::DoneConvert)
+                                Message::ProgressPage(ProgressPageMessage::DoneConvert(report))
                             }),
                             None,
                         );
                     }
 
                     self.converting = false;
+                    self.report = Some(report);
+                    self.control.resume();
 
                     self.progress_system = None;
                 }
@@ -99,7 +110,7 @@ impl Page for ProgressPage {
         }
         (Task::none(), None)
     }
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let back_btn = button(
             row![back_arrow(), "Back"]
                 .spacing(10)
@@ -137,7 +148,7 @@ impl Page for ProgressPage {
                                 ]
                                 .align_y(Alignment::Center),
                                 row![
-                                    progress_bar(0f32..=(p.total() as f32), p.count() as f32)
+                                    progress_bar(0f32..=100., p.percentage())
                                         .width(Length::FillPortion(8)),
                                     text(format!("{:2}/{:2}", p.count(), p.total()))
                                         .font(Font {
@@ -147,7 +158,36 @@ impl Page for ProgressPage {
                                         .width(Length::Fixed(48.))
                                         .center()
                                 ]
-                                .align_y(Alignment::Center)
+                                .align_y(Alignment::Center),
+                                column(
+                                    p.copying()
+                                        .iter()
+                                        .map(|f| (f.file(), f.percentage()))
+                                        .chain(
+                                            p.encoding().iter().map(|f| (f.file(), f.percentage())),
+                                        )
+                                        .collect::<Vec<_>>()
+                                        .into_iter()
+                                        .map(|(file, percentage)| {
+                                            let percentage = percentage.unwrap_or(0.);
+                                            row![
+                                                text(file.to_string())
+                                                    .size(12)
+                                                    .width(Length::FillPortion(3)),
+                                                progress_bar(0f32..=100., percentage)
+                                                    .height(Length::Fixed(8.))
+                                                    .width(Length::FillPortion(5)),
+                                                text(format!("{:3.0}%", percentage))
+                                                    .size(12)
+                                                    .width(Length::Fixed(48.))
+                                                    .center()
+                                            ]
+                                            .spacing(10)
+                                            .align_y(Alignment::Center)
+                                        })
+                                        .map(Element::from),
+                                )
+                                .spacing(5)
                             ]
                             .spacing(10),
                         )
@@ -164,13 +204,25 @@ impl Page for ProgressPage {
             container(horizontal_space())
         });
 
+        let pause_label = if self.control.is_paused() {
+            "Resume"
+        } else {
+            "Pause"
+        };
         let cancel_btn = row![
+            button(pause_label).on_press_maybe(
+                self.converting
+                    .then_some(Message::ProgressPage(ProgressPageMessage::TogglePause))
+            ),
             button("Cancel").on_press(Message::ProgressPage(ProgressPageMessage::StopConvert))
-        ];
+        ]
+        .spacing(10);
 
         let content: Element<Message> = column![
             back_btn.height(Length::Fixed(50.)),
             pb.height(Length::Fill),
+            self.create_low_space_view(),
+            self.create_report_view(),
             cancel_btn.height(Length::Fixed(50.))
         ]
         .spacing(30)
@@ -198,12 +250,81 @@ impl Page for ProgressPage {
 }
 
 impl ProgressPage {
+    /// Why the conversion paused itself while the output drive is almost full.
+    fn create_low_space_view(&self) -> Element<'_, Message> {
+        let Some(available) = self.progress.iter().find_map(|p| p.low_space()) else {
+            return horizontal_space().height(Length::Shrink).into();
+        };
+
+        text(format!(
+            "Paused, the output drive is almost full ({:.1} GB left). Free some space to continue.",
+            available as f64 / (1024. * 1024. * 1024.)
+        ))
+        .size(14)
+        .style(text::danger)
+        .into()
+    }
+
+    /// The files that failed once the conversion is done, nothing otherwise.
+    fn create_report_view(&self) -> Element<'_, Message> {
+        let Some(report) = self.report.as_ref().filter(|r| !r.is_success()) else {
+            return horizontal_space().height(Length::Shrink).into();
+        };
+
+        let bucket_errors = report.buckets.iter().filter_map(|bucket| {
+            let error = bucket.error.as_ref()?;
+            Some(format!("[{}] {}", bucket.name, error))
+        });
+        let failures = report.failures().map(|(bucket, failure)| {
+            let file = failure
+                .source
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            format!("[{}] {} : {}", bucket, file, failure.summary())
+        });
+
+        let lines: Vec<String> = bucket_errors.chain(failures).collect();
+        let title = if report.cancelled {
+            "Cancelled".to_string()
+        } else {
+            format!("{} failed", lines.len())
+        };
+
+        container(
+            column![
+                text(title).size(16).font(Font {
+                    weight: font::Weight::Bold,
+                    ..Default::default()
+                }),
+                scrollable(
+                    column(
+                        lines
+                            .into_iter()
+                            .map(|line| text(line).size(12).style(text::danger))
+                            .map(Element::from),
+                    )
+                    .spacing(5)
+                )
+                .height(Length::Shrink)
+            ]
+            .spacing(10),
+        )
+        .style(container::bordered_box)
+        .padding(20)
+        .max_height(200)
+        .width(Length::Fill)
+        .into()
+    }
+
     pub fn new(
         stop_signal: watch::Sender<bool>,
+        control: ConversionControl,
         progress_system: Arc<RwLock<ProgressSystem>>,
     ) -> Self {
         Self {
             stop_signal,
+            control,
             progress_system: Some(progress_system),
             ..Default::default()
         }
@@ -8,8 +8,11 @@ use std::{path::PathBuf, sync::Arc};
 use tokio::sync::{RwLock, watch};
 
 use lib_core::{
-    ProgressSystem,
-    types::{ArgsType, Converter, ConverterOptions, FfmpegOptions},
+    ConversionControl, ProgressSystem,
+    types::{
+        ArgsType, AudioExport, Capabilities, ConversionPlan, Converter, ConverterOptions,
+        FfmpegOptions, FrameRateMode, OutputProfile, RateControl, SpaceCheck, ThumbnailOptions,
+    },
 };
 
 use crate::{
@@ -19,7 +22,11 @@ use crate::{
         Page, Pages,
         progress::{ProgressPage, ProgressPageMessage},
         setup::{
-            types::{AudioCodec, HwAccel, OutputExtension, ToggleType, VideoCodec},
+            types::{
+                AudioCodec, AudioFormat, Channels, CodecProfile, Gop, HwAccel, ImageFormat,
+                OutputExtension, Preset, ProxyResolution, RateControlMode, SampleRate, SheetFrames,
+                StreamSet, TargetFrameRate, ToggleType, Tune, VideoCodec,
+            },
             validation::Validation,
         },
     },
@@ -34,7 +41,7 @@ use crate::config::Config;
 pub use error::Error;
 
 #[cfg(feature = "embedded")]
-use crate::assets::ffmpeg_instance;
+use crate::assets::{ffmpeg_instance, ffprobe_instance};
 
 mod types;
 
@@ -48,11 +55,40 @@ pub struct SetupPage {
     video_bitrate: Option<u32>,
     audio_codec: Option<AudioCodec>,
     video_codec: Option<VideoCodec>,
+    /// Profile of a ProRes, CineForm or DNxHR `video_codec`
+    codec_profile: Option<CodecProfile>,
     hw_accel: HwAccel,
     output_extension: Option<OutputExtension>,
+    rate_control: Option<RateControlMode>,
+    /// Bitrate in kbit/s, or the quality for [`RateControlMode::Quality`]. No rate control while
+    /// the field is empty
+    rate_value: Option<u32>,
+    /// Highest bitrate of [`RateControlMode::Vbr`] in kbit/s
+    max_bitrate: Option<u32>,
+    preset: Option<Preset>,
+    tune: Option<Tune>,
+    /// Convert to a constant frame rate, left to ffmpeg when `None`
+    frame_rate: Option<TargetFrameRate>,
+    keyframes: Option<Gop>,
+    /// Export the audio alone, the video options are hidden
+    audio_only: Option<AudioFormat>,
+    sample_rate: Option<SampleRate>,
+    channels: Option<Channels>,
+    /// Poster frame of every converted clip
+    thumbnails: Option<ImageFormat>,
+    contact_sheet: Option<SheetFrames>,
+    /// Editing proxy of every clip, in a `Proxy` folder of its bucket
+    proxy: Option<ProxyResolution>,
+    /// Streams kept besides the video, ffmpeg's default selection when `None`
+    streams: Option<StreamSet>,
+    /// What the ffmpeg build can do, every choice is offered until it is known
+    capabilities: Option<Arc<Capabilities>>,
     debug: bool,
     validation: Validation,
     converting_page_state: Option<ProgressPage>,
+    plan: Option<Result<ConversionPlan, String>>,
+    /// Shortfall found before converting, waiting for the user to confirm
+    space_warning: Option<Box<(ConverterOptions, SpaceCheck)>>,
 }
 
 #[derive(Debug, Clone)]
@@ -63,20 +99,47 @@ pub enum FolderIden {
 
 #[derive(Debug, Clone)]
 pub(crate) enum SetupPageMessage {
-    DefaultValue(Option<ConverterOptions>),
+    DefaultValue(Option<Box<ConverterOptions>>),
     SelectFolder(FolderIden),
     InputFolder(Option<PathBuf>),
     OutputFolder(Option<PathBuf>),
     AudioCodecChange(Option<AudioCodec>),
     VideoCodecChange(Option<VideoCodec>),
+    CodecProfileChange(Option<CodecProfile>),
     AudioBitrateChange(Option<String>),
     VideoBirateChange(Option<String>),
     OutputExtensionChange(Option<OutputExtension>),
     HwAccelChange(HwAccel),
-    ChangeToProgressPage(Option<(watch::Sender<bool>, Arc<RwLock<ProgressSystem>>)>),
+    RateControlChange(Option<RateControlMode>),
+    RateValueChange(Option<String>),
+    MaxBitrateChange(Option<String>),
+    PresetChange(Option<Preset>),
+    TuneChange(Option<Tune>),
+    FrameRateChange(Option<TargetFrameRate>),
+    KeyframesChange(Option<Gop>),
+    AudioOnlyChange(Option<AudioFormat>),
+    SampleRateChange(Option<SampleRate>),
+    ChannelsChange(Option<Channels>),
+    ThumbnailsChange(Option<ImageFormat>),
+    ContactSheetChange(Option<SheetFrames>),
+    ProxyChange(Option<ProxyResolution>),
+    StreamsChange(Option<StreamSet>),
+    ChangeToProgressPage(
+        Option<(
+            watch::Sender<bool>,
+            ConversionControl,
+            Arc<RwLock<ProgressSystem>>,
+        )>,
+    ),
     Convert,
-    UpdateConfigSettings(ConverterOptions),
+    SpaceChecked(Box<ConverterOptions>, Result<SpaceCheck, String>),
+    ConvertAnyway,
+    DismissSpaceWarning,
+    Plan,
+    ClosePlan,
+    UpdateConfigSettings(Box<ConverterOptions>),
     EnableToggle(ToggleType, bool),
+    CapabilitiesDetected(Option<Arc<Capabilities>>),
     Noop,
     Debug,
 }
@@ -90,14 +153,15 @@ mod error {
         OutputDirEmpty,
         AudioBitrateParseError,
         VideoBitrateParseError,
+        RateParse,
     }
 
     impl core::fmt::Display for Error {
         fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
             match self {
-                Error::AudioBitrateParseError | Error::VideoBitrateParseError => {
-                    f.write_str("Only numbers are allowed")
-                }
+                Error::AudioBitrateParseError
+                | Error::VideoBitrateParseError
+                | Error::RateParse => f.write_str("Only numbers are allowed"),
                 _ => f.write_str("Internal Error"),
             }
         }
@@ -112,10 +176,13 @@ impl Page for SetupPage {
             match message {
                 SetupPageMessage::DefaultValue(op) => {
                     if let Some(options) = op {
-                        return (
-                            Task::none(),
-                            Some(Box::new(Pages::Setup(SetupPage::new(options)))),
-                        );
+                        let mut page = SetupPage {
+                            capabilities: self.capabilities.clone(),
+                            ..SetupPage::new(*options)
+                        };
+                        page.drop_unsupported();
+
+                        return (Task::none(), Some(Box::new(Pages::Setup(page))));
                     }
                 }
                 SetupPageMessage::InputFolder(path) => self.input_dir = path,
@@ -137,7 +204,11 @@ impl Page for SetupPage {
                     );
                 }
                 SetupPageMessage::AudioCodecChange(ac) => self.audio_codec = ac,
-                SetupPageMessage::VideoCodecChange(vc) => self.video_codec = vc,
+                SetupPageMessage::VideoCodecChange(vc) => {
+                    self.video_codec = vc;
+                    self.drop_foreign_profile();
+                }
+                SetupPageMessage::CodecProfileChange(val) => self.codec_profile = val,
                 SetupPageMessage::AudioBitrateChange(ab) => {
                     self.audio_bitrate = self.validation.validate_audio_bitrate(ab);
                 }
@@ -145,7 +216,37 @@ impl Page for SetupPage {
                     self.video_bitrate = self.validation.validate_video_bitrate(vb);
                 }
                 SetupPageMessage::OutputExtensionChange(val) => self.output_extension = val,
-                SetupPageMessage::HwAccelChange(val) => self.hw_accel = val,
+                SetupPageMessage::HwAccelChange(val) => {
+                    self.hw_accel = val;
+                    // The codec may only have worked with the previous decoding
+                    self.drop_unsupported();
+                }
+                SetupPageMessage::RateControlChange(val) => {
+                    // A quality and a bitrate don't share a scale
+                    if (val == Some(RateControlMode::Quality))
+                        != (self.rate_control == Some(RateControlMode::Quality))
+                    {
+                        self.rate_value = val.map(SetupPage::default_rate_value);
+                    }
+                    self.rate_control = val;
+                }
+                SetupPageMessage::RateValueChange(val) => {
+                    self.rate_value = self.validation.validate_rate(val);
+                }
+                SetupPageMessage::MaxBitrateChange(val) => {
+                    self.max_bitrate = self.validation.validate_rate(val);
+                }
+                SetupPageMessage::PresetChange(val) => self.preset = val,
+                SetupPageMessage::TuneChange(val) => self.tune = val,
+                SetupPageMessage::FrameRateChange(val) => self.frame_rate = val,
+                SetupPageMessage::KeyframesChange(val) => self.keyframes = val,
+                SetupPageMessage::AudioOnlyChange(val) => self.audio_only = val,
+                SetupPageMessage::SampleRateChange(val) => self.sample_rate = val,
+                SetupPageMessage::ChannelsChange(val) => self.channels = val,
+                SetupPageMessage::ThumbnailsChange(val) => self.thumbnails = val,
+                SetupPageMessage::ProxyChange(val) => self.proxy = val,
+                SetupPageMessage::StreamsChange(val) => self.streams = val,
+                SetupPageMessage::ContactSheetChange(val) => self.contact_sheet = val,
                 SetupPageMessage::Convert => {
                     let input_dir = self.validation.validate_input_dir(self.input_dir.clone());
                     let output_dir = self.validation.validate_output_dir(self.output_dir.clone());
@@ -153,39 +254,68 @@ impl Page for SetupPage {
                         return (Task::none(), None);
                     };
 
-                    let (task, stop_signal, progress_system, converter_opts) =
-                        self.convert(input_dir, output_dir);
-
+                    let options = self.converter_options(input_dir, output_dir);
                     return (
-                        Task::done(Message::SetupPage(SetupPageMessage::UpdateConfigSettings(
-                            converter_opts,
-                        )))
-                        .chain(Task::done(Message::SetupPage(
-                            SetupPageMessage::ChangeToProgressPage(Some((
-                                stop_signal,
-                                progress_system,
-                            ))),
-                        )))
-                        .chain(task),
+                        Task::perform(check_space(options.clone()), move |result| {
+                            Message::SetupPage(SetupPageMessage::SpaceChecked(
+                                Box::new(options.clone()),
+                                result,
+                            ))
+                        }),
                         None,
                     );
                 }
+                SetupPageMessage::SpaceChecked(options, result) => {
+                    match result {
+                        Ok(check) if check.shortfall().is_some() => {
+                            self.space_warning = Some(Box::new((*options, check)));
+                            return (Task::none(), None);
+                        }
+                        Ok(_) => {}
+                        // The conversion itself reports what is wrong with the input
+                        Err(err) => tracing::warn!("Space check failed : {}", err),
+                    }
+
+                    return (self.start(*options), None);
+                }
+                SetupPageMessage::ConvertAnyway => {
+                    if let Some(warning) = self.space_warning.take() {
+                        return (self.start(warning.0), None);
+                    }
+                }
+                SetupPageMessage::DismissSpaceWarning => self.space_warning = None,
+                SetupPageMessage::Plan => {
+                    let input_dir = self.validation.validate_input_dir(self.input_dir.clone());
+                    let output_dir = self.validation.validate_output_dir(self.output_dir.clone());
+                    let (Some(input_dir), Some(output_dir)) = (input_dir, output_dir) else {
+                        return (Task::none(), None);
+                    };
+
+                    let options = self.converter_options(input_dir, output_dir);
+                    self.plan = Some(Converter::plan(&options).map_err(|err| err.to_string()));
+                }
+                SetupPageMessage::ClosePlan => self.plan = None,
                 SetupPageMessage::UpdateConfigSettings(opts) => {
                     return (
                         Task::future(async move {
                             { Config::get_instance().write().await }
-                                .update_last_saved_and_save(opts)
+                                .update_last_saved_and_save(*opts)
                                 .unwrap();
                             Message::SetupPage(SetupPageMessage::Noop)
                         }),
                         None,
                     );
                 }
-                SetupPageMessage::ChangeToProgressPage(Some((stop_tx, progress_system))) => {
+                SetupPageMessage::ChangeToProgressPage(Some((
+                    stop_tx,
+                    control,
+                    progress_system,
+                ))) => {
                     return (
                         Task::none(),
                         Some(Box::new(Pages::Progress(ProgressPage::new(
                             stop_tx,
+                            control,
                             progress_system,
                         )))),
                     );
@@ -221,15 +351,44 @@ impl Page for SetupPage {
                             None
                         }
                     }
+                    ToggleType::RateControl => {
+                        self.rate_control = b.then(RateControlMode::default);
+                        self.rate_value = self.rate_control.map(SetupPage::default_rate_value);
+                        self.max_bitrate = None;
+                    }
+                    ToggleType::Preset => self.preset = b.then(Preset::default),
+                    ToggleType::Tune => self.tune = b.then(Tune::default),
+                    ToggleType::FrameRate => self.frame_rate = b.then(TargetFrameRate::default),
+                    ToggleType::Keyframes => self.keyframes = b.then(Gop::default),
+                    ToggleType::CodecProfile => {
+                        self.codec_profile = self
+                            .video_codec
+                            .filter(|_| b)
+                            .and_then(CodecProfile::default_for);
+                    }
+                    ToggleType::AudioOnly => self.audio_only = b.then(AudioFormat::default),
+                    ToggleType::SampleRate => self.sample_rate = b.then(SampleRate::default),
+                    ToggleType::Channels => self.channels = b.then(Channels::default),
+                    ToggleType::Thumbnails => {
+                        self.thumbnails = b.then(ImageFormat::default);
+                        self.contact_sheet = None;
+                    }
+                    ToggleType::ContactSheet => self.contact_sheet = b.then(SheetFrames::default),
+                    ToggleType::Proxy => self.proxy = b.then(ProxyResolution::default),
+                    ToggleType::Streams => self.streams = b.then(StreamSet::default),
                 },
                 SetupPageMessage::Debug => self.debug = !self.debug,
+                SetupPageMessage::CapabilitiesDetected(capabilities) => {
+                    self.capabilities = capabilities;
+                    self.drop_unsupported();
+                }
                 SetupPageMessage::Noop => {}
             }
         }
         (Task::none(), None)
     }
 
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let folder_selector_input =
             SetupPage::create_folder_selector(FolderIden::Input, self.input_dir.as_ref(), "Input");
 
@@ -243,7 +402,10 @@ impl Page for SetupPage {
             self.audio_codec,
             "Audio Codec",
             radios!(
-                AudioCodec::all(),
+                AudioCodec::all()
+                    .into_iter()
+                    .filter(|codec| self.supports_audio_codec(*codec))
+                    .collect::<Vec<_>>(),
                 self.audio_codec,
                 |val| Message::SetupPage(SetupPageMessage::AudioCodecChange(Some(val))),
                 row,
@@ -257,7 +419,10 @@ impl Page for SetupPage {
             self.video_codec,
             "Video Codec",
             radios!(
-                VideoCodec::all(),
+                VideoCodec::all()
+                    .into_iter()
+                    .filter(|codec| self.supports_video_codec(*codec))
+                    .collect::<Vec<_>>(),
                 self.video_codec,
                 |val| Message::SetupPage(SetupPageMessage::VideoCodecChange(Some(val))),
                 row,
@@ -267,6 +432,27 @@ impl Page for SetupPage {
             ToggleType::VC,
         );
 
+        // Only the intermediate codecs have profiles
+        let codec_profile = self
+            .video_codec
+            .map(CodecProfile::of)
+            .filter(|profiles| !profiles.is_empty())
+            .map(|profiles| {
+                SetupPage::create_toggler_element(
+                    self.codec_profile,
+                    "Codec Profile",
+                    radios!(
+                        profiles,
+                        self.codec_profile,
+                        |val| Message::SetupPage(SetupPageMessage::CodecProfileChange(Some(val))),
+                        row,
+                        10
+                    )
+                    .into(),
+                    ToggleType::CodecProfile,
+                )
+            });
+
         let audio_bitrate = SetupPage::create_toggler_element(
             self.audio_bitrate,
             "Audio Bitrate",
@@ -321,6 +507,107 @@ impl Page for SetupPage {
             ToggleType::VB,
         );
 
+        let rate_placeholder = match self.rate_control {
+            Some(RateControlMode::Quality) => "23",
+            _ => "8000k",
+        };
+        let rate_control = SetupPage::create_toggler_element(
+            self.rate_control,
+            "Rate Control",
+            column![
+                radios!(
+                    RateControlMode::all(),
+                    self.rate_control,
+                    |val| Message::SetupPage(SetupPageMessage::RateControlChange(Some(val))),
+                    row,
+                    10
+                ),
+                row![
+                    text_input(rate_placeholder, &self.rate_value.unwrap_or_empty_string())
+                        .on_input(|val| {
+                            Message::SetupPage(SetupPageMessage::RateValueChange(Some(val)))
+                        })
+                        .width(Length::FillPortion(2)),
+                ]
+                .push_maybe((self.rate_control == Some(RateControlMode::Vbr)).then(|| {
+                    text_input("max 12000k", &self.max_bitrate.unwrap_or_empty_string())
+                        .on_input(|val| {
+                            Message::SetupPage(SetupPageMessage::MaxBitrateChange(Some(val)))
+                        })
+                        .width(Length::FillPortion(2))
+                }))
+                .push(
+                    text(self.validation.rate_error().unwrap_or_empty_string())
+                        .style(text::danger)
+                        .font(Font {
+                            weight: font::Weight::Bold,
+                            ..Default::default()
+                        })
+                        .width(Length::FillPortion(2)),
+                )
+                .spacing(5)
+            ]
+            .spacing(5)
+            .into(),
+            ToggleType::RateControl,
+        );
+
+        let preset = SetupPage::create_toggler_element(
+            self.preset,
+            "Encoder Preset",
+            radios!(
+                Preset::all(),
+                self.preset,
+                |val| Message::SetupPage(SetupPageMessage::PresetChange(Some(val))),
+                row,
+                10
+            )
+            .into(),
+            ToggleType::Preset,
+        );
+
+        let tune = SetupPage::create_toggler_element(
+            self.tune,
+            "Encoder Tune",
+            radios!(
+                Tune::all(),
+                self.tune,
+                |val| Message::SetupPage(SetupPageMessage::TuneChange(Some(val))),
+                row,
+                10
+            )
+            .into(),
+            ToggleType::Tune,
+        );
+
+        let frame_rate = SetupPage::create_toggler_element(
+            self.frame_rate,
+            "Constant Frame Rate",
+            radios!(
+                TargetFrameRate::all(),
+                self.frame_rate,
+                |val| Message::SetupPage(SetupPageMessage::FrameRateChange(Some(val))),
+                row,
+                10
+            )
+            .into(),
+            ToggleType::FrameRate,
+        );
+
+        let keyframes = SetupPage::create_toggler_element(
+            self.keyframes,
+            "Keyframes",
+            radios!(
+                Gop::all(),
+                self.keyframes,
+                |val| Message::SetupPage(SetupPageMessage::KeyframesChange(Some(val))),
+                row,
+                10
+            )
+            .into(),
+            ToggleType::Keyframes,
+        );
+
         let output_extension = SetupPage::create_toggler_element(
             self.output_extension,
             "Output Extension",
@@ -348,7 +635,10 @@ impl Page for SetupPage {
             )
             .width(Length::FillPortion(2)),
             radios!(
-                HwAccel::all(),
+                HwAccel::all()
+                    .into_iter()
+                    .filter(|hw_accel| self.supports_hwaccel(*hw_accel))
+                    .collect::<Vec<_>>(),
                 Some(self.hw_accel),
                 |val| Message::SetupPage(SetupPageMessage::HwAccelChange(val)),
                 row,
@@ -376,6 +666,33 @@ impl Page for SetupPage {
         .padding(10)
         .style(button::primary);
 
+        let space_warning: Element<_> = match self.space_warning.as_deref() {
+            Some((_, check)) => row![
+                text(format!("Not enough space : {check}"))
+                    .style(text::danger)
+                    .width(Length::Fill),
+                button(text!("Cancel").center())
+                    .on_press(Message::SetupPage(SetupPageMessage::DismissSpaceWarning))
+                    .style(button::secondary),
+                button(text!("Convert anyway").center())
+                    .on_press(Message::SetupPage(SetupPageMessage::ConvertAnyway))
+                    .style(button::danger),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into(),
+            None => horizontal_space().height(Length::Shrink).into(),
+        };
+
+        let plan_btn = match self.plan {
+            Some(_) => button(text!("Back").size(20).center())
+                .on_press(Message::SetupPage(SetupPageMessage::ClosePlan)),
+            None => button(text!("Preview").size(20).center())
+                .on_press(Message::SetupPage(SetupPageMessage::Plan)),
+        }
+        .padding(10)
+        .style(button::secondary);
+
         let controls = match self.converting_page_state {
             Some(_) => {
                 row![
@@ -393,19 +710,142 @@ impl Page for SetupPage {
             None => row![horizontal_space()],
         };
 
-        let content: Element<_> = column![
-            folder_selector_input,
-            folder_selector_output,
-            audio_codec,
-            video_codec,
-            audio_bitrate,
-            video_bitrate,
-            output_extension,
-            hw_a
-        ]
+        let audio_only = SetupPage::create_toggler_element(
+            self.audio_only,
+            "Audio Only",
+            radios!(
+                AudioFormat::all(),
+                self.audio_only,
+                |val| Message::SetupPage(SetupPageMessage::AudioOnlyChange(Some(val))),
+                row,
+                10
+            )
+            .into(),
+            ToggleType::AudioOnly,
+        );
+
+        let content = column![folder_selector_input, folder_selector_output, audio_only];
+
+        let content: Element<_> = match self.audio_only {
+            Some(_) => {
+                let sample_rate = SetupPage::create_toggler_element(
+                    self.sample_rate,
+                    "Sample Rate",
+                    radios!(
+                        SampleRate::all(),
+                        self.sample_rate,
+                        |val| Message::SetupPage(SetupPageMessage::SampleRateChange(Some(val))),
+                        row,
+                        10
+                    )
+                    .into(),
+                    ToggleType::SampleRate,
+                );
+
+                let channels = SetupPage::create_toggler_element(
+                    self.channels,
+                    "Channels",
+                    radios!(
+                        Channels::all(),
+                        self.channels,
+                        |val| Message::SetupPage(SetupPageMessage::ChannelsChange(Some(val))),
+                        row,
+                        10
+                    )
+                    .into(),
+                    ToggleType::Channels,
+                );
+
+                content.push(sample_rate).push(channels).push(audio_bitrate)
+            }
+            None => {
+                let thumbnails = SetupPage::create_toggler_element(
+                    self.thumbnails,
+                    "Thumbnails",
+                    radios!(
+                        ImageFormat::all(),
+                        self.thumbnails,
+                        |val| Message::SetupPage(SetupPageMessage::ThumbnailsChange(Some(val))),
+                        row,
+                        10
+                    )
+                    .into(),
+                    ToggleType::Thumbnails,
+                );
+
+                let streams = SetupPage::create_toggler_element(
+                    self.streams,
+                    "Keep Streams",
+                    radios!(
+                        StreamSet::all(),
+                        self.streams,
+                        |val| Message::SetupPage(SetupPageMessage::StreamsChange(Some(val))),
+                        row,
+                        10
+                    )
+                    .into(),
+                    ToggleType::Streams,
+                );
+
+                let proxy = SetupPage::create_toggler_element(
+                    self.proxy,
+                    "Proxy",
+                    radios!(
+                        ProxyResolution::all(),
+                        self.proxy,
+                        |val| Message::SetupPage(SetupPageMessage::ProxyChange(Some(val))),
+                        row,
+                        10
+                    )
+                    .into(),
+                    ToggleType::Proxy,
+                );
+
+                let content = content
+                    .push(audio_codec)
+                    .push(video_codec)
+                    .push_maybe(codec_profile)
+                    .push(audio_bitrate)
+                    .push(video_bitrate)
+                    .push(rate_control)
+                    .push(preset)
+                    .push(tune)
+                    .push(frame_rate)
+                    .push(keyframes)
+                    .push(output_extension)
+                    .push(hw_a)
+                    .push(streams)
+                    .push(proxy)
+                    .push(thumbnails);
+
+                match self.thumbnails {
+                    Some(_) => content.push(SetupPage::create_toggler_element(
+                        self.contact_sheet,
+                        "Contact Sheet",
+                        radios!(
+                            SheetFrames::all(),
+                            self.contact_sheet,
+                            |val| Message::SetupPage(SetupPageMessage::ContactSheetChange(Some(
+                                val
+                            ))),
+                            row,
+                            10
+                        )
+                        .into(),
+                        ToggleType::ContactSheet,
+                    )),
+                    None => content,
+                }
+            }
+        }
         .spacing(30)
         .into();
 
+        let content = match &self.plan {
+            Some(plan) => SetupPage::create_plan_view(plan),
+            None => content,
+        };
+
         let content = if self.debug {
             content.explain(Color::BLACK)
         } else {
@@ -415,10 +855,18 @@ impl Page for SetupPage {
         column![
             controls.padding([20, 50]),
             scrollable(container(content).padding(50)).height(Length::FillPortion(15)),
-            container(row![horizontal_space(), convert_btn.padding([10, 40])])
-                .height(Length::FillPortion(2))
-                .padding([10, 20])
-                .align_y(Alignment::Center)
+            container(space_warning).padding([0, 20]),
+            container(
+                row![
+                    horizontal_space(),
+                    plan_btn.padding([10, 40]),
+                    convert_btn.padding([10, 40])
+                ]
+                .spacing(20)
+            )
+            .height(Length::FillPortion(2))
+            .padding([10, 20])
+            .align_y(Alignment::Center)
         ]
         .into()
     }
@@ -436,9 +884,31 @@ impl SetupPage {
             audio_bitrate,
             video_bitrate,
             output_extension,
+            rate_control,
+            preset,
+            tune,
+            audio_only,
+            streams,
+            frame_rate,
+            frame_rate_mode,
+            keyframes,
+            codec_profile,
             ..
         } = options.ffmpeg_options;
 
+        let (rate_control, rate_value, max_bitrate) = match rate_control {
+            Some(RateControl::Quality { value }) => {
+                (Some(RateControlMode::Quality), Some(value as u32), None)
+            }
+            Some(RateControl::Cbr { bitrate }) => (Some(RateControlMode::Cbr), Some(bitrate), None),
+            Some(RateControl::Vbr {
+                bitrate,
+                max_bitrate,
+                ..
+            }) => (Some(RateControlMode::Vbr), Some(bitrate), Some(max_bitrate)),
+            None => (None, None, None),
+        };
+
         let hw_accel = match hwaccel {
             Some(t) => t.into(),
             None => HwAccel::None,
@@ -456,8 +926,40 @@ impl SetupPage {
             video_bitrate: video_bitrate.to_option(),
             audio_codec: audio_codec.to_option().map(AudioCodec::from),
             video_codec: video_codec.to_option().map(VideoCodec::from),
+            codec_profile: codec_profile.and_then(CodecProfile::from_profile),
             hw_accel,
             output_extension,
+            rate_control,
+            rate_value,
+            max_bitrate,
+            preset: preset.map(Preset::from),
+            tune: tune.map(Tune::from),
+            frame_rate: match frame_rate_mode {
+                FrameRateMode::Constant => TargetFrameRate::from_frame_rate(frame_rate),
+                _ => None,
+            },
+            keyframes: keyframes.and_then(Gop::from_keyframes),
+            audio_only: audio_only.map(|export| export.format.into()),
+            streams: streams.as_ref().map(StreamSet::from_mapping),
+            sample_rate: audio_only
+                .and_then(|export| export.sample_rate)
+                .and_then(SampleRate::from_hz),
+            channels: audio_only
+                .and_then(|export| export.channels)
+                .and_then(Channels::from_count),
+            thumbnails: options
+                .thumbnails
+                .map(|thumbnails| thumbnails.format.into()),
+            contact_sheet: options
+                .thumbnails
+                .and_then(|thumbnails| thumbnails.contact_sheet)
+                .and_then(SheetFrames::from_count),
+            proxy: options
+                .profiles
+                .iter()
+                .find(|profile| profile.name == "proxy")
+                .and_then(|profile| profile.ffmpeg_options.resolution.to_option())
+                .and_then(ProxyResolution::from_resolution),
             ..Default::default()
         }
     }
@@ -571,28 +1073,173 @@ impl SetupPage {
             .into()
     }
 
-    fn convert(
-        &self,
-        input_dir: PathBuf,
-        output_dir: PathBuf,
-    ) -> (
-        Task<Message>,
-        watch::Sender<bool>,
-        Arc<RwLock<ProgressSystem>>,
-        ConverterOptions,
-    ) {
+    fn create_plan_view(plan: &Result<ConversionPlan, String>) -> Element<'_, Message> {
+        let plan = match plan {
+            Ok(plan) => plan,
+            Err(err) => return text(err).style(text::danger).into(),
+        };
+
+        let bold = Font {
+            weight: font::Weight::Bold,
+            ..Default::default()
+        };
+
+        let mut content = column![].spacing(30);
+
+        for bucket in plan.buckets.iter() {
+            let mut files = column![
+                text(&bucket.name).size(25).font(bold).style(text::primary),
+                text(bucket.output_dir.to_string_lossy()).size(14),
+            ]
+            .spacing(8);
+
+            for copy in bucket.copies.iter() {
+                files = files.push(SetupPage::create_plan_row(
+                    "Copy",
+                    copy.target.to_string_lossy().to_string(),
+                    copy.exists,
+                ));
+            }
+
+            for encode in bucket.encodes.iter() {
+                files = files.push(SetupPage::create_plan_row(
+                    "Encode",
+                    encode.output.to_string_lossy().to_string(),
+                    encode.exists,
+                ));
+                files = files.push(text!("ffmpeg {}", encode.args.join(" ")).size(12));
+            }
+
+            content = content.push(
+                container(files)
+                    .padding(15)
+                    .width(Length::Fill)
+                    .style(container::rounded_box),
+            );
+        }
+
+        content.into()
+    }
+
+    fn create_plan_row<'a>(action: &'a str, target: String, exists: bool) -> Element<'a, Message> {
+        let exists = if exists {
+            text("exists").style(text::danger)
+        } else {
+            text("")
+        };
+
+        row![
+            text(action)
+                .font(Font {
+                    weight: font::Weight::Bold,
+                    ..Default::default()
+                })
+                .width(Length::Fixed(80.)),
+            text(target).width(Length::Fill),
+            exists
+        ]
+        .spacing(10)
+        .into()
+    }
+
+    fn default_rate_value(mode: RateControlMode) -> u32 {
+        match mode {
+            RateControlMode::Quality => 23,
+            RateControlMode::Cbr | RateControlMode::Vbr => 8000,
+        }
+    }
+
+    fn rate_control(&self) -> Option<RateControl> {
+        let value = self.rate_value?;
+
+        Some(match self.rate_control? {
+            RateControlMode::Quality => RateControl::Quality {
+                value: value.min(u8::MAX as u32) as u8,
+            },
+            RateControlMode::Cbr => RateControl::Cbr { bitrate: value },
+            RateControlMode::Vbr => {
+                let max_bitrate = self.max_bitrate.unwrap_or(value).max(value);
+                RateControl::Vbr {
+                    bitrate: value,
+                    max_bitrate,
+                    buffer_size: max_bitrate * 2,
+                }
+            }
+        })
+    }
+
+    /// The encoder behind `codec`, NVENC when decoding with CUDA.
+    fn encoder(hw_accel: HwAccel, codec: VideoCodec) -> ArgsType<lib_core::types::VideoCodec> {
+        match (hw_accel, codec) {
+            (HwAccel::Cuda, VideoCodec::H264) => {
+                ArgsType::Custom(lib_core::types::VideoCodec::H264NVENC)
+            }
+            (HwAccel::Cuda, VideoCodec::H265) => {
+                ArgsType::Custom(lib_core::types::VideoCodec::H265NVENC)
+            }
+            (_, val) => val.into(),
+        }
+    }
+
+    fn supports_video_codec(&self, codec: VideoCodec) -> bool {
+        match (&self.capabilities, SetupPage::encoder(self.hw_accel, codec)) {
+            (Some(capabilities), ArgsType::Custom(codec)) => {
+                capabilities.supports_video_codec(codec)
+            }
+            _ => true,
+        }
+    }
+
+    fn supports_audio_codec(&self, codec: AudioCodec) -> bool {
+        match (&self.capabilities, codec.into()) {
+            (Some(capabilities), ArgsType::Custom(codec)) => {
+                capabilities.supports_audio_codec(codec)
+            }
+            _ => true,
+        }
+    }
+
+    fn supports_hwaccel(&self, hw_accel: HwAccel) -> bool {
+        match (&self.capabilities, hw_accel.into()) {
+            (Some(capabilities), Some(hw_accel)) => capabilities.supports_hwaccel(hw_accel),
+            _ => true,
+        }
+    }
+
+    /// Unselect what the ffmpeg build can't do, e.g. a saved NVENC choice on a machine without
+    /// an NVIDIA card.
+    fn drop_unsupported(&mut self) {
+        if !self.supports_hwaccel(self.hw_accel) {
+            self.hw_accel = HwAccel::None;
+        }
+        if self
+            .video_codec
+            .is_some_and(|codec| !self.supports_video_codec(codec))
+        {
+            self.video_codec = None;
+        }
+        self.drop_foreign_profile();
+        if self
+            .audio_codec
+            .is_some_and(|codec| !self.supports_audio_codec(codec))
+        {
+            self.audio_codec = None;
+        }
+    }
+
+    /// Forgets a profile of another codec than the selected one.
+    fn drop_foreign_profile(&mut self) {
+        let codec = self.video_codec;
+        self.codec_profile = self
+            .codec_profile
+            .filter(|profile| Some(profile.codec()) == codec);
+    }
+
+    fn converter_options(&self, input_dir: PathBuf, output_dir: PathBuf) -> ConverterOptions {
         let ac = self.audio_codec.unwrap_or_value(ArgsType::MatchSource);
 
         let vc = match self.video_codec {
-            Some(vc) => match (self.hw_accel, vc) {
-                (HwAccel::Cuda, VideoCodec::H264) => {
-                    ArgsType::Custom(lib_core::types::VideoCodec::H264NVENC)
-                }
-                (HwAccel::Cuda, VideoCodec::H265) => {
-                    ArgsType::Custom(lib_core::types::VideoCodec::H265NVENC)
-                }
-                (_, val) => val.into(),
-            },
+            Some(vc) => SetupPage::encoder(self.hw_accel, vc),
             None => ArgsType::MatchSource,
         };
 
@@ -604,49 +1251,155 @@ impl SetupPage {
             .output_extension
             .unwrap_or_value(lib_core::types::OutputExtension::Mkv);
 
-        let ffmpeg_option = FfmpegOptions::new(
-            // NOTE : Skipping resolution (not yet implemented)
-            ArgsType::MatchSource,
-            self.hw_accel.into(),
-            ac,
-            vc,
-            ab,
-            vb,
-            // NOTE : Skipping picture format as it has not been implemented
-            ArgsType::MatchSource,
-            oex,
-        );
+        let ffmpeg_option = FfmpegOptions {
+            rate_control: self.rate_control(),
+            preset: self.preset.map(Into::into),
+            tune: self.tune.map(Into::into),
+            audio_only: self.audio_only.map(|format| AudioExport {
+                sample_rate: self.sample_rate.map(|rate| rate.hz()),
+                channels: self.channels.map(|channels| channels.count()),
+                ..AudioExport::new(format.into())
+            }),
+            streams: self.streams.map(Into::into),
+            frame_rate: self.frame_rate.and_then(|rate| rate.frame_rate()),
+            frame_rate_mode: match self.frame_rate {
+                Some(_) => FrameRateMode::Constant,
+                None => FrameRateMode::Auto,
+            },
+            keyframes: self.keyframes.map(Into::into),
+            codec_profile: self.codec_profile.map(Into::into),
+            // Only MOV and MP4 have an index to move
+            faststart: true,
+            ..FfmpegOptions::new(
+                // NOTE : Skipping resolution (not yet implemented)
+                ArgsType::MatchSource,
+                self.hw_accel.into(),
+                ac,
+                vc,
+                ab,
+                vb,
+                // NOTE : Skipping picture format as it has not been implemented
+                ArgsType::MatchSource,
+                oex,
+            )
+        };
+
+        // Decoded like the main output so both are written by the same run
+        let proxy = self.proxy.map(|resolution| {
+            let vc = match self.hw_accel {
+                HwAccel::Cuda => lib_core::types::VideoCodec::H264NVENC,
+                _ => lib_core::types::VideoCodec::H264,
+            };
+            let options = FfmpegOptions {
+                resolution: ArgsType::Custom(resolution.into()),
+                audio_codec: ArgsType::Custom(lib_core::types::AudioCodec::Aac),
+                video_codec: ArgsType::Custom(vc),
+                audio_bitrate: ArgsType::MatchSource,
+                video_bitrate: ArgsType::Custom(5000),
+                output_extension: lib_core::types::OutputExtension::Mov,
+                rate_control: None,
+                ..ffmpeg_option.clone()
+            };
+
+            OutputProfile::new("proxy", options)
+                .with_subfolder("Proxy")
+                .with_suffix("_proxy")
+        });
+
+        ConverterOptions {
+            profiles: proxy.into_iter().collect(),
+            thumbnails: self.thumbnails.map(|format| ThumbnailOptions {
+                format: format.into(),
+                contact_sheet: self.contact_sheet.map(|frames| frames.count()),
+                ..ThumbnailOptions::default()
+            }),
+            ..ConverterOptions::new(input_dir, output_dir, true, ffmpeg_option)
+        }
+    }
 
+    /// Switch to the progress page and run the conversion.
+    fn start(&self, options: ConverterOptions) -> Task<Message> {
+        let (task, stop_signal, control, progress_system, converter_opts) = self.convert(options);
+
+        Task::done(Message::SetupPage(SetupPageMessage::UpdateConfigSettings(
+            Box::new(converter_opts),
+        )))
+        .chain(Task::done(Message::SetupPage(
+            SetupPageMessage::ChangeToProgressPage(Some((stop_signal, control, progress_system))),
+        )))
+        .chain(task)
+    }
+
+    fn convert(
+        &self,
+        options: ConverterOptions,
+    ) -> (
+        Task<Message>,
+        watch::Sender<bool>,
+        ConversionControl,
+        Arc<RwLock<ProgressSystem>>,
+        ConverterOptions,
+    ) {
         let (stop_tx, stop_rx) = watch::channel(false);
 
         let progress_system = Arc::new(RwLock::new(ProgressSystem::new(200)));
 
+        // The space was checked before, possibly confirmed by the user
         let mut converter =
-            Converter::new_with_progress_tracker(stop_rx.clone(), progress_system.clone());
-
-        let options = ConverterOptions::new(input_dir, output_dir, true, ffmpeg_option);
+            Converter::new_with_progress_tracker(stop_rx.clone(), progress_system.clone())
+                .ignore_space_check(true);
+        let control = converter.control();
 
         let converter_opts = options.clone();
 
         let task = Task::future(async move {
-            converter.prepare_task(Arc::new(options)).await.unwrap();
-
             #[cfg(feature = "embedded")]
             converter
+                .prepare_task(Arc::new(options), Some(ffprobe_instance()))
+                .await
+                .unwrap();
+
+            #[cfg(not(feature = "embedded"))]
+            converter
+                .prepare_task(Arc::new(options), None)
+                .await
+                .unwrap();
+
+            #[cfg(feature = "embedded")]
+            let report = converter
                 .start_conversion(Some(ffmpeg_instance()))
                 .await
                 .unwrap();
 
             #[cfg(not(feature = "embedded"))]
-            converter.start_conversion(None).await.unwrap();
+            let report = converter.start_conversion(None).await.unwrap();
 
-            Message::ProgressPage(ProgressPageMessage::DoneConvert)
+            Message::ProgressPage(ProgressPageMessage::DoneConvert(Arc::new(report)))
         });
 
-        (task, stop_tx, progress_system, converter_opts)
+        (task, stop_tx, control, progress_system, converter_opts)
     }
 }
 
+/// Probe the input and compare the expected output size with the free space of the output drive.
+async fn check_space(options: ConverterOptions) -> Result<SpaceCheck, String> {
+    let (_stop_tx, stop_rx) = watch::channel(false);
+    let mut converter = Converter::new(stop_rx);
+
+    #[cfg(feature = "embedded")]
+    let ffprobe_executable = Some(ffprobe_instance());
+
+    #[cfg(not(feature = "embedded"))]
+    let ffprobe_executable = None;
+
+    converter
+        .prepare_task(Arc::new(options), ffprobe_executable)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    converter.check_space().map_err(|err| err.to_string())
+}
+
 async fn select_folder() -> Option<PathBuf> {
     Some(
         rfd::AsyncFileDialog::new()
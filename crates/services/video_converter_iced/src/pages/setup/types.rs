@@ -15,6 +15,7 @@ pub enum VideoCodec {
     H265,
     CineForm,
     Prores,
+    DnxHr,
 }
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
 pub enum HwAccel {
@@ -87,14 +88,15 @@ impl From<OutputExtension> for String {
     }
 }
 
-impl EnumToArray<4> for VideoCodec {
+impl EnumToArray<5> for VideoCodec {
     type T = VideoCodec;
-    fn all() -> [Self::T; 4] {
+    fn all() -> [Self::T; 5] {
         [
             VideoCodec::H264,
             VideoCodec::H265,
             VideoCodec::CineForm,
             VideoCodec::Prores,
+            VideoCodec::DnxHr,
         ]
     }
 }
@@ -106,6 +108,7 @@ impl From<VideoCodec> for String {
             VideoCodec::CineForm => "cineform",
             VideoCodec::H265 => "h265",
             VideoCodec::Prores => "prores",
+            VideoCodec::DnxHr => "dnxhr",
         };
 
         val.to_string()
@@ -131,6 +134,635 @@ impl From<AudioCodec> for String {
     }
 }
 
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum RateControlMode {
+    #[default]
+    Quality,
+    Cbr,
+    Vbr,
+}
+
+impl EnumToArray<3> for RateControlMode {
+    type T = RateControlMode;
+    fn all() -> [Self::T; 3] {
+        [
+            RateControlMode::Quality,
+            RateControlMode::Cbr,
+            RateControlMode::Vbr,
+        ]
+    }
+}
+
+impl From<RateControlMode> for String {
+    fn from(value: RateControlMode) -> Self {
+        let val = match value {
+            RateControlMode::Quality => "quality",
+            RateControlMode::Cbr => "cbr",
+            RateControlMode::Vbr => "vbr",
+        };
+
+        val.to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Preset {
+    Fastest,
+    Fast,
+    #[default]
+    Medium,
+    Slow,
+    Slowest,
+}
+
+impl EnumToArray<5> for Preset {
+    type T = Preset;
+    fn all() -> [Self::T; 5] {
+        [
+            Preset::Fastest,
+            Preset::Fast,
+            Preset::Medium,
+            Preset::Slow,
+            Preset::Slowest,
+        ]
+    }
+}
+
+impl From<Preset> for String {
+    fn from(value: Preset) -> Self {
+        let val = match value {
+            Preset::Fastest => "fastest",
+            Preset::Fast => "fast",
+            Preset::Medium => "medium",
+            Preset::Slow => "slow",
+            Preset::Slowest => "slowest",
+        };
+
+        val.to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Tune {
+    #[default]
+    Film,
+    Animation,
+    Grain,
+    StillImage,
+    FastDecode,
+    ZeroLatency,
+}
+
+impl EnumToArray<6> for Tune {
+    type T = Tune;
+    fn all() -> [Self::T; 6] {
+        [
+            Tune::Film,
+            Tune::Animation,
+            Tune::Grain,
+            Tune::StillImage,
+            Tune::FastDecode,
+            Tune::ZeroLatency,
+        ]
+    }
+}
+
+impl From<Tune> for String {
+    fn from(value: Tune) -> Self {
+        let val = match value {
+            Tune::Film => "film",
+            Tune::Animation => "animation",
+            Tune::Grain => "grain",
+            Tune::StillImage => "still image",
+            Tune::FastDecode => "fast decode",
+            Tune::ZeroLatency => "zero latency",
+        };
+
+        val.to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum AudioFormat {
+    Mp3,
+    #[default]
+    M4a,
+    Opus,
+    Flac,
+    Wav,
+}
+
+impl EnumToArray<5> for AudioFormat {
+    type T = AudioFormat;
+    fn all() -> [Self::T; 5] {
+        [
+            AudioFormat::Mp3,
+            AudioFormat::M4a,
+            AudioFormat::Opus,
+            AudioFormat::Flac,
+            AudioFormat::Wav,
+        ]
+    }
+}
+
+impl From<AudioFormat> for String {
+    fn from(value: AudioFormat) -> Self {
+        let val = match value {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::M4a => "m4a",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+        };
+
+        val.to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum SampleRate {
+    Hz44100,
+    #[default]
+    Hz48000,
+}
+
+impl SampleRate {
+    pub fn hz(&self) -> u32 {
+        match self {
+            SampleRate::Hz44100 => 44100,
+            SampleRate::Hz48000 => 48000,
+        }
+    }
+
+    pub fn from_hz(hz: u32) -> Option<Self> {
+        SampleRate::all().into_iter().find(|rate| rate.hz() == hz)
+    }
+}
+
+impl EnumToArray<2> for SampleRate {
+    type T = SampleRate;
+    fn all() -> [Self::T; 2] {
+        [SampleRate::Hz44100, SampleRate::Hz48000]
+    }
+}
+
+impl From<SampleRate> for String {
+    fn from(value: SampleRate) -> Self {
+        let val = match value {
+            SampleRate::Hz44100 => "44.1 kHz",
+            SampleRate::Hz48000 => "48 kHz",
+        };
+
+        val.to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Channels {
+    Mono,
+    #[default]
+    Stereo,
+}
+
+impl Channels {
+    pub fn count(&self) -> u8 {
+        match self {
+            Channels::Mono => 1,
+            Channels::Stereo => 2,
+        }
+    }
+
+    pub fn from_count(count: u8) -> Option<Self> {
+        Channels::all()
+            .into_iter()
+            .find(|channels| channels.count() == count)
+    }
+}
+
+impl EnumToArray<2> for Channels {
+    type T = Channels;
+    fn all() -> [Self::T; 2] {
+        [Channels::Mono, Channels::Stereo]
+    }
+}
+
+impl From<Channels> for String {
+    fn from(value: Channels) -> Self {
+        let val = match value {
+            Channels::Mono => "mono",
+            Channels::Stereo => "stereo",
+        };
+
+        val.to_string()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ImageFormat {
+    #[default]
+    Jpeg,
+    Png,
+}
+
+impl EnumToArray<2> for ImageFormat {
+    type T = ImageFormat;
+    fn all() -> [Self::T; 2] {
+        [ImageFormat::Jpeg, ImageFormat::Png]
+    }
+}
+
+impl From<ImageFormat> for String {
+    fn from(value: ImageFormat) -> Self {
+        let val = match value {
+            ImageFormat::Jpeg => "jpeg",
+            ImageFormat::Png => "png",
+        };
+
+        val.to_string()
+    }
+}
+
+/// Frames of a contact sheet.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum SheetFrames {
+    #[default]
+    Nine,
+    Sixteen,
+    TwentyFive,
+}
+
+impl SheetFrames {
+    pub fn count(&self) -> u32 {
+        match self {
+            SheetFrames::Nine => 9,
+            SheetFrames::Sixteen => 16,
+            SheetFrames::TwentyFive => 25,
+        }
+    }
+
+    pub fn from_count(count: u32) -> Option<Self> {
+        SheetFrames::all()
+            .into_iter()
+            .find(|frames| frames.count() == count)
+    }
+}
+
+impl EnumToArray<3> for SheetFrames {
+    type T = SheetFrames;
+    fn all() -> [Self::T; 3] {
+        [
+            SheetFrames::Nine,
+            SheetFrames::Sixteen,
+            SheetFrames::TwentyFive,
+        ]
+    }
+}
+
+impl From<SheetFrames> for String {
+    fn from(value: SheetFrames) -> Self {
+        let val = match value {
+            SheetFrames::Nine => "3x3",
+            SheetFrames::Sixteen => "4x4",
+            SheetFrames::TwentyFive => "5x5",
+        };
+
+        val.to_string()
+    }
+}
+
+/// Resolution of the editing proxy written next to every clip.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ProxyResolution {
+    #[default]
+    R720P,
+    R1080P,
+}
+
+impl ProxyResolution {
+    pub fn from_resolution(resolution: lib_core::types::Resolution) -> Option<Self> {
+        match resolution {
+            lib_core::types::Resolution::R720P => Some(ProxyResolution::R720P),
+            lib_core::types::Resolution::R1080P => Some(ProxyResolution::R1080P),
+            _ => None,
+        }
+    }
+}
+
+impl EnumToArray<2> for ProxyResolution {
+    type T = ProxyResolution;
+    fn all() -> [Self::T; 2] {
+        [ProxyResolution::R720P, ProxyResolution::R1080P]
+    }
+}
+
+impl From<ProxyResolution> for lib_core::types::Resolution {
+    fn from(value: ProxyResolution) -> Self {
+        match value {
+            ProxyResolution::R720P => lib_core::types::Resolution::R720P,
+            ProxyResolution::R1080P => lib_core::types::Resolution::R1080P,
+        }
+    }
+}
+
+impl From<ProxyResolution> for String {
+    fn from(value: ProxyResolution) -> Self {
+        let val = match value {
+            ProxyResolution::R720P => "720p",
+            ProxyResolution::R1080P => "1080p",
+        };
+
+        val.to_string()
+    }
+}
+
+/// Streams of the source kept besides the video.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum StreamSet {
+    /// Every audio track
+    #[default]
+    AudioTracks,
+    /// Every audio track, the subtitles, the timecode track, the chapters and the tags
+    Everything,
+}
+
+impl StreamSet {
+    pub fn from_mapping(mapping: &lib_core::types::StreamMapping) -> Self {
+        if mapping.subtitles || mapping.data || mapping.chapters || mapping.metadata {
+            StreamSet::Everything
+        } else {
+            StreamSet::AudioTracks
+        }
+    }
+}
+
+impl EnumToArray<2> for StreamSet {
+    type T = StreamSet;
+    fn all() -> [Self::T; 2] {
+        [StreamSet::AudioTracks, StreamSet::Everything]
+    }
+}
+
+impl From<StreamSet> for lib_core::types::StreamMapping {
+    fn from(value: StreamSet) -> Self {
+        let everything = value == StreamSet::Everything;
+        lib_core::types::StreamMapping {
+            subtitles: everything,
+            data: everything,
+            chapters: everything,
+            metadata: everything,
+            ..lib_core::types::StreamMapping::all()
+        }
+    }
+}
+
+impl From<StreamSet> for String {
+    fn from(value: StreamSet) -> Self {
+        let val = match value {
+            StreamSet::AudioTracks => "All audio tracks",
+            StreamSet::Everything => "Everything",
+        };
+
+        val.to_string()
+    }
+}
+
+/// Constant frame rate of the outputs, editors drift out of sync on variable ones.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum TargetFrameRate {
+    /// The rate of the source, only made constant
+    #[default]
+    Source,
+    R23976,
+    R25,
+    R2997,
+    R30,
+    R50,
+    R60,
+}
+
+impl TargetFrameRate {
+    pub fn frame_rate(&self) -> Option<lib_core::types::FrameRate> {
+        use lib_core::types::FrameRate;
+
+        match self {
+            TargetFrameRate::Source => None,
+            TargetFrameRate::R23976 => Some(FrameRate::ntsc(24)),
+            TargetFrameRate::R25 => Some(FrameRate::fps(25)),
+            TargetFrameRate::R2997 => Some(FrameRate::ntsc(30)),
+            TargetFrameRate::R30 => Some(FrameRate::fps(30)),
+            TargetFrameRate::R50 => Some(FrameRate::fps(50)),
+            TargetFrameRate::R60 => Some(FrameRate::fps(60)),
+        }
+    }
+
+    pub fn from_frame_rate(frame_rate: Option<lib_core::types::FrameRate>) -> Option<Self> {
+        TargetFrameRate::all()
+            .into_iter()
+            .find(|target| target.frame_rate() == frame_rate)
+    }
+}
+
+impl EnumToArray<7> for TargetFrameRate {
+    type T = TargetFrameRate;
+    fn all() -> [Self::T; 7] {
+        [
+            TargetFrameRate::Source,
+            TargetFrameRate::R23976,
+            TargetFrameRate::R25,
+            TargetFrameRate::R2997,
+            TargetFrameRate::R30,
+            TargetFrameRate::R50,
+            TargetFrameRate::R60,
+        ]
+    }
+}
+
+impl From<TargetFrameRate> for String {
+    fn from(value: TargetFrameRate) -> Self {
+        let val = match value {
+            TargetFrameRate::Source => "Source",
+            TargetFrameRate::R23976 => "23.976",
+            TargetFrameRate::R25 => "25",
+            TargetFrameRate::R2997 => "29.97",
+            TargetFrameRate::R30 => "30",
+            TargetFrameRate::R50 => "50",
+            TargetFrameRate::R60 => "60",
+        };
+
+        val.to_string()
+    }
+}
+
+/// Keyframe spacing, editors seek faster with short GOPs.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Gop {
+    #[default]
+    Frames12,
+    Frames30,
+    AllIntra,
+}
+
+impl Gop {
+    pub fn from_keyframes(keyframes: lib_core::types::Keyframes) -> Option<Self> {
+        Gop::all()
+            .into_iter()
+            .find(|gop| lib_core::types::Keyframes::from(*gop) == keyframes)
+    }
+}
+
+impl EnumToArray<3> for Gop {
+    type T = Gop;
+    fn all() -> [Self::T; 3] {
+        [Gop::Frames12, Gop::Frames30, Gop::AllIntra]
+    }
+}
+
+impl From<Gop> for lib_core::types::Keyframes {
+    fn from(value: Gop) -> Self {
+        match value {
+            Gop::Frames12 => lib_core::types::Keyframes::Interval(12),
+            Gop::Frames30 => lib_core::types::Keyframes::Interval(30),
+            Gop::AllIntra => lib_core::types::Keyframes::AllIntra,
+        }
+    }
+}
+
+impl From<Gop> for String {
+    fn from(value: Gop) -> Self {
+        let val = match value {
+            Gop::Frames12 => "Every 12 frames",
+            Gop::Frames30 => "Every 30 frames",
+            Gop::AllIntra => "All intra",
+        };
+
+        val.to_string()
+    }
+}
+
+/// Profiles of the intermediate codecs, each one belongs to a single [`VideoCodec`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CodecProfile {
+    ProresProxy,
+    ProresLt,
+    Prores422,
+    ProresHq,
+    Prores4444,
+    CineFormLow,
+    CineFormMedium,
+    CineFormHigh,
+    CineFormFilm1,
+    CineFormFilm2,
+    CineFormFilm3,
+    DnxHrLb,
+    DnxHrSq,
+    DnxHrHq,
+    DnxHrHqx,
+    DnxHr444,
+}
+
+impl CodecProfile {
+    /// Profiles of `codec`, none for the delivery codecs.
+    pub fn of(codec: VideoCodec) -> Vec<Self> {
+        CodecProfile::all()
+            .into_iter()
+            .filter(|profile| profile.codec() == codec)
+            .collect()
+    }
+
+    /// What the toggle starts on, the HQ flavour of each codec.
+    pub fn default_for(codec: VideoCodec) -> Option<Self> {
+        match codec {
+            VideoCodec::Prores => Some(CodecProfile::ProresHq),
+            VideoCodec::CineForm => Some(CodecProfile::CineFormHigh),
+            VideoCodec::DnxHr => Some(CodecProfile::DnxHrHq),
+            VideoCodec::H264 | VideoCodec::H265 => None,
+        }
+    }
+
+    pub fn from_profile(profile: lib_core::types::CodecProfile) -> Option<Self> {
+        CodecProfile::all()
+            .into_iter()
+            .find(|p| lib_core::types::CodecProfile::from(*p) == profile)
+    }
+
+    pub fn codec(&self) -> VideoCodec {
+        lib_core::types::CodecProfile::from(*self).codec().into()
+    }
+}
+
+impl EnumToArray<16> for CodecProfile {
+    type T = CodecProfile;
+    fn all() -> [Self::T; 16] {
+        [
+            CodecProfile::ProresProxy,
+            CodecProfile::ProresLt,
+            CodecProfile::Prores422,
+            CodecProfile::ProresHq,
+            CodecProfile::Prores4444,
+            CodecProfile::CineFormLow,
+            CodecProfile::CineFormMedium,
+            CodecProfile::CineFormHigh,
+            CodecProfile::CineFormFilm1,
+            CodecProfile::CineFormFilm2,
+            CodecProfile::CineFormFilm3,
+            CodecProfile::DnxHrLb,
+            CodecProfile::DnxHrSq,
+            CodecProfile::DnxHrHq,
+            CodecProfile::DnxHrHqx,
+            CodecProfile::DnxHr444,
+        ]
+    }
+}
+
+impl From<CodecProfile> for lib_core::types::CodecProfile {
+    fn from(value: CodecProfile) -> Self {
+        use lib_core::types::{CineFormQuality, CodecProfile as Core, DnxHrProfile, ProresProfile};
+        match value {
+            CodecProfile::ProresProxy => Core::Prores(ProresProfile::Proxy),
+            CodecProfile::ProresLt => Core::Prores(ProresProfile::Lt),
+            CodecProfile::Prores422 => Core::Prores(ProresProfile::Standard),
+            CodecProfile::ProresHq => Core::Prores(ProresProfile::Hq),
+            CodecProfile::Prores4444 => Core::Prores(ProresProfile::P4444),
+            CodecProfile::CineFormLow => Core::CineForm(CineFormQuality::Low),
+            CodecProfile::CineFormMedium => Core::CineForm(CineFormQuality::Medium),
+            CodecProfile::CineFormHigh => Core::CineForm(CineFormQuality::High),
+            CodecProfile::CineFormFilm1 => Core::CineForm(CineFormQuality::Film1),
+            CodecProfile::CineFormFilm2 => Core::CineForm(CineFormQuality::Film2),
+            CodecProfile::CineFormFilm3 => Core::CineForm(CineFormQuality::Film3),
+            CodecProfile::DnxHrLb => Core::DnxHr(DnxHrProfile::Lb),
+            CodecProfile::DnxHrSq => Core::DnxHr(DnxHrProfile::Sq),
+            CodecProfile::DnxHrHq => Core::DnxHr(DnxHrProfile::Hq),
+            CodecProfile::DnxHrHqx => Core::DnxHr(DnxHrProfile::Hqx),
+            CodecProfile::DnxHr444 => Core::DnxHr(DnxHrProfile::P444),
+        }
+    }
+}
+
+impl From<CodecProfile> for String {
+    fn from(value: CodecProfile) -> Self {
+        let val = match value {
+            CodecProfile::ProresProxy => "Proxy",
+            CodecProfile::ProresLt => "LT",
+            CodecProfile::Prores422 => "422",
+            CodecProfile::ProresHq => "HQ",
+            CodecProfile::Prores4444 => "4444",
+            CodecProfile::CineFormLow => "Low",
+            CodecProfile::CineFormMedium => "Medium",
+            CodecProfile::CineFormHigh => "High",
+            CodecProfile::CineFormFilm1 => "Film 1",
+            CodecProfile::CineFormFilm2 => "Film 2",
+            CodecProfile::CineFormFilm3 => "Film 3",
+            CodecProfile::DnxHrLb => "LB",
+            CodecProfile::DnxHrSq => "SQ",
+            CodecProfile::DnxHrHq => "HQ",
+            CodecProfile::DnxHrHqx => "HQX",
+            CodecProfile::DnxHr444 => "444",
+        };
+
+        val.to_string()
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum ToggleType {
     AC,
@@ -138,6 +770,19 @@ pub enum ToggleType {
     AB,
     VB,
     OEX,
+    RateControl,
+    Preset,
+    Tune,
+    AudioOnly,
+    SampleRate,
+    Channels,
+    Thumbnails,
+    ContactSheet,
+    Proxy,
+    Streams,
+    FrameRate,
+    Keyframes,
+    CodecProfile,
 }
 
 impl From<HwAccel> for Option<lib_core::types::HwAccel> {
@@ -169,6 +814,7 @@ impl From<VideoCodec> for ArgsType<lib_core::types::VideoCodec> {
             VideoCodec::H265 => ArgsType::Custom(lib_core::types::VideoCodec::H265),
             VideoCodec::CineForm => ArgsType::Custom(lib_core::types::VideoCodec::CineForm),
             VideoCodec::Prores => ArgsType::Custom(lib_core::types::VideoCodec::Prores),
+            VideoCodec::DnxHr => ArgsType::Custom(lib_core::types::VideoCodec::DnxHr),
         }
     }
 }
@@ -204,6 +850,7 @@ impl From<lib_core::types::VideoCodec> for VideoCodec {
             Core::H265 | Core::H265NVENC | Core::H265QSV | Core::H265AMF => VideoCodec::H265,
             Core::CineForm => VideoCodec::CineForm,
             Core::Prores => VideoCodec::Prores,
+            Core::DnxHr => VideoCodec::DnxHr,
             _ => VideoCodec::H264,
         }
     }
@@ -233,3 +880,103 @@ impl From<lib_core::types::HwAccel> for HwAccel {
         }
     }
 }
+
+impl From<Preset> for lib_core::types::EncoderPreset {
+    fn from(value: Preset) -> Self {
+        use lib_core::types::EncoderPreset as Core;
+        match value {
+            Preset::Fastest => Core::Fastest,
+            Preset::Fast => Core::Fast,
+            Preset::Medium => Core::Medium,
+            Preset::Slow => Core::Slow,
+            Preset::Slowest => Core::Slowest,
+        }
+    }
+}
+
+impl From<lib_core::types::EncoderPreset> for Preset {
+    fn from(value: lib_core::types::EncoderPreset) -> Self {
+        use lib_core::types::EncoderPreset as Core;
+        match value {
+            Core::Fastest => Preset::Fastest,
+            Core::Fast => Preset::Fast,
+            Core::Medium => Preset::Medium,
+            Core::Slow => Preset::Slow,
+            Core::Slowest => Preset::Slowest,
+        }
+    }
+}
+
+impl From<Tune> for lib_core::types::EncoderTune {
+    fn from(value: Tune) -> Self {
+        use lib_core::types::EncoderTune as Core;
+        match value {
+            Tune::Film => Core::Film,
+            Tune::Animation => Core::Animation,
+            Tune::Grain => Core::Grain,
+            Tune::StillImage => Core::StillImage,
+            Tune::FastDecode => Core::FastDecode,
+            Tune::ZeroLatency => Core::ZeroLatency,
+        }
+    }
+}
+
+impl From<lib_core::types::EncoderTune> for Tune {
+    fn from(value: lib_core::types::EncoderTune) -> Self {
+        use lib_core::types::EncoderTune as Core;
+        match value {
+            Core::Film => Tune::Film,
+            Core::Animation => Tune::Animation,
+            Core::Grain => Tune::Grain,
+            Core::StillImage => Tune::StillImage,
+            Core::FastDecode => Tune::FastDecode,
+            Core::ZeroLatency => Tune::ZeroLatency,
+        }
+    }
+}
+
+impl From<AudioFormat> for lib_core::types::AudioFormat {
+    fn from(value: AudioFormat) -> Self {
+        use lib_core::types::AudioFormat as Core;
+        match value {
+            AudioFormat::Mp3 => Core::Mp3,
+            AudioFormat::M4a => Core::M4a,
+            AudioFormat::Opus => Core::Opus,
+            AudioFormat::Flac => Core::Flac,
+            AudioFormat::Wav => Core::Wav,
+        }
+    }
+}
+
+impl From<lib_core::types::AudioFormat> for AudioFormat {
+    fn from(value: lib_core::types::AudioFormat) -> Self {
+        use lib_core::types::AudioFormat as Core;
+        match value {
+            Core::Mp3 => AudioFormat::Mp3,
+            Core::M4a => AudioFormat::M4a,
+            Core::Opus => AudioFormat::Opus,
+            Core::Flac => AudioFormat::Flac,
+            Core::Wav => AudioFormat::Wav,
+        }
+    }
+}
+
+impl From<ImageFormat> for lib_core::types::ImageFormat {
+    fn from(value: ImageFormat) -> Self {
+        use lib_core::types::ImageFormat as Core;
+        match value {
+            ImageFormat::Jpeg => Core::Jpeg,
+            ImageFormat::Png => Core::Png,
+        }
+    }
+}
+
+impl From<lib_core::types::ImageFormat> for ImageFormat {
+    fn from(value: lib_core::types::ImageFormat) -> Self {
+        use lib_core::types::ImageFormat as Core;
+        match value {
+            Core::Jpeg => ImageFormat::Jpeg,
+            Core::Png => ImageFormat::Png,
+        }
+    }
+}
@@ -5,6 +5,7 @@ use std::path::PathBuf;
 pub struct Validation {
     audio_bitrate_error: Option<Error>,
     video_bitrate_error: Option<Error>,
+    rate_error: Option<Error>,
     input_dir_error: Option<Error>,
     output_dir_error: Option<Error>,
 }
@@ -48,6 +49,25 @@ impl Validation {
         }
     }
 
+    /// Bitrate or quality of the rate control. Unlike the bitrates 0 doesn't mean the source's
+    /// here, an empty field gives no rate control.
+    pub fn validate_rate(&mut self, value: Option<String>) -> Option<u32> {
+        self.rate_error = None;
+        let rate = value?;
+
+        if rate.is_empty() {
+            return None;
+        }
+
+        match rate.parse() {
+            Ok(val) => Some(val),
+            Err(_) => {
+                self.rate_error = Some(Error::RateParse);
+                None
+            }
+        }
+    }
+
     pub fn validate_input_dir(&mut self, value: Option<PathBuf>) -> Option<PathBuf> {
         if value.is_none() {
             self.input_dir_error = Some(Error::InputDirEmpty);
@@ -74,6 +94,10 @@ impl Validation {
         self.video_bitrate_error
     }
 
+    pub fn rate_error(&self) -> Option<Error> {
+        self.rate_error
+    }
+
     pub fn input_dir_error(&self) -> Option<Error> {
         self.input_dir_error
     }